@@ -0,0 +1,19 @@
+-- Stop order of each station on a route, counted from the source station (1-based).
+-- get_routes_between_stations and get_stop_options_between_stations depend on it.
+ALTER TABLE distance_map
+ADD COLUMN order_from_start INT;
+
+-- Backfill the order of existing routes from their distances
+UPDATE distance_map dm
+JOIN (
+    SELECT
+        route_id,
+        station_id,
+        ROW_NUMBER() OVER (PARTITION BY route_id ORDER BY distance) AS stop_order
+    FROM distance_map
+) o ON o.route_id = dm.route_id AND o.station_id = dm.station_id
+SET dm.order_from_start = o.stop_order;
+
+-- Distances are measured from the source station, so they can never be negative
+ALTER TABLE distance_map
+ADD CONSTRAINT chk_distance_map_distance CHECK (distance >= 0);
@@ -0,0 +1,82 @@
+-- Station codes, coordinates, state/zone and alternative names
+ALTER TABLE station
+ADD COLUMN station_code VARCHAR(8) UNIQUE,
+ADD COLUMN latitude DOUBLE,
+ADD COLUMN longitude DOUBLE,
+ADD COLUMN state VARCHAR(64),
+ADD COLUMN zone VARCHAR(8);
+
+ALTER TABLE station
+ADD CONSTRAINT chk_station_latitude CHECK (latitude BETWEEN -90 AND 90),
+ADD CONSTRAINT chk_station_longitude CHECK (longitude BETWEEN -180 AND 180);
+
+CREATE INDEX idx_station_geo ON station (latitude, longitude);
+
+-- Alias Table
+CREATE TABLE station_alias (
+    alias_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    station_id BIGINT NOT NULL,
+    alias_name VARCHAR(100) NOT NULL,
+    UNIQUE (station_id, alias_name)
+);
+
+ALTER TABLE station_alias
+ADD CONSTRAINT fk_station_alias_station
+FOREIGN KEY (station_id) REFERENCES station(station_id) ON DELETE CASCADE;
+
+-- Metadata for the demo stations
+UPDATE station s
+JOIN (
+    SELECT 'New Delhi' AS station_name, 'NDLS' AS station_code, 28.6430 AS latitude, 77.2194 AS longitude, 'Delhi' AS state, 'NR' AS zone
+    UNION ALL SELECT 'Mumbai Central', 'MMCT', 18.9690, 72.8195, 'Maharashtra', 'WR'
+    UNION ALL SELECT 'Howrah Junction', 'HWH', 22.5839, 88.3424, 'West Bengal', 'ER'
+    UNION ALL SELECT 'Chennai Central', 'MAS', 13.0827, 80.2757, 'Tamil Nadu', 'SR'
+    UNION ALL SELECT 'Bengaluru City', 'SBC', 12.9780, 77.5697, 'Karnataka', 'SWR'
+    UNION ALL SELECT 'Secunderabad Junction', 'SC', 17.4337, 78.5016, 'Telangana', 'SCR'
+    UNION ALL SELECT 'Ahmedabad Junction', 'ADI', 23.0258, 72.6012, 'Gujarat', 'WR'
+    UNION ALL SELECT 'Kanpur Central', 'CNB', 26.4539, 80.3516, 'Uttar Pradesh', 'NCR'
+    UNION ALL SELECT 'Patna Junction', 'PNBE', 25.6036, 85.1372, 'Bihar', 'ECR'
+    UNION ALL SELECT 'Lucknow NR', 'LKO', 26.8315, 80.9235, 'Uttar Pradesh', 'NR'
+    UNION ALL SELECT 'Bhopal Junction', 'BPL', 23.2661, 77.4126, 'Madhya Pradesh', 'WCR'
+    UNION ALL SELECT 'Nagpur Junction', 'NGP', 21.1520, 79.0880, 'Maharashtra', 'CR'
+    UNION ALL SELECT 'Jaipur Junction', 'JP', 26.9196, 75.7878, 'Rajasthan', 'NWR'
+    UNION ALL SELECT 'Visakhapatnam', 'VSKP', 17.7215, 83.2897, 'Andhra Pradesh', 'ECOR'
+    UNION ALL SELECT 'Guwahati', 'GHY', 26.1817, 91.7509, 'Assam', 'NFR'
+    UNION ALL SELECT 'Kolkata Shalimar', 'SHM', 22.5515, 88.3172, 'West Bengal', 'SER'
+    UNION ALL SELECT 'Pune Junction', 'PUNE', 18.5289, 73.8744, 'Maharashtra', 'CR'
+    UNION ALL SELECT 'Coimbatore Junction', 'CBE', 10.9968, 76.9661, 'Tamil Nadu', 'SR'
+    UNION ALL SELECT 'Thiruvananthapuram Central', 'TVC', 8.4875, 76.9525, 'Kerala', 'SR'
+    UNION ALL SELECT 'Madurai Junction', 'MDU', 9.9195, 78.1098, 'Tamil Nadu', 'SR'
+    UNION ALL SELECT 'Vijayawada Junction', 'BZA', 16.5180, 80.6199, 'Andhra Pradesh', 'SCR'
+    UNION ALL SELECT 'Varanasi Junction', 'BSB', 25.3264, 82.9868, 'Uttar Pradesh', 'NR'
+    UNION ALL SELECT 'Amritsar Junction', 'ASR', 31.6328, 74.8683, 'Punjab', 'NR'
+    UNION ALL SELECT 'Jodhpur Junction', 'JU', 26.2843, 73.0244, 'Rajasthan', 'NWR'
+    UNION ALL SELECT 'Ranchi Junction', 'RNC', 23.3486, 85.3375, 'Jharkhand', 'SER'
+    UNION ALL SELECT 'Raipur Junction', 'R', 21.2593, 81.6315, 'Chhattisgarh', 'SECR'
+    UNION ALL SELECT 'Bhubaneswar', 'BBS', 20.2656, 85.8437, 'Odisha', 'ECOR'
+    UNION ALL SELECT 'Tirupati', 'TPTY', 13.6288, 79.4192, 'Andhra Pradesh', 'SCR'
+    UNION ALL SELECT 'Agra Cantt', 'AGC', 27.1589, 77.9894, 'Uttar Pradesh', 'NCR'
+    UNION ALL SELECT 'Gaya Junction', 'GAYA', 24.8030, 84.9998, 'Bihar', 'ECR'
+) m ON m.station_name = s.station_name
+SET s.station_code = m.station_code,
+    s.latitude = m.latitude,
+    s.longitude = m.longitude,
+    s.state = m.state,
+    s.zone = m.zone;
+
+INSERT INTO station_alias (station_id, alias_name)
+SELECT s.station_id, a.alias_name
+FROM station s
+JOIN (
+    SELECT 'Mumbai Central' AS station_name, 'Bombay Central' AS alias_name
+    UNION ALL SELECT 'Chennai Central', 'Madras Central'
+    UNION ALL SELECT 'Chennai Central', 'MGR Chennai Central'
+    UNION ALL SELECT 'Bengaluru City', 'Bangalore City'
+    UNION ALL SELECT 'Bengaluru City', 'KSR Bengaluru'
+    UNION ALL SELECT 'Thiruvananthapuram Central', 'Trivandrum Central'
+    UNION ALL SELECT 'Visakhapatnam', 'Vizag'
+    UNION ALL SELECT 'Pune Junction', 'Poona'
+    UNION ALL SELECT 'Lucknow NR', 'Lucknow Charbagh'
+    UNION ALL SELECT 'Varanasi Junction', 'Banaras'
+    UNION ALL SELECT 'Howrah Junction', 'Kolkata Howrah'
+) a ON a.station_name = s.station_name;
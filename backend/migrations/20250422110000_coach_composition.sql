@@ -0,0 +1,12 @@
+-- Position of a coach within its train's composition (1 = next to the engine).
+-- Detached coaches keep their seats for history but have no train or position.
+ALTER TABLE coach
+ADD COLUMN position INT;
+
+UPDATE coach c
+JOIN (
+    SELECT coach_id, ROW_NUMBER() OVER (PARTITION BY train_id ORDER BY coach_id) AS coach_position
+    FROM coach
+) o ON o.coach_id = c.coach_id
+SET c.position = o.coach_position
+WHERE c.train_id IS NOT NULL;
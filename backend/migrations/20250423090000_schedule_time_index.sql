@@ -0,0 +1,3 @@
+-- Journey search looks up a boarding station within a time range; this lets it
+-- use an index instead of evaluating DATE(sched_toa) on every schedule row.
+CREATE INDEX idx_schedule_station_toa ON schedule (station_id, sched_toa);
@@ -0,0 +1,23 @@
+-- Journey lifecycle. Journeys are never deleted once booked; cancelling one keeps
+-- its rows and cancels/refunds the bookings instead.
+ALTER TABLE journey
+ADD COLUMN journey_status ENUM('SCHEDULED', 'DEPARTED', 'COMPLETED', 'CANCELLED') NOT NULL DEFAULT 'SCHEDULED',
+ADD COLUMN cancel_reason VARCHAR(255),
+ADD COLUMN cancelled_at TIMESTAMP NULL;
+
+UPDATE journey SET journey_status = 'COMPLETED' WHERE end_time < NOW();
+UPDATE journey SET journey_status = 'DEPARTED' WHERE start_time <= NOW() AND end_time >= NOW();
+
+-- Messages for passengers, written in the same transaction as the change they
+-- describe and delivered later.
+CREATE TABLE notification_outbox (
+    notification_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    email VARCHAR(255),
+    pnr BIGINT,
+    event_type VARCHAR(50) NOT NULL,
+    payload JSON NOT NULL,
+    status ENUM('PENDING', 'SENT', 'FAILED') NOT NULL DEFAULT 'PENDING',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_notification_status ON notification_outbox (status, created_at);
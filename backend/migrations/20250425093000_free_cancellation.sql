@@ -0,0 +1,4 @@
+-- Set when the railway changes a booked journey's timings: the passenger may then
+-- cancel for a full refund.
+ALTER TABLE booking
+ADD COLUMN free_cancellation BOOLEAN NOT NULL DEFAULT FALSE;
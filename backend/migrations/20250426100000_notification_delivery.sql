@@ -0,0 +1,50 @@
+-- Delivery state for the notification outbox and per-user channel preferences.
+ALTER TABLE notification_outbox
+ADD COLUMN attempts INT NOT NULL DEFAULT 0,
+ADD COLUMN next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+ADD COLUMN delivered_channels VARCHAR(100) NOT NULL DEFAULT '', -- Comma separated, so retries skip them
+ADD COLUMN last_error VARCHAR(500),
+ADD COLUMN sent_at TIMESTAMP NULL;
+
+DROP INDEX idx_notification_status ON notification_outbox;
+CREATE INDEX idx_notification_due ON notification_outbox (status, next_attempt_at);
+
+-- Users without a row get email only
+CREATE TABLE notification_preference (
+    email VARCHAR(255) PRIMARY KEY,
+    email_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    webhook_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    webhook_url VARCHAR(500),
+    FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE
+);
+
+DROP TRIGGER IF EXISTS notify_booking_confirmed;
+DROP TRIGGER IF EXISTS notify_reservation_updated;
+
+-- Confirmation happens inside after_payment_complete, so it is queued here
+CREATE TRIGGER notify_booking_confirmed
+AFTER UPDATE ON booking
+FOR EACH ROW
+BEGIN
+    IF NEW.booking_status = 'CONFIRMED' AND NOT (OLD.booking_status <=> 'CONFIRMED') THEN
+        INSERT INTO notification_outbox (email, pnr, event_type, payload)
+        SELECT p.email, NEW.pnr, 'BOOKING_CONFIRMED',
+               JSON_OBJECT('booking_id', NEW.booking_id, 'journey_id', NEW.journey_id, 'txn_id', NEW.txn_id)
+        FROM passenger p
+        WHERE p.pnr = NEW.pnr;
+    END IF;
+END;
+
+-- Waitlist/RAC movement and seat changes
+CREATE TRIGGER notify_reservation_updated
+AFTER UPDATE ON reservation_status
+FOR EACH ROW
+BEGIN
+    IF NOT (OLD.reservation_status <=> NEW.reservation_status) OR NOT (OLD.seat_id <=> NEW.seat_id) THEN
+        INSERT INTO notification_outbox (email, pnr, event_type, payload)
+        SELECT p.email, NEW.pnr, 'RESERVATION_UPDATED',
+               JSON_OBJECT('old_status', OLD.reservation_status, 'new_status', NEW.reservation_status, 'seat_id', NEW.seat_id)
+        FROM passenger p
+        WHERE p.pnr = NEW.pnr;
+    END IF;
+END;
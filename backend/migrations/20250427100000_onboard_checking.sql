@@ -0,0 +1,25 @@
+-- Ticket examiners (TTE) work on board; their actions are recorded as running
+-- events so the chart reflects who actually travelled.
+ALTER TABLE users
+MODIFY role ENUM('ADMIN', 'USER', 'TTE') DEFAULT 'USER';
+
+ALTER TABLE booking
+ADD COLUMN onboard_status ENUM('PENDING', 'BOARDED', 'NO_SHOW') NOT NULL DEFAULT 'PENDING';
+
+CREATE TABLE running_event (
+    event_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    journey_id BIGINT NOT NULL,
+    station_id BIGINT,             -- Where the train was, if the examiner said
+    event_type ENUM('VERIFIED', 'BOARDED', 'NO_SHOW', 'BERTH_REALLOCATED') NOT NULL,
+    pnr BIGINT,
+    booking_id BIGINT,
+    seat_id BIGINT,                -- Berth after the event
+    previous_seat_id BIGINT,       -- Berth before a reallocation
+    examiner_email VARCHAR(255) NOT NULL,
+    recorded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (journey_id) REFERENCES journey(journey_id),
+    FOREIGN KEY (booking_id) REFERENCES booking(booking_id),
+    FOREIGN KEY (examiner_email) REFERENCES users(email)
+);
+
+CREATE INDEX idx_running_event_journey ON running_event (journey_id, recorded_at);
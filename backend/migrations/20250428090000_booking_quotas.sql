@@ -0,0 +1,213 @@
+-- Booking quotas: GN general, LD ladies, SS senior citizen, TQ tatkal, EQ emergency.
+-- A seat's quota applies on every run of its coach; journey_seat_quota overrides
+-- it for a single journey. Once the chart is prepared every seat counts as GN.
+ALTER TABLE seat
+ADD COLUMN quota ENUM('GN', 'LD', 'SS', 'TQ', 'EQ') NOT NULL DEFAULT 'GN';
+
+CREATE TABLE journey_seat_quota (
+    journey_id BIGINT NOT NULL,
+    seat_id BIGINT NOT NULL,
+    quota ENUM('GN', 'LD', 'SS', 'TQ', 'EQ') NOT NULL,
+    PRIMARY KEY (journey_id, seat_id),
+    FOREIGN KEY (journey_id) REFERENCES journey(journey_id),
+    FOREIGN KEY (seat_id) REFERENCES seat(seat_id)
+);
+
+ALTER TABLE journey
+ADD COLUMN chart_prepared_at TIMESTAMP NULL;
+
+ALTER TABLE reservation_status
+ADD COLUMN quota ENUM('GN', 'LD', 'SS', 'TQ', 'EQ') NOT NULL DEFAULT 'GN';
+
+DROP FUNCTION IF EXISTS seat_quota;
+DROP PROCEDURE IF EXISTS get_available_cnf_seats;
+DROP PROCEDURE IF EXISTS get_available_rac_seats;
+DROP PROCEDURE IF EXISTS create_group_booking;
+
+-- Quota a seat is sold under on a journey
+CREATE FUNCTION seat_quota(p_journey_id BIGINT, p_seat_id BIGINT)
+RETURNS VARCHAR(2)
+READS SQL DATA
+BEGIN
+    IF (SELECT chart_prepared_at FROM journey WHERE journey_id = p_journey_id) IS NOT NULL THEN
+        RETURN 'GN';
+    END IF;
+
+    RETURN COALESCE(
+        (SELECT quota FROM journey_seat_quota WHERE journey_id = p_journey_id AND seat_id = p_seat_id),
+        (SELECT quota FROM seat WHERE seat_id = p_seat_id),
+        'GN'
+    );
+END;
+
+-- This SQL script creates a stored procedure for getting available CNF seats for a given train, journey and quota.
+CREATE PROCEDURE get_available_cnf_seats (
+    IN p_train_id BIGINT,
+    IN p_journey_id BIGINT,
+    IN p_reservation_category VARCHAR(20),
+    IN p_quota VARCHAR(2),
+    IN p_limit INT
+)
+BEGIN
+    INSERT INTO temp_available_seats (seat_id, seat_category)
+    SELECT 
+        s.seat_id, 
+        'CNF' AS seat_category
+    FROM 
+        seat s
+    JOIN 
+        coach c ON s.coach_id = c.coach_id
+    LEFT JOIN 
+        booking b ON s.seat_id = b.seat_id AND b.journey_id = p_journey_id 
+            AND b.booking_status IN ('CONFIRMED', 'PENDING')
+    WHERE 
+        c.train_id = p_train_id
+        AND c.coach_type = p_reservation_category
+        AND s.seat_category = 'CNF'
+        AND seat_quota(p_journey_id, s.seat_id) = p_quota
+        AND b.seat_id IS NULL  -- Means this seat is NOT already booked for this journey
+    LIMIT 
+        p_limit;
+END;
+
+-- This SQL script creates a stored procedure for getting available RAC seats for a given train, journey and quota.
+CREATE PROCEDURE get_available_rac_seats (
+    IN p_train_id BIGINT,
+    IN p_journey_id BIGINT,
+    IN p_reservation_category VARCHAR(20),
+    IN p_quota VARCHAR(2),
+    IN p_limit INT
+)
+BEGIN
+    INSERT INTO temp_available_seats (seat_id, seat_category)
+    SELECT 
+        s.seat_id, 
+        'RAC' AS seat_category
+    FROM 
+        seat s
+    JOIN 
+        coach c ON s.coach_id = c.coach_id
+    LEFT JOIN 
+        booking b ON s.seat_id = b.seat_id AND b.journey_id = p_journey_id 
+            AND b.booking_status IN ('CONFIRMED', 'PENDING')
+    WHERE 
+        c.train_id = p_train_id
+        AND c.coach_type = p_reservation_category
+        AND s.seat_category = 'RAC'
+        AND seat_quota(p_journey_id, s.seat_id) = p_quota
+        AND b.seat_id IS NULL  -- Means this seat is NOT already booked for this journey
+    LIMIT 
+        p_limit;
+END;
+
+-- This SQL script creates a stored procedure for creating a group booking under a quota.
+CREATE PROCEDURE create_group_booking(
+    IN p_group_size INT,
+    IN p_passenger_data JSON,
+    IN p_journey_id BIGINT,
+    IN p_train_id BIGINT,
+    IN p_start_station_id BIGINT,
+    IN p_end_station_id BIGINT,
+    IN p_mode VARCHAR(20),
+    IN p_txn_id BIGINT,
+    IN p_email VARCHAR(255),
+    IN p_reservation_category ENUM('SL', 'AC3', 'AC2', 'AC1', 'CC', 'FC', '2S'),
+    IN p_quota ENUM('GN', 'LD', 'SS', 'TQ', 'EQ')
+)
+BEGIN
+    DECLARE i INT DEFAULT 0;
+    DECLARE p_pnr BIGINT;
+    DECLARE p_passenger_name VARCHAR(100);
+    DECLARE p_passenger_age INT;
+    DECLARE p_passenger_sex CHAR(1);
+    DECLARE p_passenger_disability BOOLEAN;
+    DECLARE p_seat_id BIGINT;
+    DECLARE p_individual_amount FLOAT;
+    DECLARE p_seat_category ENUM('CNF', 'RAC');
+    DECLARE temp_total_seats INT;
+    DECLARE p_total_price FLOAT DEFAULT 0;
+
+    -- Drop if exists and create temporary table for available seats
+    DROP TEMPORARY TABLE IF EXISTS temp_available_seats;
+    CREATE TEMPORARY TABLE temp_available_seats (
+        seat_id BIGINT,
+        seat_category ENUM('CNF', 'RAC')
+    );
+
+    -- Fetch CNF seats first
+    CALL get_available_cnf_seats(p_train_id, p_journey_id, p_reservation_category, p_quota, p_group_size);
+    SET temp_total_seats = (SELECT COUNT(*) FROM temp_available_seats);
+
+    -- If not enough CNF, fetch RAC
+    IF temp_total_seats < p_group_size THEN
+        CALL get_available_rac_seats(p_train_id, p_journey_id, p_reservation_category, p_quota, (p_group_size - temp_total_seats));
+    END IF;
+
+    -- Process passengers one by one
+    WHILE i < p_group_size DO
+        -- Extract passenger details from JSON
+        SET p_passenger_name = JSON_UNQUOTE(JSON_EXTRACT(p_passenger_data, CONCAT('$[', i, '].name')));
+        SET p_passenger_age = JSON_UNQUOTE(JSON_EXTRACT(p_passenger_data, CONCAT('$[', i, '].age')));
+        SET p_passenger_sex = JSON_UNQUOTE(JSON_EXTRACT(p_passenger_data, CONCAT('$[', i, '].sex')));
+        SET p_passenger_disability = JSON_UNQUOTE(JSON_EXTRACT(p_passenger_data, CONCAT('$[', i, '].disability')));
+        SET p_individual_amount = JSON_UNQUOTE(JSON_EXTRACT(p_passenger_data, CONCAT('$[', i, '].fare')));
+
+        -- Update total price
+        SET p_total_price = p_total_price + p_individual_amount;
+
+        -- Insert passenger and get pnr
+        INSERT INTO passenger (pass_name, age, sex, disability, email)
+        VALUES (p_passenger_name, p_passenger_age, p_passenger_sex, p_passenger_disability, p_email);
+        SET p_pnr = LAST_INSERT_ID();
+
+        -- Default values
+        SET p_seat_id = NULL;
+        SET p_seat_category = NULL;
+
+        -- Check for seat availability and assign
+        IF (SELECT COUNT(*) FROM temp_available_seats) > 0 THEN
+            SELECT seat_id, seat_category
+            INTO p_seat_id, p_seat_category
+            FROM temp_available_seats
+            LIMIT 1;
+
+            -- Delete assigned seat
+            DELETE FROM temp_available_seats WHERE seat_id = p_seat_id;
+
+            -- Booking with seat
+            INSERT INTO booking (
+                booking_time, booking_status, pnr, journey_id, seat_id,
+                start_station_id, end_station_id, amount, txn_id
+            ) VALUES (
+                NOW(), 'PENDING', p_pnr, p_journey_id, p_seat_id,
+                p_start_station_id, p_end_station_id, p_individual_amount, p_txn_id
+            );
+
+            INSERT INTO reservation_status (
+                pnr, seat_id, reservation_status, booking_time, reservation_category, quota
+            ) VALUES (
+                p_pnr, p_seat_id, p_seat_category, NOW(), p_reservation_category, p_quota
+            );
+        ELSE
+            -- Booking as WL
+            INSERT INTO booking (
+                booking_time, booking_status, pnr, journey_id, seat_id,
+                start_station_id, end_station_id, amount, txn_id
+            ) VALUES (
+                NOW(), 'PENDING', p_pnr, p_journey_id, NULL,
+                p_start_station_id, p_end_station_id, p_individual_amount, p_txn_id
+            );
+
+            INSERT INTO reservation_status (
+                pnr, seat_id, reservation_status, booking_time, reservation_category, quota
+            ) VALUES (
+                p_pnr, NULL, 'WL', NOW(), p_reservation_category, p_quota
+            );
+        END IF;
+
+        SET i = i + 1;
+    END WHILE;
+
+    -- Clean up
+    DROP TEMPORARY TABLE IF EXISTS temp_available_seats;
+END;
@@ -0,0 +1,19 @@
+-- Tatkal premium per class: a share of the base fare, capped both ways, and the
+-- local time its booking window opens on the day before departure from origin.
+CREATE TABLE tatkal_fare (
+    reservation_category ENUM('SL', 'AC3', 'AC2', 'AC1', 'CC', 'FC', '2S') PRIMARY KEY,
+    premium_rate FLOAT NOT NULL,   -- Fraction of the base fare, e.g. 0.3
+    min_premium FLOAT NOT NULL,
+    max_premium FLOAT NOT NULL,
+    opens_at TIME NOT NULL,        -- IST
+    CHECK (min_premium <= max_premium)
+);
+
+INSERT INTO tatkal_fare (reservation_category, premium_rate, min_premium, max_premium, opens_at) VALUES
+    ('2S',  0.1, 10,  15,  '11:00:00'),
+    ('SL',  0.3, 100, 200, '11:00:00'),
+    ('CC',  0.3, 125, 225, '10:00:00'),
+    ('AC3', 0.3, 300, 400, '10:00:00'),
+    ('AC2', 0.3, 400, 500, '10:00:00'),
+    ('FC',  0.3, 400, 500, '10:00:00'),
+    ('AC1', 0.3, 400, 500, '10:00:00');
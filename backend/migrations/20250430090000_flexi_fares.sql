@@ -0,0 +1,33 @@
+-- Flexi fares: on listed train types the fare steps up as the share of CNF berths
+-- sold for a journey and class crosses each threshold. A NULL class applies to
+-- every class of the train type; a class-specific step set replaces it.
+CREATE TABLE flexi_fare_step (
+    step_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    train_type ENUM('EX', 'ML', 'SF', 'VB', 'MM', 'IN') NOT NULL,
+    reservation_category ENUM('SL', 'AC3', 'AC2', 'AC1', 'CC', 'FC', '2S'),
+    sold_percent INT NOT NULL,     -- Step applies once this share of CNF berths is sold
+    multiplier FLOAT NOT NULL,     -- Applied to the coach's base fare
+    CHECK (sold_percent BETWEEN 0 AND 100),
+    CHECK (multiplier > 0)
+);
+
+INSERT INTO flexi_fare_step (train_type, reservation_category, sold_percent, multiplier) VALUES
+    ('SF', NULL, 0, 1.0), ('SF', NULL, 10, 1.1), ('SF', NULL, 20, 1.2),
+    ('SF', NULL, 30, 1.3), ('SF', NULL, 40, 1.4), ('SF', NULL, 50, 1.5),
+    ('VB', NULL, 0, 1.0), ('VB', NULL, 10, 1.1), ('VB', NULL, 20, 1.2),
+    ('VB', NULL, 30, 1.3), ('VB', NULL, 40, 1.4), ('VB', NULL, 50, 1.5);
+
+-- A fare shown to a passenger, held for the checkout and used by at most one booking
+CREATE TABLE fare_quote (
+    quote_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    journey_id BIGINT NOT NULL,
+    reservation_category ENUM('SL', 'AC3', 'AC2', 'AC1', 'CC', 'FC', '2S') NOT NULL,
+    base_fare FLOAT NOT NULL,
+    multiplier FLOAT NOT NULL,
+    fare FLOAT NOT NULL,           -- Per passenger
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    redeemed_at TIMESTAMP NULL,
+    txn_id BIGINT,
+    FOREIGN KEY (journey_id) REFERENCES journey(journey_id)
+);
@@ -0,0 +1,45 @@
+-- Admin-managed promo codes. NULL applicability lists mean "any"; NULL limits
+-- mean unlimited. Discounts are recorded on the booking and the payment so
+-- refunds and revenue figures use what was actually paid.
+CREATE TABLE promo_code (
+    promo_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    code VARCHAR(32) NOT NULL UNIQUE,
+    description VARCHAR(255),
+    discount_type ENUM('PERCENT', 'FLAT') NOT NULL,
+    discount_value FLOAT NOT NULL,
+    max_discount FLOAT,            -- Cap for percentage discounts
+    min_amount FLOAT,              -- Smallest booking total the code applies to
+    valid_from TIMESTAMP NOT NULL,
+    valid_until TIMESTAMP NOT NULL,
+    train_types JSON,              -- e.g. ["SF", "VB"]
+    classes JSON,                  -- e.g. ["AC3", "AC2"]
+    route_ids JSON,                -- e.g. [1, 4]
+    usage_limit INT,               -- Across all users
+    per_user_limit INT,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CHECK (discount_value > 0),
+    CHECK (valid_from < valid_until)
+);
+
+CREATE TABLE promo_redemption (
+    redemption_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    promo_id BIGINT NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    txn_id BIGINT NOT NULL,
+    discount FLOAT NOT NULL,
+    redeemed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (promo_id) REFERENCES promo_code(promo_id),
+    FOREIGN KEY (txn_id) REFERENCES payment_transaction(txn_id)
+);
+
+CREATE INDEX idx_promo_redemption_user ON promo_redemption (promo_id, email);
+
+-- booking.amount stays the amount paid for the passenger; discount is what was taken off it
+ALTER TABLE booking
+ADD COLUMN discount FLOAT NOT NULL DEFAULT 0;
+
+ALTER TABLE payment_transaction
+ADD COLUMN discount FLOAT NOT NULL DEFAULT 0,
+ADD COLUMN promo_id BIGINT NULL,
+ADD FOREIGN KEY (promo_id) REFERENCES promo_code(promo_id);
@@ -0,0 +1,23 @@
+-- Travellers a user books for repeatedly. Age is not stored: it is worked out
+-- from date_of_birth for each journey.
+CREATE TABLE traveller (
+    traveller_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    email VARCHAR(255) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    date_of_birth DATE NOT NULL,
+    sex CHAR(1) NOT NULL,
+    disability BOOLEAN NOT NULL DEFAULT FALSE,
+    id_type ENUM('AADHAAR', 'PASSPORT', 'PAN', 'VOTER_ID', 'DRIVING_LICENCE'),
+    id_number VARCHAR(32),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE,
+    CHECK (sex IN ('M', 'F', 'O'))
+);
+
+CREATE INDEX idx_traveller_email ON traveller (email);
+
+-- The saved traveller a PNR was booked for, if any
+ALTER TABLE passenger
+ADD COLUMN traveller_id BIGINT NULL,
+ADD FOREIGN KEY (traveller_id) REFERENCES traveller(traveller_id) ON DELETE SET NULL;
@@ -0,0 +1,29 @@
+-- Account security: passwords are stored as Argon2 hashes (plaintext rows left
+-- from before are upgraded on the next successful login), logins issue bearer
+-- sessions, and email verification / password reset use single-use tokens.
+-- Only SHA-256 digests of session and account tokens are stored.
+ALTER TABLE users
+ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN password_changed_at TIMESTAMP NULL;
+
+CREATE TABLE user_session (
+    token_hash CHAR(64) PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX idx_user_session_email ON user_session (email);
+
+CREATE TABLE account_token (
+    token_hash CHAR(64) PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    purpose ENUM('VERIFY_EMAIL', 'PASSWORD_RESET') NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP NULL,
+    FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX idx_account_token_email ON account_token (email, purpose);
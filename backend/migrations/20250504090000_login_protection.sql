@@ -0,0 +1,25 @@
+-- Failed sign-in tracking. One row per account and per client IP; a row is
+-- reset once its last failure is older than the tracking window, and locked
+-- rows are cleared by an admin or by a password reset.
+CREATE TABLE login_failure (
+    scope ENUM('ACCOUNT', 'IP') NOT NULL,
+    subject VARCHAR(255) NOT NULL,   -- Email for ACCOUNT, address for IP
+    failures INT NOT NULL DEFAULT 0,
+    first_failure_at TIMESTAMP NOT NULL,
+    last_failure_at TIMESTAMP NOT NULL,
+    locked_until TIMESTAMP NULL,
+    PRIMARY KEY (scope, subject)
+);
+
+-- Audit trail of suspicious sign-in activity and admin interventions
+CREATE TABLE security_event (
+    event_id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    event_type VARCHAR(40) NOT NULL,  -- ACCOUNT_LOCKED, IP_BLOCKED, LOGIN_BLOCKED, LOGIN_AFTER_FAILURES, ACCOUNT_UNLOCKED, IP_UNLOCKED
+    email VARCHAR(255),
+    client_ip VARCHAR(64),
+    details JSON,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_security_event_email ON security_event (email, created_at);
+CREATE INDEX idx_security_event_created ON security_event (created_at);
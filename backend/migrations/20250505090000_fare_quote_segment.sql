@@ -0,0 +1,4 @@
+-- A quote prices the segment being booked, so it is only valid for that segment
+ALTER TABLE fare_quote
+ADD COLUMN start_station_id BIGINT NULL AFTER reservation_category,
+ADD COLUMN end_station_id BIGINT NULL AFTER start_station_id;
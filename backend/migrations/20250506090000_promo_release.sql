@@ -0,0 +1,4 @@
+-- A redemption is given back when its payment fails or every booking on it is
+-- cancelled. Released rows stay for the audit trail but no longer count against limits.
+ALTER TABLE promo_redemption
+ADD COLUMN released_at TIMESTAMP NULL;
@@ -0,0 +1,12 @@
+-- Completing a payment confirms only the bookings still waiting for it, so a
+-- booking cancelled in the meantime (e.g. by a journey cancellation) stays cancelled.
+DROP TRIGGER IF EXISTS after_payment_complete;
+
+CREATE TRIGGER after_payment_complete
+AFTER UPDATE ON payment_transaction
+FOR EACH ROW
+BEGIN
+    IF NEW.txn_status = 'COMPLETE' THEN
+        UPDATE booking SET booking_status = 'CONFIRMED' WHERE txn_id = NEW.txn_id AND booking_status = 'PENDING';
+    END IF;
+END;
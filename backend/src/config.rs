@@ -1,20 +1,484 @@
+// config.rs
+//
+// Settings are layered: built-in defaults, then the TOML file named by CONFIG_FILE
+// (config.toml when unset; skipped if it does not exist), then environment
+// variables. Everything is validated once at startup and all problems are
+// reported together.
+
 use std::env;
+use std::fmt::{self, Debug, Display};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::logging::{self, REDACTED};
 
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
-    pub database_url: String,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub logging: LoggingConfig,
+    pub tickets: TicketConfig,
+    pub notifications: NotificationConfig,
+    pub rate_limits: RateLimitConfig,
+    pub business: BusinessConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    pub workers: Option<usize>, // Defaults to the number of CPUs
+    pub tls: Option<TlsConfig>,
+    pub metrics_host: String, // GET /metrics is served on its own listener, not the public one
+    pub metrics_port: u16,
+}
+
+// PEM files; when set the server speaks HTTPS only
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub slow_query_ms: u64, // Statements slower than this are logged as warnings
+}
+
+// Exact origins such as "https://rail.example.com". An origin without a port
+// also matches that host on any port; "*" allows every origin.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub max_age_secs: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: String,          // trace, debug, info, warn, error
+    pub format: String,         // full, compact, pretty, json
+    pub filter: Option<String>, // Extra directives such as "sqlx::query=debug"
+    pub slow_request_ms: u64,   // Requests slower than this are logged as warnings
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TicketConfig {
+    pub signing_key: Option<String>, // base64 Ed25519 seed for e-ticket QR codes
+}
+
+// Delivery settings for the notification dispatcher. Channels without settings are skipped.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_tls: bool, // false for a local mail catcher such as MailHog or Mailpit
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_from: String,
+    pub log_file: Option<String>, // Every notification is also appended here as a JSON line
+    pub mail_log_file: Option<String>, // Account mail goes here when SMTP is not configured; otherwise only its recipient is logged
+    pub poll_interval_secs: u64,
+    pub max_attempts: i32,
+}
+
+// Per-client request limits. A request counts against the first scope whose
+// prefix matches its path, else the default. A limit of 0 disables limiting.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub trust_proxy: bool, // Take the client address from X-Forwarded-For / Forwarded
+    pub default_per_minute: u32,
+    pub scopes: Vec<RateLimitScope>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitScope {
+    pub name: String,
+    pub prefixes: Vec<String>,
+    pub per_minute: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BusinessConfig {
+    pub chart_lead_hours: i64,   // Charts are prepared this long before departure
+    pub fare_quote_minutes: i64, // How long a flexi fare quote stays locked
+    pub session_days: i64,       // Lifetime of a sign-in session
+    pub refund_percent: i64,     // Share of the fare returned on a normal cancellation
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("invalid config file {path}: {source}")]
+    Parse { path: String, source: toml::de::Error },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: None,
+            tls: None,
+            metrics_host: "127.0.0.1".to_string(),
+            metrics_port: 9091,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            slow_query_ms: 500,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["http://localhost".to_string(), "http://127.0.0.1".to_string()],
+            max_age_secs: 3600,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: "full".to_string(),
+            filter: None,
+            slow_request_ms: 2000,
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_port: 1025,
+            smtp_tls: false,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_from: "Railway Reservations <no-reply@railway.local>".to_string(),
+            log_file: None,
+            mail_log_file: None,
+            poll_interval_secs: 10,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let scope = |name: &str, prefixes: &[&str], per_minute: u32| RateLimitScope {
+            name: name.to_string(),
+            prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            per_minute,
+        };
+
+        Self {
+            trust_proxy: false,
+            default_per_minute: 300,
+            scopes: vec![
+                scope("auth", &["/api/auth"], 10),
+                scope("booking", &["/api/booking", "/api/transaction", "/api/promos/apply"], 30),
+                scope(
+                    "search",
+                    &["/api/journeys", "/api/trains", "/api/station", "/api/route", "/api/schedules", "/api/seats", "/api/coaches"],
+                    120,
+                ),
+            ],
+        }
+    }
+}
+
+impl Default for BusinessConfig {
+    fn default() -> Self {
+        Self { chart_lead_hours: 4, fare_quote_minutes: 15, session_days: 7, refund_percent: 80 }
+    }
+}
+
+// Secrets are masked so the config can be logged
+
+impl Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &logging::redact_url(&self.url))
+            .field("max_connections", &self.max_connections)
+            .field("min_connections", &self.min_connections)
+            .field("acquire_timeout_secs", &self.acquire_timeout_secs)
+            .field("idle_timeout_secs", &self.idle_timeout_secs)
+            .field("slow_query_ms", &self.slow_query_ms)
+            .finish()
+    }
+}
+
+impl Debug for TicketConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TicketConfig")
+            .field("signing_key", &self.signing_key.as_ref().map(|_| REDACTED))
+            .finish()
+    }
+}
+
+impl Debug for NotificationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotificationConfig")
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_tls", &self.smtp_tls)
+            .field("smtp_username", &self.smtp_username)
+            .field("smtp_password", &self.smtp_password.as_ref().map(|_| REDACTED))
+            .field("smtp_from", &self.smtp_from)
+            .field("log_file", &self.log_file)
+            .field("mail_log_file", &self.mail_log_file)
+            .field("poll_interval_secs", &self.poll_interval_secs)
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+// Replaces `target` with the parsed value of `var`, if set
+fn env_value<T>(target: &mut T, var: &str, errors: &mut Vec<String>)
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(var) {
+        match value.trim().parse() {
+            Ok(parsed) => *target = parsed,
+            Err(e) => errors.push(format!("{}: cannot parse {:?}: {}", var, value, e)),
+        }
+    }
+}
+
+// Like env_value for optional settings; an empty value unsets them
+fn env_option<T>(target: &mut Option<T>, var: &str, errors: &mut Vec<String>)
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(var) {
+        if value.trim().is_empty() {
+            *target = None;
+        } else {
+            match value.trim().parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(e) => errors.push(format!("{}: cannot parse {:?}: {}", var, value, e)),
+            }
+        }
+    }
 }
 
 impl Config {
-    pub fn from_env() -> Result<Self, std::io::Error> {
-        Ok(Self {
-            database_url: env::var("DATABASE_URL").unwrap(),
-            host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .unwrap(),
-        })
-    }
-}
\ No newline at end of file
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match env::var("CONFIG_FILE") {
+            Ok(path) => (path, true),
+            Err(_) => ("config.toml".to_string(), false),
+        };
+
+        let mut config = if required || Path::new(&path).exists() {
+            let text = std::fs::read_to_string(&path).map_err(|source| ConfigError::Read { path: path.clone(), source })?;
+            toml::from_str::<Config>(&text).map_err(|source| ConfigError::Parse { path: path.clone(), source })?
+        } else {
+            Config::default()
+        };
+
+        let mut errors = Vec::new();
+        config.apply_env(&mut errors);
+        config.validate(&mut errors);
+
+        if errors.is_empty() { Ok(config) } else { Err(ConfigError::Invalid(errors)) }
+    }
+
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        let server = &mut self.server;
+        env_value(&mut server.host, "HOST", errors);
+        env_value(&mut server.port, "PORT", errors);
+        env_option(&mut server.workers, "WORKERS", errors);
+        env_value(&mut server.metrics_host, "METRICS_HOST", errors);
+        env_value(&mut server.metrics_port, "METRICS_PORT", errors);
+        match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+            (Ok(cert_path), Ok(key_path)) => server.tls = Some(TlsConfig { cert_path, key_path }),
+            (Err(_), Err(_)) => {}
+            _ => errors.push("TLS_CERT_PATH and TLS_KEY_PATH must be set together".to_string()),
+        }
+
+        let database = &mut self.database;
+        env_value(&mut database.url, "DATABASE_URL", errors);
+        env_value(&mut database.max_connections, "DB_MAX_CONNECTIONS", errors);
+        env_value(&mut database.min_connections, "DB_MIN_CONNECTIONS", errors);
+        env_value(&mut database.acquire_timeout_secs, "DB_ACQUIRE_TIMEOUT_SECS", errors);
+        env_value(&mut database.idle_timeout_secs, "DB_IDLE_TIMEOUT_SECS", errors);
+        env_value(&mut database.slow_query_ms, "DB_SLOW_QUERY_MS", errors);
+
+        if let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        }
+
+        env_value(&mut self.logging.level, "LOG_LEVEL", errors);
+        env_value(&mut self.logging.format, "LOG_FORMAT", errors);
+        env_option(&mut self.logging.filter, "RUST_LOG", errors);
+        env_value(&mut self.logging.slow_request_ms, "SLOW_REQUEST_MS", errors);
+
+        env_option(&mut self.tickets.signing_key, "TICKET_SIGNING_KEY", errors);
+
+        let notifications = &mut self.notifications;
+        env_option(&mut notifications.smtp_host, "SMTP_HOST", errors);
+        env_value(&mut notifications.smtp_port, "SMTP_PORT", errors);
+        env_value(&mut notifications.smtp_tls, "SMTP_TLS", errors);
+        env_option(&mut notifications.smtp_username, "SMTP_USERNAME", errors);
+        env_option(&mut notifications.smtp_password, "SMTP_PASSWORD", errors);
+        env_value(&mut notifications.smtp_from, "SMTP_FROM", errors);
+        env_option(&mut notifications.log_file, "NOTIFY_LOG_FILE", errors);
+        env_option(&mut notifications.mail_log_file, "MAIL_LOG_FILE", errors);
+        env_value(&mut notifications.poll_interval_secs, "NOTIFY_POLL_SECS", errors);
+        env_value(&mut notifications.max_attempts, "NOTIFY_MAX_ATTEMPTS", errors);
+
+        let rate_limits = &mut self.rate_limits;
+        env_value(&mut rate_limits.trust_proxy, "TRUST_PROXY", errors);
+        env_value(&mut rate_limits.default_per_minute, "RATE_LIMIT_DEFAULT", errors);
+        for scope in rate_limits.scopes.iter_mut() {
+            env_value(&mut scope.per_minute, &format!("RATE_LIMIT_{}", scope.name.to_uppercase()), errors);
+        }
+
+        let business = &mut self.business;
+        env_value(&mut business.chart_lead_hours, "CHART_LEAD_HOURS", errors);
+        env_value(&mut business.fare_quote_minutes, "FARE_QUOTE_MINUTES", errors);
+        env_value(&mut business.session_days, "SESSION_DAYS", errors);
+        env_value(&mut business.refund_percent, "REFUND_PERCENT", errors);
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        let server = &self.server;
+        if server.host.trim().is_empty() {
+            errors.push("server.host must not be empty".to_string());
+        }
+        if server.port == 0 {
+            errors.push("server.port must be between 1 and 65535".to_string());
+        }
+        if server.metrics_host.trim().is_empty() {
+            errors.push("server.metrics_host must not be empty".to_string());
+        }
+        if server.metrics_port == 0 {
+            errors.push("server.metrics_port must be between 1 and 65535".to_string());
+        } else if server.metrics_port == server.port && server.metrics_host == server.host {
+            errors.push("server.metrics_port must differ from server.port".to_string());
+        }
+        if server.workers == Some(0) {
+            errors.push("server.workers must be at least 1".to_string());
+        }
+        if let Some(tls) = &server.tls {
+            for (name, path) in [("cert_path", &tls.cert_path), ("key_path", &tls.key_path)] {
+                if !Path::new(path).is_file() {
+                    errors.push(format!("server.tls.{}: {} does not exist", name, path));
+                }
+            }
+        }
+
+        let database = &self.database;
+        if database.url.is_empty() {
+            errors.push("database.url is required (or set DATABASE_URL)".to_string());
+        } else if !(database.url.starts_with("mysql://") || database.url.starts_with("mariadb://")) {
+            errors.push("database.url must be a mysql:// URL".to_string());
+        }
+        if database.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+        if database.min_connections > database.max_connections {
+            errors.push("database.min_connections cannot exceed database.max_connections".to_string());
+        }
+        if database.acquire_timeout_secs == 0 {
+            errors.push("database.acquire_timeout_secs must be at least 1".to_string());
+        }
+
+        for origin in &self.cors.allowed_origins {
+            let valid = origin == "*"
+                || ((origin.starts_with("http://") || origin.starts_with("https://")) && !origin.ends_with('/'));
+            if !valid {
+                errors.push(format!("cors.allowed_origins: {:?} must be \"*\" or scheme://host[:port] without a path", origin));
+            }
+        }
+
+        if self.logging.level.parse::<tracing::Level>().is_err() {
+            errors.push(format!("logging.level: unknown level {:?}", self.logging.level));
+        }
+        if !["full", "compact", "pretty", "json"].contains(&self.logging.format.as_str()) {
+            errors.push(format!("logging.format must be full, compact, pretty or json, not {:?}", self.logging.format));
+        }
+        if let Err(e) = logging::env_filter(&self.logging) {
+            errors.push(format!("logging.filter: invalid directives {}", e));
+        }
+
+        if let Some(key) = &self.tickets.signing_key {
+            use base64::Engine;
+            let decoded = base64::engine::general_purpose::STANDARD.decode(key.trim());
+            if !decoded.is_ok_and(|bytes| bytes.len() == 32) {
+                errors.push("tickets.signing_key must be base64 of exactly 32 bytes".to_string());
+            }
+        }
+
+        let notifications = &self.notifications;
+        if notifications.smtp_from.parse::<lettre::message::Mailbox>().is_err() {
+            errors.push(format!("notifications.smtp_from: {:?} is not a valid mailbox", notifications.smtp_from));
+        }
+        if notifications.smtp_username.is_some() != notifications.smtp_password.is_some() {
+            errors.push("notifications.smtp_username and smtp_password must be set together".to_string());
+        }
+        if notifications.poll_interval_secs == 0 {
+            errors.push("notifications.poll_interval_secs must be at least 1".to_string());
+        }
+        if notifications.max_attempts < 1 {
+            errors.push("notifications.max_attempts must be at least 1".to_string());
+        }
+
+        for scope in &self.rate_limits.scopes {
+            if scope.prefixes.iter().any(|prefix| !prefix.starts_with('/')) {
+                errors.push(format!("rate_limits.scopes.{}: prefixes must start with '/'", scope.name));
+            }
+        }
+
+        let business = &self.business;
+        if !(1..=48).contains(&business.chart_lead_hours) {
+            errors.push("business.chart_lead_hours must be between 1 and 48".to_string());
+        }
+        if !(1..=120).contains(&business.fare_quote_minutes) {
+            errors.push("business.fare_quote_minutes must be between 1 and 120".to_string());
+        }
+        if !(1..=90).contains(&business.session_days) {
+            errors.push("business.session_days must be between 1 and 90".to_string());
+        }
+        if !(0..=100).contains(&business.refund_percent) {
+            errors.push("business.refund_percent must be between 0 and 100".to_string());
+        }
+    }
+}
@@ -1,8 +1,24 @@
-use sqlx::{mysql::MySqlPoolOptions, MySqlPool};
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::LevelFilter;
+use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
+use sqlx::{ConnectOptions, MySqlPool};
+
+use crate::config::DatabaseConfig;
+
+// Statements are logged at debug under `sqlx::query` (with their timings) and
+// as warnings once they exceed the slow-query threshold
+pub async fn init_pool(config: &DatabaseConfig) -> Result<MySqlPool, sqlx::Error> {
+    let options = MySqlConnectOptions::from_str(&config.url)?
+        .log_statements(LevelFilter::Debug)
+        .log_slow_statements(LevelFilter::Warn, Duration::from_millis(config.slow_query_ms));
 
-pub async fn init_pool(database_url: &str) -> Result<MySqlPool, sqlx::Error> {
     MySqlPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.idle_timeout_secs))
+        .connect_with(options)
         .await
 }
\ No newline at end of file
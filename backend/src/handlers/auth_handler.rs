@@ -0,0 +1,269 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use actix_web::{dev::Payload, error::InternalError, web, Error, FromRequest, HttpRequest, HttpResponse, Responder, Result};
+use sqlx::MySqlPool;
+
+use crate::logging::REDACTED;
+use crate::models::user::{AccountEmail, ConfirmToken, ResetPassword};
+use crate::services::accounts::{
+    consume_token, end_session, issue_token, render_token_mail, session_user, set_password, validate_password,
+    PASSWORD_RESET, VERIFY_EMAIL,
+};
+use crate::services::login_guard;
+use crate::services::mailer::Mailer;
+
+// The signed-in user, from an `Authorization: Bearer <token>` header
+#[derive(Clone)]
+pub struct AuthUser {
+    pub email: String,
+    pub role: String,
+    pub token: String,
+}
+
+impl fmt::Debug for AuthUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthUser")
+            .field("email", &self.email)
+            .field("role", &self.role)
+            .field("token", &REDACTED)
+            .finish()
+    }
+}
+
+impl AuthUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == "ADMIN"
+    }
+
+    // Ticket examiners, and admins standing in for one
+    pub fn is_examiner(&self) -> bool {
+        matches!(self.role.as_str(), "TTE" | "ADMIN")
+    }
+
+    // Whether this user may act on the account of `email`
+    pub fn can_manage(&self, email: &str) -> bool {
+        self.is_admin() || self.email.eq_ignore_ascii_case(email)
+    }
+}
+
+fn unauthorized(message: &str) -> Error {
+    InternalError::from_response(
+        message.to_string(),
+        HttpResponse::Unauthorized().json(serde_json::json!({ "error": message })),
+    )
+    .into()
+}
+
+impl FromRequest for AuthUser {
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let pool = req.app_data::<web::Data<MySqlPool>>().cloned();
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+
+        Box::pin(async move {
+            let (Some(pool), Some(token)) = (pool, token) else {
+                return Err(unauthorized("Sign in required"));
+            };
+            match session_user(pool.get_ref(), &token).await {
+                Ok(Some((email, role))) => Ok(AuthUser { email, role, token }),
+                Ok(None) => Err(unauthorized("Session expired or invalid")),
+                Err(e) => {
+                    tracing::error!("Error looking up session: {:?}", e);
+                    Err(actix_web::error::ErrorInternalServerError("Failed to check session"))
+                }
+            }
+        })
+    }
+}
+
+// Issues a token and mails it. Failures are logged only, so responses do not
+// reveal whether an address has an account.
+pub(crate) async fn send_account_token(pool: &MySqlPool, mailer: &dyn Mailer, email: &str, purpose: &str) {
+    let token = match issue_token(pool, email, purpose).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Error issuing {} token: {:?}", purpose, e);
+            return;
+        }
+    };
+    let (subject, body) = render_token_mail(purpose, &token);
+    if let Err(e) = mailer.send(email, &subject, &body).await {
+        tracing::error!("Error sending {} mail to {}: {}", purpose, email, e);
+    }
+}
+
+async fn account_exists(pool: &MySqlPool, email: &str, unverified_only: bool) -> Result<bool, sqlx::Error> {
+    let verified = sqlx::query_scalar::<_, bool>("SELECT email_verified FROM users WHERE email = ?")
+        .bind(email)
+        .fetch_optional(pool)
+        .await?;
+    Ok(match verified {
+        Some(verified) => !(unverified_only && verified),
+        None => false,
+    })
+}
+
+// POST /api/auth/logout
+pub async fn logout(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+) -> Result<impl Responder, Error> {
+    match end_session(pool.get_ref(), &user.token).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "Signed out" }))),
+        Err(e) => {
+            tracing::error!("Error ending session: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to sign out",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/auth/verify-email/request
+pub async fn request_email_verification(
+    pool: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    payload: web::Json<AccountEmail>,
+) -> Result<impl Responder, Error> {
+    match account_exists(pool.get_ref(), &payload.email, true).await {
+        Ok(true) => send_account_token(pool.get_ref(), mailer.get_ref(), &payload.email, VERIFY_EMAIL).await,
+        Ok(false) => {}
+        Err(e) => tracing::error!("Error fetching user: {:?}", e),
+    }
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "message": "If the address belongs to an unverified account, a verification code has been sent"
+    })))
+}
+
+// POST /api/auth/verify-email/confirm
+pub async fn confirm_email_verification(
+    pool: web::Data<MySqlPool>,
+    payload: web::Json<ConfirmToken>,
+) -> Result<impl Responder, Error> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to start transaction")
+    })?;
+
+    let result = async {
+        let Some(email) = consume_token(&mut tx, &payload.token, VERIFY_EMAIL).await? else {
+            return Ok(None);
+        };
+        sqlx::query("UPDATE users SET email_verified = TRUE WHERE email = ?")
+            .bind(&email)
+            .execute(&mut *tx)
+            .await?;
+        Ok::<_, sqlx::Error>(Some(email))
+    }
+    .await;
+
+    match result {
+        Ok(Some(email)) => {
+            tx.commit().await.map_err(|e| {
+                tracing::error!("Failed to commit transaction: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to verify email")
+            })?;
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Email verified",
+                "email": email
+            })))
+        }
+        Ok(None) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Verification code is invalid, expired or already used"
+        }))),
+        Err(e) => {
+            tracing::error!("Error verifying email: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify email",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/auth/password-reset/request
+pub async fn request_password_reset(
+    pool: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    payload: web::Json<AccountEmail>,
+) -> Result<impl Responder, Error> {
+    match account_exists(pool.get_ref(), &payload.email, false).await {
+        Ok(true) => send_account_token(pool.get_ref(), mailer.get_ref(), &payload.email, PASSWORD_RESET).await,
+        Ok(false) => {}
+        Err(e) => tracing::error!("Error fetching user: {:?}", e),
+    }
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "message": "If the address belongs to an account, a reset code has been sent"
+    })))
+}
+
+// POST /api/auth/password-reset/confirm
+pub async fn confirm_password_reset(
+    pool: web::Data<MySqlPool>,
+    payload: web::Json<ResetPassword>,
+) -> Result<impl Responder, Error> {
+    if let Err(reason) = validate_password(&payload.new_password) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to start transaction")
+    })?;
+
+    let result = async {
+        let Some(email) = consume_token(&mut tx, &payload.token, PASSWORD_RESET).await? else {
+            return Ok(Err(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Reset code is invalid, expired or already used"
+            }))));
+        };
+        if let Err(e) = set_password(&mut tx, &email, &payload.new_password).await? {
+            tracing::error!("Error hashing password: {}", e);
+            return Ok(Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reset password"
+            }))));
+        }
+        // Receiving the code proves the address, so the account counts as verified
+        sqlx::query("UPDATE users SET email_verified = TRUE WHERE email = ?")
+            .bind(&email)
+            .execute(&mut *tx)
+            .await?;
+        // and a lockout from failed sign-ins no longer serves a purpose
+        login_guard::clear(&mut *tx, login_guard::ACCOUNT, &email).await?;
+        Ok::<_, sqlx::Error>(Ok(email))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(email)) => {
+            tx.commit().await.map_err(|e| {
+                tracing::error!("Failed to commit transaction: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to reset password")
+            })?;
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Password reset. Sign in with the new password.",
+                "email": email
+            })))
+        }
+        Ok(Err(response)) => Ok(response),
+        Err(e) => {
+            tracing::error!("Error resetting password: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reset password",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
@@ -1,159 +1,273 @@
 use actix_web::{web, Error, HttpResponse, Responder, Result};
 use chrono::Utc;
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
 
-use crate::models::{booking::{BookingDetail, GroupBookingRequest}, seat::SeatCount, transaction::CancelBookingRequest};
+use crate::config::BusinessConfig;
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::{
+    booking::{BookingDetail, GroupBookingRequest},
+    fare::{ReplaceFlexiSteps, RequestFareQuote},
+    tatkal::UpdateTatkalFare,
+    train::TRAIN_TYPES,
+    transaction::CancelBookingRequest,
+};
+use crate::services::availability::{journey_availability, CLASS_ORDER};
+use crate::services::flexi_fare::{concession_fare, fetch_steps, lock_quote, redeem_quote};
+use crate::services::metrics::Metrics;
+use crate::services::notifications::{enqueue_all, Notification, BOOKING_CANCELLED, BOOKING_CREATED};
+use crate::services::promo::{evaluate, redeem, release};
+use crate::services::quota::{check_eligibility, is_quota, EMERGENCY, GENERAL, TATKAL};
+use crate::services::tatkal::tatkal_quote;
+use crate::services::travellers::{link_passengers, resolve_passengers};
 
 use super::utils::QueryParams;
 
-pub async fn get_cnf_seat_count_by_coach_category(
+// GET /api/booking/availability/{journey_id}?from=..&to=..&reservation_category=..&quota=..
+#[tracing::instrument(skip_all, fields(journey_id = *journey_id))]
+pub async fn get_journey_availability(
     pool: web::Data<MySqlPool>,
     journey_id: web::Path<i64>,
+    query: web::Query<QueryParams>,
 ) -> Result<impl Responder, Error> {
-    let results = sqlx::query_as!(
-        SeatCount,
-        r#"
-        SELECT
-            c.coach_type AS reservation_category,
-            COUNT(rs.reservation_status) AS seat_count
-        FROM (
-            SELECT 'SL' AS coach_type
-            UNION ALL SELECT 'AC3'
-            UNION ALL SELECT 'AC2'
-            UNION ALL SELECT 'AC1'
-            UNION ALL SELECT 'CC'
-            UNION ALL SELECT 'FC'
-            UNION ALL SELECT '2S'
-        ) AS c
-        LEFT JOIN (
-            SELECT rs.reservation_category, rs.reservation_status
-            FROM reservation_status rs
-            JOIN booking b ON rs.pnr = b.pnr
-            WHERE b.journey_id = ? AND rs.reservation_status = 'CNF'
-            AND b.booking_status IN ('CONFIRMED', 'PENDING')
-        ) AS rs ON rs.reservation_category = c.coach_type
-        GROUP BY c.coach_type
-        ORDER BY c.coach_type;
-        "#,
-        *journey_id
-    )
-    .fetch_all(pool.get_ref())
-    .await;
+    let journey_id = journey_id.into_inner();
+    let quota = query.quota.as_deref().unwrap_or(GENERAL).to_uppercase();
 
-    match results {
-        Ok(counts) => Ok(HttpResponse::Ok().json(counts)),
+    match journey_availability(pool.get_ref(), journey_id, query.from, query.to, &quota).await {
+        Ok(Some(mut classes)) => {
+            if let Some(category) = &query.reservation_category {
+                classes.retain(|c| &c.reservation_category == category);
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "journey_id": journey_id,
+                "from": query.from,
+                "to": query.to,
+                "quota": quota,
+                "data": classes
+            })))
+        }
+        Ok(None) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "from and to must be stops of this journey, in travel order"
+        }))),
         Err(e) => {
-            eprintln!("Error fetching CNF seat count: {:?}", e);
+            tracing::error!("Error fetching availability: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch CNF seat count",
+                "error": "Failed to fetch availability",
                 "details": e.to_string()
             })))
         }
     }
 }
 
-pub async fn get_rac_seat_count_by_coach_category(
+#[tracing::instrument(skip_all, fields(journey_id = booking.journey_id, txn_id = booking.txn_id, class = %booking.reservation_category))]
+pub async fn create_group_booking_handler(
     pool: web::Data<MySqlPool>,
-    journey_id: web::Path<i64>,
-) -> Result<impl Responder, Error> {
-    let results = sqlx::query_as!(
-        SeatCount,
-        r#"
-        SELECT
-            c.coach_type AS reservation_category,
-            COUNT(rs.reservation_status) AS seat_count
-        FROM (
-            SELECT 'SL' AS coach_type
-            UNION ALL SELECT 'AC3'
-            UNION ALL SELECT 'AC2'
-            UNION ALL SELECT 'AC1'
-            UNION ALL SELECT 'CC'
-            UNION ALL SELECT 'FC'
-            UNION ALL SELECT '2S'
-        ) AS c
-        LEFT JOIN (
-            SELECT rs.reservation_category, rs.reservation_status
-            FROM reservation_status rs
-            JOIN booking b ON rs.pnr = b.pnr
-            WHERE b.journey_id = ? AND rs.reservation_status = 'RAC'
-            AND b.booking_status IN ('CONFIRMED', 'PENDING')
-        ) AS rs ON rs.reservation_category = c.coach_type
-        GROUP BY c.coach_type
-        ORDER BY c.coach_type;
-        "#,
-        *journey_id
-    )
-    .fetch_all(pool.get_ref())
-    .await;
+    business: web::Data<BusinessConfig>,
+    metrics: web::Data<Metrics>,
+    user: AuthUser,
+    booking: web::Json<GroupBookingRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    if !user.can_manage(&booking.email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only book on your own account"
+        })));
+    }
 
-    match results {
-        Ok(counts) => Ok(HttpResponse::Ok().json(counts)),
-        Err(e) => {
-            eprintln!("Error fetching RAC seat count: {:?}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch RAC seat count",
-                "details": e.to_string()
-            })))
+    let journey_status = sqlx::query_scalar::<_, String>("SELECT journey_status FROM journey WHERE journey_id = ?")
+        .bind(booking.journey_id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching journey: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch journey")
+        })?;
+
+    match journey_status.as_deref() {
+        Some("SCHEDULED") => {}
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Journey not found"
+            })));
+        }
+        Some(_) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Journey is no longer open for booking"
+            })));
         }
     }
-}
 
-pub async fn get_wl_seat_count_by_coach_category(
-    pool: web::Data<MySqlPool>,
-    journey_id: web::Path<i64>,
-) -> Result<impl Responder, Error> {
-    let results = sqlx::query_as!(
-        SeatCount,
-        r#"
-        SELECT
-            c.coach_type AS reservation_category,
-            COUNT(rs.reservation_status) AS seat_count
-        FROM (
-            SELECT 'SL' AS coach_type
-            UNION ALL SELECT 'AC3'
-            UNION ALL SELECT 'AC2'
-            UNION ALL SELECT 'AC1'
-            UNION ALL SELECT 'CC'
-            UNION ALL SELECT 'FC'
-            UNION ALL SELECT '2S'
-        ) AS c
-        LEFT JOIN (
-            SELECT rs.reservation_category, rs.reservation_status
-            FROM reservation_status rs
-            JOIN booking b ON rs.pnr = b.pnr
-            WHERE b.journey_id = ? AND rs.reservation_status = 'WL'
-            AND b.booking_status IN ('CONFIRMED', 'PENDING')
-        ) AS rs ON rs.reservation_category = c.coach_type
-        GROUP BY c.coach_type
-        ORDER BY c.coach_type
-        "#,
-        *journey_id
+    let quota = booking.quota.as_deref().unwrap_or(GENERAL).to_uppercase();
+    if !is_quota(&quota) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown quota"
+        })));
+    }
+
+    // Passengers may be saved travellers, given by id, of the signed-in user
+    let mut passenger_data = booking.passenger_data.clone();
+    let resolved = resolve_passengers(
+        pool.get_ref(),
+        &user.email,
+        booking.journey_id,
+        booking.start_station_id,
+        &mut passenger_data,
     )
-    .fetch_all(pool.get_ref())
-    .await;
+    .await
+    .map_err(|e| {
+        tracing::error!("Error fetching saved travellers: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch saved travellers")
+    })?;
+    let traveller_ids = match resolved {
+        Ok(ids) => ids,
+        Err(reason) => {
+            return Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+                "error": reason
+            })));
+        }
+    };
 
-    match results {
-        Ok(counts) => Ok(HttpResponse::Ok().json(counts)),
-        Err(e) => {
-            eprintln!("Error fetching WL seat count: {:?}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch WL seat count",
-                "details": e.to_string()
-            })))
+    if let Err(reason) = check_eligibility(&quota, &passenger_data) {
+        return Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": reason
+        })));
+    }
+
+    // Emergency quota seats are allotted by railway staff only
+    if quota == EMERGENCY && !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Emergency quota is allotted by railway staff"
+        })));
+    }
+
+    // Tatkal is sold only inside its window, at the server's fare
+    if quota == TATKAL {
+        let quote = tatkal_quote(
+            pool.get_ref(),
+            booking.journey_id,
+            &booking.reservation_category,
+            (Some(booking.start_station_id), Some(booking.end_station_id)),
+            business.chart_lead_hours,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching tatkal fare: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch tatkal fare")
+        })?;
+        let Some(quote) = quote else {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Tatkal is not offered for this class between these stations"
+            })));
+        };
+        if !quote.window.is_open(Utc::now()) {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Tatkal booking window is not open",
+                "opens_at": quote.window.opens_at,
+                "closes_at": quote.window.closes_at
+            })));
+        }
+        if let Some(passengers) = passenger_data.as_array_mut() {
+            for passenger in passengers {
+                passenger["fare"] = serde_json::json!(quote.fare);
+            }
         }
     }
-}
 
-pub async fn create_group_booking_handler(
-    pool: web::Data<MySqlPool>,
-    booking: web::Json<GroupBookingRequest>,
-) -> Result<impl Responder, actix_web::Error> {
+    // Flexi-priced classes are sold only at a fare the passenger was quoted
+    if quota == TATKAL && booking.quote_id.is_some() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Tatkal bookings are charged the tatkal fare; omit quote_id"
+        })));
+    }
+    if quota != TATKAL && booking.quote_id.is_none() {
+        let steps = fetch_steps(pool.get_ref(), booking.journey_id, &booking.reservation_category)
+            .await
+            .map_err(|e| {
+                tracing::error!("Error fetching flexi fare steps: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to fetch fare")
+            })?;
+        if !steps.is_empty() {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "This class has flexi fares; request a fare quote and book with its quote_id"
+            })));
+        }
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    if let Some(quote_id) = booking.quote_id {
+        let redeemed = redeem_quote(
+            &mut tx,
+            quote_id,
+            booking.journey_id,
+            &booking.reservation_category,
+            (booking.start_station_id, booking.end_station_id),
+            booking.txn_id,
+        )
+        .await;
+        match redeemed {
+            // Each passenger pays the quoted fare less their concession, whatever was sent
+            Ok(Ok(fare)) => {
+                if let Some(passengers) = passenger_data.as_array_mut() {
+                    for passenger in passengers {
+                        passenger["fare"] = serde_json::json!(concession_fare(fare, passenger));
+                    }
+                }
+            }
+            Ok(Err(reason)) => {
+                return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": reason
+                })));
+            }
+            Err(e) => {
+                tracing::error!("Error redeeming fare quote: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to redeem fare quote",
+                    "details": e.to_string()
+                })));
+            }
+        }
+    }
+
+    // The promo code is checked against the final fares, then redeemed once the bookings exist
+    let promo = match &booking.promo_code {
+        Some(code) => {
+            let amount: f32 = passenger_data
+                .as_array()
+                .map(|passengers| {
+                    passengers
+                        .iter()
+                        .filter_map(|p| p.get("fare").and_then(serde_json::Value::as_f64))
+                        .sum::<f64>() as f32
+                })
+                .unwrap_or_default();
+            let evaluated = evaluate(&mut tx, code, &user.email, booking.journey_id, &booking.reservation_category, amount).await;
+            match evaluated {
+                Ok(Ok(promo)) => Some(promo),
+                Ok(Err(reason)) => {
+                    return Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+                        "error": reason
+                    })));
+                }
+                Err(e) => {
+                    tracing::error!("Error checking promo code: {:?}", e);
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to check promo code",
+                        "details": e.to_string()
+                    })));
+                }
+            }
+        }
+        None => None,
+    };
+
     let result = sqlx::query!(
         r#"
-        CALL create_group_booking(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        CALL create_group_booking(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         booking.group_size,
-        booking.passenger_data.to_string(), // serialize JSON
+        passenger_data.to_string(), // serialize JSON
         booking.journey_id,
         booking.train_id,
         booking.start_station_id,
@@ -162,20 +276,66 @@ pub async fn create_group_booking_handler(
         booking.txn_id,
         booking.email,
         booking.reservation_category,
+        quota,
     )
-    .execute(pool.get_ref())
+    .execute(&mut *tx)
     .await;
 
+    let notification = Notification {
+        email: Some(booking.email.clone()),
+        pnr: None,
+        event_type: BOOKING_CREATED,
+        payload: serde_json::json!({
+            "journey_id": booking.journey_id,
+            "txn_id": booking.txn_id,
+            "group_size": booking.group_size,
+            "reservation_category": booking.reservation_category,
+            "quota": quota,
+            "discount": promo.as_ref().map(|p| p.discount),
+        }),
+    };
+    let result = match result {
+        Ok(_) => link_passengers(&mut tx, &user.email, booking.txn_id, &traveller_ids).await,
+        Err(e) => Err(e),
+    };
+    let result = match (result, &promo) {
+        (Ok(_), Some(promo)) => redeem(&mut tx, promo, &user.email, booking.txn_id).await,
+        (Ok(_), None) => Ok(()),
+        (Err(e), _) => Err(e),
+    };
+    let result = match result {
+        Ok(_) => enqueue_all(&mut tx, &[notification]).await,
+        Err(e) => Err(e),
+    };
+    let result = match result {
+        Ok(_) => status_counts(&mut tx, booking.txn_id).await,
+        Err(e) => Err(e),
+    };
+
     match result {
-        Ok(_) => {
+        Ok(statuses) => {
+            if let Err(e) = tx.commit().await {
+                tracing::error!("Transaction commit failed: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Transaction commit failed",
+                    "details": e.to_string()
+                })));
+            }
+            for (status, count) in statuses {
+                metrics
+                    .bookings_created
+                    .with_label_values(&[booking.reservation_category.as_str(), status.as_str()])
+                    .inc_by(count.max(0) as u64);
+            }
             // You could also query back for bookings based on txn_id if needed
             Ok(HttpResponse::Created().json(serde_json::json!({
                 "message": "Group booking created successfully",
-                "txn_id": booking.txn_id
+                "txn_id": booking.txn_id,
+                "promo": promo
             })))
         },
         Err(e) => {
-            eprintln!("Error in group booking: {:?}", e);
+            tracing::error!("Error in group booking: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Group booking failed",
                 "details": e.to_string()
@@ -184,12 +344,33 @@ pub async fn create_group_booking_handler(
     }
 }
 
+// Passengers of a transaction by reservation status, for the booking metrics
+async fn status_counts(tx: &mut Transaction<'_, MySql>, txn_id: i64) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT CAST(rs.reservation_status AS CHAR), COUNT(*)
+        FROM booking b
+        JOIN reservation_status rs ON rs.pnr = b.pnr
+        WHERE b.txn_id = ?
+        GROUP BY rs.reservation_status
+        "#,
+    )
+    .bind(txn_id)
+    .fetch_all(&mut **tx)
+    .await
+}
+
 pub async fn get_booking_details_by_email(
     pool: web::Data<MySqlPool>,
+    user: AuthUser,
     query: web::Query<QueryParams>,
 ) -> Result<impl Responder, Error> {
-
-    let email = &query.email;
+    let email = query.email.clone().unwrap_or_else(|| user.email.clone());
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only view your own bookings"
+        })));
+    }
 
     let bookings = sqlx::query_as!(
         BookingDetail,
@@ -209,6 +390,7 @@ pub async fn get_booking_details_by_email(
             pt.txn_status,
             rs.reservation_status,
             rs.reservation_category,
+            rs.quota AS `quota?`,
             s.seat_no,
             s.seat_type,
             s.seat_category,
@@ -245,29 +427,81 @@ pub async fn get_booking_details_by_email(
     .fetch_all(pool.get_ref())
     .await
     .map_err(|e| {
-        eprintln!("Error fetching booking details: {}", e);
+        tracing::error!("Error fetching booking details: {}", e);
         actix_web::error::ErrorInternalServerError("Could not retrieve booking details")
     })?;
 
     Ok(HttpResponse::Ok().json(bookings))
 }
 
+#[tracing::instrument(skip_all, fields(booking_id = request.booking_id))]
 pub async fn cancel_booking_handler(
     pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    business: web::Data<BusinessConfig>,
+    metrics: web::Data<Metrics>,
     request: web::Json<CancelBookingRequest>,
 ) -> Result<impl Responder, Error> {
     let booking_id = request.booking_id;
-    let refund_amount = request.refund_amount;
-    let txn_id = request.txn_id;
 
     // Cancel time
     let cancel_time = Utc::now().naive_utc();
 
     let mut tx = pool.begin().await.map_err(|e| {
-        eprintln!("Transaction begin failed: {:?}", e);
+        tracing::error!("Transaction begin failed: {:?}", e);
         actix_web::error::ErrorInternalServerError("Transaction begin failed")
     })?;
 
+    // Bookings whose journey was retimed by the railway are refunded in full
+    let booking = sqlx::query_as::<_, (bool, Option<f32>, Option<String>, Option<i64>, Option<i64>, Option<String>, Option<String>, Option<String>)>(
+        r#"
+        SELECT b.free_cancellation, b.amount, b.booking_status, b.txn_id, b.pnr, p.email, rs.quota, rs.reservation_status
+        FROM booking b
+        LEFT JOIN passenger p ON p.pnr = b.pnr
+        LEFT JOIN reservation_status rs ON rs.pnr = b.pnr
+        WHERE b.booking_id = ?
+        FOR UPDATE
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch booking: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch booking")
+    })?;
+
+    let Some((free_cancellation, amount, booking_status, booked_txn_id, pnr, email, quota, reservation_status)) = booking else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Booking not found"
+        })));
+    };
+    if !email.as_deref().is_some_and(|email| user.can_manage(email)) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only cancel your own bookings"
+        })));
+    }
+    // Repeat requests must not refund the same booking twice
+    let live = matches!(booking_status.as_deref(), Some("CONFIRMED" | "PENDING"))
+        && matches!(reservation_status.as_deref(), Some("CNF" | "RAC" | "WL"));
+    if !live {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Booking is not active and cannot be cancelled",
+            "booking_status": booking_status,
+            "reservation_status": reservation_status,
+        })));
+    }
+
+    let paid = if booking_status.as_deref() == Some("CONFIRMED") { amount.map_or(0.0, f64::from) } else { 0.0 };
+    let refund_amount = if free_cancellation {
+        paid
+    } else if quota.as_deref() == Some(TATKAL) && reservation_status.as_deref() == Some("CNF") {
+        // Confirmed tatkal tickets are not refundable
+        0.0
+    } else {
+        (paid * business.refund_percent as f64).round() / 100.0
+    };
+
     // Step 1: Update booking status
     let update_result = sqlx::query!(
         r#"
@@ -281,13 +515,24 @@ pub async fn cancel_booking_handler(
     .await;
 
     if let Err(e) = update_result {
-        eprintln!("Failed to update booking: {:?}", e);
+        tracing::error!("Failed to update booking: {:?}", e);
         return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
             "error": "Failed to update booking status",
             "details": e.to_string()
         })));
     }
 
+    // The promo code is given back once the whole transaction is cancelled
+    if let Some(booked_txn_id) = booked_txn_id
+        && let Err(e) = release(&mut tx, booked_txn_id).await
+    {
+        tracing::error!("Failed to release promo code: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to release promo code",
+            "details": e.to_string()
+        })));
+    }
+
     // Step 2: Insert into cancellation_record
     let insert_result = sqlx::query!(
         r#"
@@ -297,30 +542,326 @@ pub async fn cancel_booking_handler(
         booking_id,
         cancel_time,
         refund_amount,
-        txn_id
+        booked_txn_id
     )
     .execute(&mut *tx)
     .await;
 
     if let Err(e) = insert_result {
-        eprintln!("Failed to insert cancellation record: {:?}", e);
+        tracing::error!("Failed to insert cancellation record: {:?}", e);
         return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
             "error": "Failed to insert cancellation record",
             "details": e.to_string()
         })));
     }
 
+    let notification = Notification {
+        email,
+        pnr,
+        event_type: BOOKING_CANCELLED,
+        payload: serde_json::json!({
+            "booking_id": booking_id,
+            "refund_amount": refund_amount,
+        }),
+    };
+    if let Err(e) = enqueue_all(&mut tx, &[notification]).await {
+        tracing::error!("Failed to queue cancellation notice: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to queue cancellation notice",
+            "details": e.to_string()
+        })));
+    }
+
     // Commit transaction
     if let Err(e) = tx.commit().await {
-        eprintln!("Transaction commit failed: {:?}", e);
+        tracing::error!("Transaction commit failed: {:?}", e);
         return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
             "error": "Transaction commit failed",
             "details": e.to_string()
         })));
     }
+    metrics
+        .cancellations
+        .with_label_values(&[quota.as_deref().unwrap_or("unknown"), reservation_status.as_deref().unwrap_or("unknown")])
+        .inc();
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "message": "Booking cancelled successfully",
-        "booking_id": booking_id
+        "booking_id": booking_id,
+        "refund_amount": refund_amount
+    })))
+}
+
+// GET /api/booking/tatkal/{journey_id}?from=..&to=..
+// Tatkal fare and booking window for every class of the journey, for the segment
+// from/to (the whole journey when omitted)
+pub async fn get_tatkal_quotes(
+    pool: web::Data<MySqlPool>,
+    business: web::Data<BusinessConfig>,
+    journey_id: web::Path<i64>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let journey_id = journey_id.into_inner();
+
+    let res = async {
+        let classes = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT c.coach_type
+            FROM coach c
+            JOIN journey j ON j.train_id = c.train_id
+            WHERE j.journey_id = ? AND c.coach_type IS NOT NULL
+            "#,
+        )
+        .bind(journey_id)
+        .fetch_all(pool.get_ref())
+        .await?;
+
+        let mut quotes = Vec::new();
+        for class in classes {
+            if let Some(quote) = tatkal_quote(pool.get_ref(), journey_id, &class, (query.from, query.to), business.chart_lead_hours).await? {
+                quotes.push(quote);
+            }
+        }
+        Ok::<_, sqlx::Error>(quotes)
+    }
+    .await;
+
+    match res {
+        Ok(quotes) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "journey_id": journey_id,
+            "data": quotes
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching tatkal quotes: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch tatkal quotes",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// PUT /api/booking/tatkal/fares/{reservation_category}
+pub async fn update_tatkal_fare(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    reservation_category: web::Path<String>,
+    payload: web::Json<UpdateTatkalFare>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can change tatkal fares"
+        })));
+    }
+
+    let reservation_category = reservation_category.into_inner().to_uppercase();
+    if !CLASS_ORDER.contains(&reservation_category.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown class",
+            "classes": CLASS_ORDER
+        })));
+    }
+
+    if payload.premium_rate < 0.0 || payload.min_premium < 0.0 || payload.min_premium > payload.max_premium {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "premium_rate and min_premium must be non-negative, and min_premium at most max_premium"
+        })));
+    }
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO tatkal_fare (reservation_category, premium_rate, min_premium, max_premium, opens_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            premium_rate = VALUES(premium_rate),
+            min_premium = VALUES(min_premium),
+            max_premium = VALUES(max_premium),
+            opens_at = VALUES(opens_at)
+        "#,
+    )
+    .bind(&reservation_category)
+    .bind(payload.premium_rate)
+    .bind(payload.min_premium)
+    .bind(payload.max_premium)
+    .bind(payload.opens_at)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Tatkal fare updated",
+            "reservation_category": reservation_category
+        }))),
+        Err(e) => {
+            tracing::error!("Error updating tatkal fare: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update tatkal fare",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/booking/quote
+// Prices a class of a journey and holds that fare for the checkout
+pub async fn create_fare_quote(
+    pool: web::Data<MySqlPool>,
+    business: web::Data<BusinessConfig>,
+    payload: web::Json<RequestFareQuote>,
+) -> Result<impl Responder, Error> {
+    let quote = lock_quote(
+        pool.get_ref(),
+        payload.journey_id,
+        &payload.reservation_category,
+        (payload.start_station_id, payload.end_station_id),
+        business.fare_quote_minutes,
+    )
+    .await;
+
+    match quote {
+        Ok(Some(quote)) => Ok(HttpResponse::Created().json(quote)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "The journey does not run this class between these stations"
+        }))),
+        Err(e) => {
+            tracing::error!("Error creating fare quote: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create fare quote",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// GET /api/booking/flexi/{train_type}
+pub async fn get_flexi_steps(
+    pool: web::Data<MySqlPool>,
+    train_type: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let train_type = train_type.into_inner().to_uppercase();
+
+    let steps = sqlx::query_as::<_, (Option<String>, i32, f32)>(
+        r#"
+        SELECT reservation_category, sold_percent, multiplier
+        FROM flexi_fare_step
+        WHERE train_type = ?
+        ORDER BY reservation_category, sold_percent
+        "#,
+    )
+    .bind(&train_type)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match steps {
+        Ok(steps) => {
+            let data: Vec<_> = steps
+                .into_iter()
+                .map(|(reservation_category, sold_percent, multiplier)| {
+                    serde_json::json!({
+                        "reservation_category": reservation_category,
+                        "sold_percent": sold_percent,
+                        "multiplier": multiplier
+                    })
+                })
+                .collect();
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "train_type": train_type,
+                "data": data
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Error fetching flexi steps: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch flexi steps",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// PUT /api/booking/flexi/{train_type}
+// Replaces the steps for one class (or the train type's default) in one go
+pub async fn replace_flexi_steps(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    train_type: web::Path<String>,
+    payload: web::Json<ReplaceFlexiSteps>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can change flexi fares"
+        })));
+    }
+
+    let train_type = train_type.into_inner().to_uppercase();
+    let payload = payload.into_inner();
+
+    if !TRAIN_TYPES.contains(&train_type.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown train type",
+            "train_types": TRAIN_TYPES
+        })));
+    }
+    if payload.reservation_category.as_deref().is_some_and(|c| !CLASS_ORDER.contains(&c)) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown class",
+            "classes": CLASS_ORDER
+        })));
+    }
+
+    if payload.steps.iter().any(|s| !(0..=100).contains(&s.sold_percent) || s.multiplier <= 0.0) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "sold_percent must be 0-100 and multiplier positive"
+        })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        sqlx::query("DELETE FROM flexi_fare_step WHERE train_type = ? AND reservation_category <=> ?")
+            .bind(&train_type)
+            .bind(&payload.reservation_category)
+            .execute(&mut *tx)
+            .await?;
+
+        if !payload.steps.is_empty() {
+            let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+                "INSERT INTO flexi_fare_step (train_type, reservation_category, sold_percent, multiplier) ",
+            );
+            query.push_values(&payload.steps, |mut row, step| {
+                row.push_bind(&train_type)
+                    .push_bind(&payload.reservation_category)
+                    .push_bind(step.sold_percent)
+                    .push_bind(step.multiplier);
+            });
+            query.build().execute(&mut *tx).await?;
+        }
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+
+    if let Err(e) = res {
+        tracing::error!("Error replacing flexi steps: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to replace flexi steps",
+            "details": e.to_string()
+        })));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "train_type": train_type,
+        "reservation_category": payload.reservation_category,
+        "steps": payload.steps
     })))
-}
\ No newline at end of file
+}
@@ -1,9 +1,23 @@
+use std::collections::HashSet;
+
 use actix_web::{web, Error, HttpResponse, Responder, Result};
-use sqlx::MySqlPool;
-use crate::models::coach::{CoachPricesByType, CoachResponse, CreateCoach};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use crate::models::coach::{AttachCoach, BuildComposition, CoachPricesByType, CoachResponse, CompositionEntry, CreateCoach, ReorderCoaches};
+use crate::services::coach_layout::{layout_seats, template_for, COACH_TEMPLATES};
 
 use super::utils::QueryParams;
 
+// coach.coach_name is a VARCHAR(10)
+const COACH_NAME_MAX: usize = 10;
+
+fn validate_coach_name(name: &str) -> Result<(), String> {
+    let length = name.trim().chars().count();
+    if length == 0 || length > COACH_NAME_MAX {
+        return Err(format!("coach_name must be 1-{} characters", COACH_NAME_MAX));
+    }
+    Ok(())
+}
+
 pub async fn get_coaches_for_train(
     pool: web::Data<MySqlPool>,
     train_id: web::Path<i64>,  // Path parameter for train_id
@@ -57,7 +71,7 @@ pub async fn get_coaches_for_train(
             })))
         },
         (Err(e), _) | (_, Err(e)) => {
-            eprintln!("Error fetching coaches: {:?}", e);
+            tracing::error!("Error fetching coaches: {:?}", e);
             // Return an internal server error response
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
@@ -70,6 +84,7 @@ pub async fn get_coaches_for_train(
 }
 
 
+// Appends the coach at the end of the train's composition
 pub async fn create_coach(
     pool: web::Data<MySqlPool>,
     new_coach: web::Json<CreateCoach>,
@@ -77,30 +92,86 @@ pub async fn create_coach(
 
     let new_coach = new_coach.into_inner();
 
+    if let Err(message) = validate_coach_name(&new_coach.coach_name) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": message })));
+    }
+    if template_for(&new_coach.coach_type).is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unknown coach_type {}", new_coach.coach_type)
+        })));
+    }
+    if new_coach.fare < 0.0 {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": "fare must not be negative" })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    // Locking the train serialises position assignment with other composition changes
+    let train_exists: Option<i64> = sqlx::query_scalar("SELECT train_id FROM train WHERE train_id = ? FOR UPDATE")
+        .bind(new_coach.train_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching train: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch train")
+        })?;
+
+    if train_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Train not found" })));
+    }
+
+    let last_position: Option<i32> = sqlx::query_scalar("SELECT MAX(position) FROM coach WHERE train_id = ?")
+        .bind(new_coach.train_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching coach positions: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch coach positions")
+        })?;
+    let position = last_position.unwrap_or(0) + 1;
+
     // Insert the new coach into the database
-    let result = sqlx::query!(
+    let result = sqlx::query(
         r#"
-        INSERT INTO coach (coach_name, coach_type, fare, train_id)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO coach (coach_name, coach_type, fare, train_id, position)
+        VALUES (?, ?, ?, ?, ?)
         "#,
-        new_coach.coach_name,
-        new_coach.coach_type,
-        new_coach.fare,
-        new_coach.train_id
     )
-    .execute(pool.get_ref())
+    .bind(new_coach.coach_name.trim())
+    .bind(&new_coach.coach_type)
+    .bind(new_coach.fare)
+    .bind(new_coach.train_id)
+    .bind(position)
+    .execute(&mut *tx)
     .await;
 
-    match result {
-        Ok(_) => Ok(HttpResponse::Created().finish()),
+    let coach_id = match result {
+        Ok(result) => result.last_insert_id() as i64,
         Err(e) => {
-            eprintln!("Error creating coach: {:?}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            tracing::error!("Error creating coach: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create coach",
                 "details": e.to_string()
-            })))
+            })));
         }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
     }
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "message": "Coach created successfully",
+        "coach_id": coach_id,
+        "position": position
+    })))
 }
 
 // Fetch the fare for a specific coach
@@ -133,11 +204,465 @@ pub async fn get_coach_prices(
     match fare {
         Ok(fare) => Ok(HttpResponse::Ok().json(serde_json::json!(fare))),
         Err(e) => {
-            eprintln!("Error fetching fare: {:?}", e);
+            tracing::error!("Error fetching fare: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch fare",
                 "details": e.to_string()
             })))
         }
     }
-}
\ No newline at end of file
+}
+
+// GET /api/coaches/templates
+pub async fn get_coach_templates() -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(COACH_TEMPLATES))
+}
+
+// Next free number for a coach name prefix on a train, e.g. S4 after S1..S3
+fn next_coach_number(existing_names: &[String], prefix: &str) -> i64 {
+    existing_names
+        .iter()
+        .filter_map(|name| name.strip_prefix(prefix)?.parse::<i64>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+// GET /api/trains/composition/id/{train_id}
+pub async fn get_train_composition(
+    pool: web::Data<MySqlPool>,
+    train_id: web::Path<i64>,
+) -> Result<impl Responder, Error> {
+    let train_id = train_id.into_inner();
+
+    let coaches = sqlx::query_as::<_, CompositionEntry>(
+        r#"
+        SELECT
+            c.coach_id, c.coach_name, c.coach_type, c.fare, c.position,
+            (SELECT COUNT(*) FROM seat s WHERE s.coach_id = c.coach_id) AS total_seats,
+            (SELECT COUNT(*) FROM seat s WHERE s.coach_id = c.coach_id AND s.seat_category = 'RAC') AS rac_seats
+        FROM coach c
+        WHERE c.train_id = ?
+        ORDER BY c.position IS NULL, c.position, c.coach_id
+        "#,
+    )
+    .bind(train_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match coaches {
+        Ok(coaches) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "train_id": train_id,
+            "total_coaches": coaches.len(),
+            "total_seats": coaches.iter().map(|c| c.total_seats).sum::<i64>(),
+            "data": coaches
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching train composition: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch train composition",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/trains/composition/id/{train_id}
+// Appends the given coaches to the train and generates all of their seats in one transaction
+pub async fn build_train_composition(
+    pool: web::Data<MySqlPool>,
+    train_id: web::Path<i64>,
+    payload: web::Json<BuildComposition>,
+) -> Result<impl Responder, Error> {
+    let train_id = train_id.into_inner();
+    let payload = payload.into_inner();
+
+    if payload.coaches.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "At least one coach is required"
+        })));
+    }
+
+    for coach in &payload.coaches {
+        let Some(template) = template_for(&coach.coach_type) else {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Unknown coach_type {}", coach.coach_type)
+            })));
+        };
+        if let Some(Err(message)) = coach.coach_name.as_deref().map(validate_coach_name) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": message })));
+        }
+        let capacity = coach.capacity.unwrap_or(template.capacity);
+        if !(1..=200).contains(&capacity) || coach.fare < 0.0 {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Coach capacity must be between 1 and 200 and fare must not be negative"
+            })));
+        }
+    }
+
+    let train_exists: Option<i64> = sqlx::query_scalar("SELECT train_id FROM train WHERE train_id = ?")
+        .bind(train_id)
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching train: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch train")
+        })?;
+
+    if train_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Train not found" })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let existing = sqlx::query_as::<_, (Option<String>, Option<i32>)>(
+        "SELECT coach_name, position FROM coach WHERE train_id = ? FOR UPDATE",
+    )
+    .bind(train_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Error fetching existing coaches: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch existing coaches")
+    })?;
+
+    let mut names: Vec<String> = existing.iter().filter_map(|(name, _)| name.clone()).collect();
+    let mut position = existing.iter().filter_map(|(_, p)| *p).max().unwrap_or(0);
+    let mut created = Vec::new();
+
+    for coach in &payload.coaches {
+        let Some(template) = template_for(&coach.coach_type) else {
+            continue;
+        };
+        let capacity = coach.capacity.unwrap_or(template.capacity);
+        let coach_name = coach.coach_name.as_deref().map(|name| name.trim().to_string()).unwrap_or_else(|| {
+            format!("{}{}", template.name_prefix, next_coach_number(&names, template.name_prefix))
+        });
+        position += 1;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO coach (coach_name, coach_type, fare, train_id, position)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&coach_name)
+        .bind(&coach.coach_type)
+        .bind(coach.fare)
+        .bind(train_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await;
+
+        let coach_id = match inserted {
+            Ok(res) => res.last_insert_id() as i64,
+            Err(e) => {
+                tracing::error!("Error creating coach {}: {:?}", coach_name, e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to create coach",
+                    "details": e.to_string()
+                })));
+            }
+        };
+
+        let seats = layout_seats(template, capacity);
+        let mut seat_insert: QueryBuilder<MySql> =
+            QueryBuilder::new("INSERT INTO seat (seat_no, seat_type, coach_id, seat_category) ");
+        seat_insert.push_values(&seats, |mut row, (seat_no, seat_type, seat_category)| {
+            row.push_bind(*seat_no)
+                .push_bind(*seat_type)
+                .push_bind(coach_id)
+                .push_bind(*seat_category);
+        });
+
+        if let Err(e) = seat_insert.build().execute(&mut *tx).await {
+            tracing::error!("Error creating seats for coach {}: {:?}", coach_name, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create seats",
+                "details": e.to_string()
+            })));
+        }
+
+        created.push(serde_json::json!({
+            "coach_id": coach_id,
+            "coach_name": coach_name,
+            "coach_type": coach.coach_type,
+            "position": position,
+            "total_seats": seats.len(),
+            "rac_seats": seats.iter().filter(|s| s.2 == "RAC").count()
+        }));
+        names.push(coach_name);
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "message": "Train composition created successfully",
+        "train_id": train_id,
+        "data": created
+    })))
+}
+
+// PUT /api/trains/composition/id/{train_id}/order
+pub async fn reorder_train_coaches(
+    pool: web::Data<MySqlPool>,
+    train_id: web::Path<i64>,
+    payload: web::Json<ReorderCoaches>,
+) -> Result<impl Responder, Error> {
+    let train_id = train_id.into_inner();
+
+    let current: Vec<i64> = sqlx::query_scalar("SELECT coach_id FROM coach WHERE train_id = ?")
+        .bind(train_id)
+        .fetch_all(pool.get_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching coaches: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch coaches")
+        })?;
+
+    let current: HashSet<i64> = current.into_iter().collect();
+    let requested: HashSet<i64> = payload.coach_ids.iter().copied().collect();
+
+    if current != requested || requested.len() != payload.coach_ids.len() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "coach_ids must list every coach of the train exactly once"
+        })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    for (index, coach_id) in payload.coach_ids.iter().enumerate() {
+        let res = sqlx::query("UPDATE coach SET position = ? WHERE coach_id = ? AND train_id = ?")
+            .bind(index as i32 + 1)
+            .bind(coach_id)
+            .bind(train_id)
+            .execute(&mut *tx)
+            .await;
+
+        if let Err(e) = res {
+            tracing::error!("Error reordering coach {}: {:?}", coach_id, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reorder coaches",
+                "details": e.to_string()
+            })));
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Coaches reordered successfully",
+        "total_coaches": payload.coach_ids.len()
+    })))
+}
+
+// POST /api/trains/composition/id/{train_id}/attach
+pub async fn attach_coach(
+    pool: web::Data<MySqlPool>,
+    train_id: web::Path<i64>,
+    payload: web::Json<AttachCoach>,
+) -> Result<impl Responder, Error> {
+    let train_id = train_id.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let train_exists: Option<i64> = sqlx::query_scalar("SELECT train_id FROM train WHERE train_id = ? FOR UPDATE")
+        .bind(train_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching train: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch train")
+        })?;
+
+    if train_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Train not found" })));
+    }
+
+    let coach = sqlx::query_scalar::<_, Option<i64>>("SELECT train_id FROM coach WHERE coach_id = ? FOR UPDATE")
+        .bind(payload.coach_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching coach: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch coach")
+        })?;
+
+    match coach {
+        None => return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Coach not found" }))),
+        Some(Some(_)) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Coach is attached to a train, detach it first"
+            })));
+        }
+        Some(None) => {}
+    }
+
+    let last_position: Option<i32> = sqlx::query_scalar("SELECT MAX(position) FROM coach WHERE train_id = ?")
+        .bind(train_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching coach positions: {:?}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch coach positions")
+        })?;
+
+    let end = last_position.unwrap_or(0) + 1;
+    let position = payload.position.map(|p| p.clamp(1, end)).unwrap_or(end);
+
+    // Make room for the coach when it goes in the middle of the rake
+    let shifted = sqlx::query("UPDATE coach SET position = position + 1 WHERE train_id = ? AND position >= ?")
+        .bind(train_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await;
+
+    let attached = match shifted {
+        Ok(_) => sqlx::query("UPDATE coach SET train_id = ?, position = ? WHERE coach_id = ?")
+            .bind(train_id)
+            .bind(position)
+            .bind(payload.coach_id)
+            .execute(&mut *tx)
+            .await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = attached {
+        tracing::error!("Error attaching coach: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to attach coach",
+            "details": e.to_string()
+        })));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Coach attached successfully",
+        "coach_id": payload.coach_id,
+        "position": position
+    })))
+}
+
+// POST /api/trains/composition/id/{train_id}/detach/{coach_id}
+pub async fn detach_coach(
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(i64, i64)>,
+) -> Result<impl Responder, Error> {
+    let (train_id, coach_id) = path.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let position = sqlx::query_scalar::<_, Option<i32>>(
+        "SELECT position FROM coach WHERE coach_id = ? AND train_id = ? FOR UPDATE",
+    )
+    .bind(coach_id)
+    .bind(train_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Error fetching coach: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch coach")
+    })?;
+
+    let Some(position) = position else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Coach is not part of this train"
+        })));
+    };
+
+    // Passengers holding berths on upcoming journeys would lose their seats
+    let upcoming_bookings: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM booking b
+        JOIN seat s ON s.seat_id = b.seat_id
+        JOIN journey j ON j.journey_id = b.journey_id
+        WHERE s.coach_id = ?
+        AND j.start_time >= NOW()
+        AND b.booking_status <> 'CANCELLED'
+        "#,
+    )
+    .bind(coach_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Error checking coach bookings: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to check coach bookings")
+    })?;
+
+    if upcoming_bookings > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Coach has bookings on upcoming journeys",
+            "bookings": upcoming_bookings
+        })));
+    }
+
+    let detached = sqlx::query("UPDATE coach SET train_id = NULL, position = NULL WHERE coach_id = ?")
+        .bind(coach_id)
+        .execute(&mut *tx)
+        .await;
+
+    // Close the gap left in the rake
+    let closed = match (detached, position) {
+        (Ok(_), Some(position)) => sqlx::query("UPDATE coach SET position = position - 1 WHERE train_id = ? AND position > ?")
+            .bind(train_id)
+            .bind(position)
+            .execute(&mut *tx)
+            .await
+            .map(|_| ()),
+        (Ok(_), None) => Ok(()),
+        (Err(e), _) => Err(e),
+    };
+
+    if let Err(e) = closed {
+        tracing::error!("Error detaching coach: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to detach coach",
+            "details": e.to_string()
+        })));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Coach detached successfully",
+        "coach_id": coach_id
+    })))
+}
@@ -1,8 +1,26 @@
+use std::collections::BTreeMap;
+
 use actix_web::{web, Error, HttpResponse, Responder, Result};
-use chrono::NaiveDate;
-use sqlx::MySqlPool;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
 
-use crate::models::journey::{CreateJourney, JourneyBetweenStations, JourneyDetailedResponse, JourneyResponse, UpdateJourney};
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::journey::{
+    AffectedBooking, BookingImpact, CalendarDay, CalendarJourney, CancelJourney, CreateJourney, ImpactKind,
+    JourneyBetweenStations, JourneyCalendarRow, JourneyClass, JourneyDetailedResponse, JourneyResponse,
+    JourneySearchResult, RescheduleJourney, UpdateJourney, UpdateJourneyStatus,
+};
+use crate::services::availability::{batch_availability, fares_for_journeys, JourneySegment};
+use crate::services::journey_changes::{
+    assess_impact, cancel_with_refund, fetch_live_bookings, fetch_stops, full_refund, planned_stops, write_stops,
+    JourneyStop,
+};
+use crate::models::seat::{QuotaSeatCount, SetSeatQuota};
+use crate::services::local_time::{journey_day, local_date, local_day_range, station_offset};
+use crate::services::occupancy::push_id_list;
+use crate::services::metrics::Metrics;
+use crate::services::quota::{is_quota, prepare_chart};
+use crate::services::notifications::{enqueue_all, Notification, JOURNEY_CANCELLED, JOURNEY_DIVERTED, JOURNEY_RESCHEDULED};
 
 use super::utils::QueryParams;
 
@@ -30,7 +48,7 @@ pub async fn create_journey(
     match res {
         Ok(_) => Ok(HttpResponse::Created().finish()),
         Err(e) => {
-            eprintln!("Error creating journey: {:?}", e);
+            tracing::error!("Error creating journey: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create journey",
                 "details": e.to_string()
@@ -96,7 +114,7 @@ pub async fn get_all_journeys(
     }
     if train_no != 0 {
         query = query.bind(train_no);
-        println!("Train No: {}", train_no);
+        tracing::debug!("Train No: {}", train_no);
     }
     if !start_station_name.is_empty() {
         let like_start = format!("%{}%", start_station_name); // Add wildcards for LIKE
@@ -153,7 +171,7 @@ pub async fn get_all_journeys(
             })
         })),
         (Err(e), _) | (_, Err(e)) => {
-            eprintln!("Error fetching journeys: {:?}", e);
+            tracing::error!("Error fetching journeys: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch journeys",
                 "details": e.to_string()
@@ -184,7 +202,7 @@ pub async fn get_journeys_by_train(
     match res {
         Ok(journeys) => Ok(HttpResponse::Ok().json(journeys)),
         Err(e) => {
-            eprintln!("Error fetching journeys for train: {:?}", e);
+            tracing::error!("Error fetching journeys for train: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch journeys for train",
                 "details": e.to_string()
@@ -201,6 +219,25 @@ pub async fn update_journey(
 ) -> Result<impl Responder, Error> {
     let update = update.into_inner();
 
+    // Retiming a booked journey must go through /reschedule so passengers are told
+    let booked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM booking WHERE journey_id = ? AND booking_status IN ('CONFIRMED', 'PENDING')",
+    )
+    .bind(*journey_id)
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| {
+        tracing::error!("Error checking journey bookings: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to check journey bookings")
+    })?;
+
+    if booked > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Journey has bookings; use the reschedule endpoint to change it",
+            "bookings": booked
+        })));
+    }
+
     let res = sqlx::query!(
         r#"
         UPDATE journey
@@ -223,7 +260,7 @@ pub async fn update_journey(
     match res {
         Ok(_) => Ok(HttpResponse::Ok().finish()),
         Err(e) => {
-            eprintln!("Error updating journey: {:?}", e);
+            tracing::error!("Error updating journey: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update journey",
                 "details": e.to_string()
@@ -233,21 +270,87 @@ pub async fn update_journey(
 }
 
 
+// DELETE /api/journeys/id/{journey_id}/delete
+// Only journeys that never had a booking can be deleted; booked journeys are cancelled instead.
 pub async fn delete_journey(
     pool: web::Data<MySqlPool>,
     journey_id: web::Path<i64>,
 ) -> Result<impl Responder, Error> {
-    let res = sqlx::query!(
-        r#"DELETE FROM journey WHERE journey_id = ?"#,
-        *journey_id
+    let journey_id = journey_id.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    // Anything recorded against the journey keeps it; only an unused journey is deleted
+    let references = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM booking WHERE journey_id = ?),
+            (SELECT COUNT(*) FROM running_event WHERE journey_id = ?),
+            (SELECT COUNT(*) FROM running WHERE journey_id = ?),
+            (SELECT COUNT(*) FROM journey_seat_quota WHERE journey_id = ?),
+            (SELECT COUNT(*) FROM fare_quote WHERE journey_id = ?)
+        "#,
     )
-    .execute(pool.get_ref())
+    .bind(journey_id)
+    .bind(journey_id)
+    .bind(journey_id)
+    .bind(journey_id)
+    .bind(journey_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Error checking journey references: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to check journey references")
+    })?;
+
+    let (bookings, running_events, running, seat_quotas, fare_quotes) = references;
+    if bookings > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Journey has bookings; cancel it instead of deleting",
+            "bookings": bookings
+        })));
+    }
+    if running_events + running + seat_quotas + fare_quotes > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Journey has running records, seat quota overrides or fare quotes; cancel it instead of deleting",
+            "running_events": running_events,
+            "running": running,
+            "seat_quotas": seat_quotas,
+            "fare_quotes": fare_quotes
+        })));
+    }
+
+    let res = async {
+        sqlx::query("DELETE FROM schedule WHERE journey_id = ?")
+            .bind(journey_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM journey WHERE journey_id = ?")
+            .bind(journey_id)
+            .execute(&mut *tx)
+            .await
+    }
     .await;
 
     match res {
-        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Ok(done) if done.rows_affected() == 0 => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Journey not found"
+        }))),
+        Ok(_) => match tx.commit().await {
+            Ok(_) => Ok(HttpResponse::Ok().finish()),
+            Err(e) => {
+                tracing::error!("Transaction commit failed: {:?}", e);
+                Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Transaction commit failed",
+                    "details": e.to_string()
+                })))
+            }
+        },
         Err(e) => {
-            eprintln!("Error deleting journey: {:?}", e);
+            tracing::error!("Error deleting journey: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete journey",
                 "details": e.to_string()
@@ -256,6 +359,597 @@ pub async fn delete_journey(
     }
 }
 
+async fn fetch_journey_status(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>("SELECT journey_status FROM journey WHERE journey_id = ? FOR UPDATE")
+        .bind(journey_id)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+// POST /api/journeys/id/{journey_id}/cancel
+#[tracing::instrument(skip_all, fields(journey_id = *journey_id))]
+pub async fn cancel_journey(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+    payload: web::Json<CancelJourney>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can cancel journeys"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+    let reason = payload.into_inner().reason.unwrap_or_else(|| "Cancelled by the railway".to_string());
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let status = fetch_journey_status(&mut tx, journey_id).await.map_err(|e| {
+        tracing::error!("Error fetching journey: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch journey")
+    })?;
+
+    match status.as_deref() {
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Journey not found"
+            })));
+        }
+        Some("SCHEDULED") => {}
+        Some(status) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("A {} journey cannot be cancelled", status.to_lowercase())
+            })));
+        }
+    }
+
+    let res = async {
+        let cancelled = fetch_live_bookings(&mut tx, journey_id).await?;
+        cancel_with_refund(&mut tx, journey_id, &cancelled, JOURNEY_CANCELLED, &reason).await?;
+        sqlx::query(
+            "UPDATE journey SET journey_status = 'CANCELLED', cancel_reason = ?, cancelled_at = NOW() WHERE journey_id = ?",
+        )
+        .bind(&reason)
+        .bind(journey_id)
+        .execute(&mut *tx)
+        .await?;
+        Ok::<_, sqlx::Error>(cancelled)
+    }
+    .await;
+
+    let cancelled = match res {
+        Ok(cancelled) => cancelled,
+        Err(e) => {
+            tracing::error!("Error cancelling journey: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to cancel journey",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    let refunded: f32 = cancelled.iter().map(full_refund).sum();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Journey cancelled successfully",
+        "journey_id": journey_id,
+        "bookings_cancelled": cancelled.len(),
+        "total_refund": refunded
+    })))
+}
+
+// Impact of a reschedule request, computed against the locked current state.
+// Err carries the response to return (404/409/400).
+async fn reschedule_impact(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+    request: &RescheduleJourney,
+) -> Result<Result<(Vec<JourneyStop>, Vec<AffectedBooking>, Vec<BookingImpact>), HttpResponse>, sqlx::Error> {
+    match fetch_journey_status(tx, journey_id).await?.as_deref() {
+        None => {
+            return Ok(Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Journey not found"
+            }))));
+        }
+        Some("SCHEDULED") => {}
+        Some(status) => {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("A {} journey cannot be rescheduled", status.to_lowercase())
+            }))));
+        }
+    }
+
+    let current = fetch_stops(tx, journey_id).await?;
+    if current.is_empty() {
+        return Ok(Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Journey has no schedule to change"
+        }))));
+    }
+
+    let planned = match planned_stops(&current, request.shift_minutes, request.stops.as_deref()) {
+        Ok(planned) => planned,
+        Err(message) => {
+            return Ok(Err(HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))));
+        }
+    };
+
+    let bookings = fetch_live_bookings(tx, journey_id).await?;
+    let impact = assess_impact(&bookings, &current, &planned);
+    Ok(Ok((planned, bookings, impact)))
+}
+
+// POST /api/journeys/id/{journey_id}/reschedule/preview
+// Reports which bookings a shift or diversion would affect, without changing anything.
+pub async fn preview_reschedule(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+    request: web::Json<RescheduleJourney>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can reschedule journeys"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    // Rolled back on drop; the transaction only gives a consistent snapshot
+    match reschedule_impact(&mut tx, journey_id, &request).await {
+        Ok(Ok((planned, bookings, impact))) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "journey_id": journey_id,
+            "new_start_time": planned.first().and_then(|s| s.sched_tod),
+            "new_end_time": planned.last().and_then(|s| s.sched_toa),
+            "bookings_total": bookings.len(),
+            "bookings_affected": impact.len(),
+            "impact": impact
+        }))),
+        Ok(Err(response)) => Ok(response),
+        Err(e) => {
+            tracing::error!("Error assessing reschedule: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to assess reschedule",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/journeys/id/{journey_id}/reschedule
+// Applies a shift or diversion. Bookings that lose a station are cancelled with a
+// full refund; bookings whose times move are notified and may cancel for free.
+#[tracing::instrument(skip_all, fields(journey_id = *journey_id))]
+pub async fn apply_reschedule(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+    request: web::Json<RescheduleJourney>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can reschedule journeys"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+    let request = request.into_inner();
+    let reason = request.reason.clone().unwrap_or_else(|| "Timetable change".to_string());
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        let (planned, bookings, impact) = match reschedule_impact(&mut tx, journey_id, &request).await? {
+            Ok(assessed) => assessed,
+            Err(response) => return Ok(Err(response)),
+        };
+
+        write_stops(&mut tx, journey_id, &planned).await?;
+
+        let kind_of = |booking_id: i64| impact.iter().find(|i| i.booking_id == booking_id).map(|i| i.impact);
+        let (removed, retimed): (Vec<AffectedBooking>, Vec<AffectedBooking>) = bookings
+            .into_iter()
+            .filter(|b| kind_of(b.booking_id).is_some())
+            .partition(|b| kind_of(b.booking_id) == Some(ImpactKind::StationRemoved));
+
+        cancel_with_refund(&mut tx, journey_id, &removed, JOURNEY_DIVERTED, &reason).await?;
+
+        if !retimed.is_empty() {
+            let mut flag: QueryBuilder<MySql> =
+                QueryBuilder::new("UPDATE booking SET free_cancellation = TRUE WHERE booking_id IN (");
+            let mut ids = flag.separated(", ");
+            for booking in &retimed {
+                ids.push_bind(booking.booking_id);
+            }
+            ids.push_unseparated(")");
+            flag.build().execute(&mut *tx).await?;
+
+            let notifications: Vec<Notification> = retimed
+                .iter()
+                .filter_map(|b| {
+                    let change = impact.iter().find(|i| i.booking_id == b.booking_id)?;
+                    Some(Notification {
+                        email: b.email.clone(),
+                        pnr: b.pnr,
+                        event_type: JOURNEY_RESCHEDULED,
+                        payload: serde_json::json!({
+                            "journey_id": journey_id,
+                            "booking_id": b.booking_id,
+                            "reason": reason,
+                            "new_departure": change.new_departure,
+                            "new_arrival": change.new_arrival,
+                            "free_cancellation": true,
+                        }),
+                    })
+                })
+                .collect();
+            enqueue_all(&mut tx, &notifications).await?;
+        }
+
+        Ok::<_, sqlx::Error>(Ok((removed.len(), retimed.len(), impact)))
+    }
+    .await;
+
+    let (cancelled, retimed, impact) = match res {
+        Ok(Ok(applied)) => applied,
+        Ok(Err(response)) => return Ok(response),
+        Err(e) => {
+            tracing::error!("Error rescheduling journey: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reschedule journey",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Journey rescheduled successfully",
+        "journey_id": journey_id,
+        "bookings_cancelled": cancelled,
+        "bookings_retimed": retimed,
+        "impact": impact
+    })))
+}
+
+// PUT /api/journeys/id/{journey_id}/status
+// Moves a journey forward: SCHEDULED -> DEPARTED -> COMPLETED. Cancelling goes through /cancel.
+pub async fn update_journey_status(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+    payload: web::Json<UpdateJourneyStatus>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can change journey status"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+    let next = payload.journey_status.to_uppercase();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let current = fetch_journey_status(&mut tx, journey_id).await.map_err(|e| {
+        tracing::error!("Error fetching journey: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch journey")
+    })?;
+
+    let Some(current) = current else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Journey not found"
+        })));
+    };
+
+    let allowed = matches!(
+        (current.as_str(), next.as_str()),
+        ("SCHEDULED", "DEPARTED") | ("SCHEDULED", "COMPLETED") | ("DEPARTED", "COMPLETED")
+    );
+    if !allowed {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Cannot move journey from {} to {}", current, next)
+        })));
+    }
+
+    let res = sqlx::query("UPDATE journey SET journey_status = ? WHERE journey_id = ?")
+        .bind(&next)
+        .bind(journey_id)
+        .execute(&mut *tx)
+        .await;
+
+    if let Err(e) = res {
+        tracing::error!("Error updating journey status: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to update journey status",
+            "details": e.to_string()
+        })));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "journey_id": journey_id,
+        "journey_status": next
+    })))
+}
+
+// GET /api/journeys/{journey_id}/quotas
+// Seats per class and quota on this journey, and how many of them are unsold
+pub async fn get_journey_quotas(
+    pool: web::Data<MySqlPool>,
+    journey_id: web::Path<i64>,
+) -> Result<impl Responder, Error> {
+    let journey_id = journey_id.into_inner();
+
+    let res = async {
+        let chart_prepared_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT chart_prepared_at FROM journey WHERE journey_id = ?",
+        )
+        .bind(journey_id)
+        .fetch_optional(pool.get_ref())
+        .await?;
+
+        let counts = sqlx::query_as::<_, QuotaSeatCount>(
+            r#"
+            SELECT c.coach_type, q.quota, COUNT(*) AS seats, COUNT(*) - COUNT(b.booking_id) AS unsold
+            FROM (
+                SELECT s.seat_id, s.coach_id, seat_quota(j.journey_id, s.seat_id) AS quota
+                FROM seat s
+                JOIN coach c ON c.coach_id = s.coach_id
+                JOIN journey j ON j.train_id = c.train_id
+                WHERE j.journey_id = ?
+            ) q
+            JOIN coach c ON c.coach_id = q.coach_id
+            LEFT JOIN booking b ON b.booking_id = (
+                SELECT MIN(booking_id) FROM booking
+                WHERE seat_id = q.seat_id AND journey_id = ? AND booking_status IN ('CONFIRMED', 'PENDING')
+            )
+            GROUP BY c.coach_type, q.quota
+            ORDER BY c.coach_type, q.quota
+            "#,
+        )
+        .bind(journey_id)
+        .bind(journey_id)
+        .fetch_all(pool.get_ref())
+        .await?;
+
+        Ok::<_, sqlx::Error>(chart_prepared_at.map(|prepared| (prepared, counts)))
+    }
+    .await;
+
+    match res {
+        Ok(Some((chart_prepared_at, counts))) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "journey_id": journey_id,
+            "chart_prepared_at": chart_prepared_at,
+            "data": counts
+        }))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Journey not found"
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching journey quotas: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch journey quotas",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// PUT /api/journeys/{journey_id}/quotas
+// Overrides the quota of seats (or a whole coach) for this journey only
+pub async fn set_journey_seat_quota(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+    payload: web::Json<SetSeatQuota>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can change seat quotas"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+    let quota = payload.quota.to_uppercase();
+    if !is_quota(&quota) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown quota"
+        })));
+    }
+    if payload.coach_id.is_some() != payload.seat_ids.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Provide either coach_id or seat_ids"
+        })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        let chart_prepared = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT chart_prepared_at FROM journey WHERE journey_id = ? FOR UPDATE",
+        )
+        .bind(journey_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        match chart_prepared {
+            None => return Ok(Err(HttpResponse::NotFound().json(serde_json::json!({ "error": "Journey not found" })))),
+            Some(Some(_)) => {
+                return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Chart is already prepared; all seats are general quota"
+                }))))
+            }
+            Some(None) => {}
+        }
+
+        // Only seats of the journey's own train can be overridden
+        let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+            "INSERT INTO journey_seat_quota (journey_id, seat_id, quota) SELECT j.journey_id, s.seat_id, ",
+        );
+        query.push_bind(&quota);
+        query.push(
+            r#"
+            FROM seat s
+            JOIN coach c ON c.coach_id = s.coach_id
+            JOIN journey j ON j.train_id = c.train_id
+            WHERE j.journey_id = "#,
+        );
+        query.push_bind(journey_id);
+        match payload.coach_id {
+            Some(coach_id) => {
+                query.push(" AND s.coach_id = ");
+                query.push_bind(coach_id);
+            }
+            None => {
+                query.push(" AND s.seat_id IN (");
+                push_id_list(&mut query, &payload.seat_ids);
+            }
+        }
+        query.push(" ON DUPLICATE KEY UPDATE quota = VALUES(quota)");
+        query.build().execute(&mut *tx).await?;
+
+        Ok::<_, sqlx::Error>(Ok(()))
+    }
+    .await;
+
+    match res {
+        Ok(Ok(())) => {}
+        Ok(Err(response)) => return Ok(response),
+        Err(e) => {
+            tracing::error!("Error setting journey quota: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to set journey quota",
+                "details": e.to_string()
+            })));
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "journey_id": journey_id,
+        "quota": quota
+    })))
+}
+
+// POST /api/journeys/{journey_id}/chart
+// Prepares the chart now instead of waiting for the automatic run before departure
+#[tracing::instrument(skip_all, fields(journey_id = *journey_id))]
+pub async fn prepare_journey_chart(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can prepare charts"
+        })));
+    }
+
+    let journey_id = journey_id.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let current = fetch_journey_status(&mut tx, journey_id).await.map_err(|e| {
+        tracing::error!("Error fetching journey: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch journey")
+    })?;
+
+    match current.as_deref() {
+        Some("SCHEDULED") => {}
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Journey not found"
+            })));
+        }
+        Some(status) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("Cannot prepare the chart of a {} journey", status)
+            })));
+        }
+    }
+
+    let released = match prepare_chart(&mut tx, journey_id).await {
+        Ok(Some(released)) => released,
+        Ok(None) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Chart is already prepared"
+            })));
+        }
+        Err(e) => {
+            tracing::error!("Error preparing chart: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to prepare chart",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "journey_id": journey_id,
+        "released_seats": released
+    })))
+}
+
 pub async fn get_journey_by_id(
     pool: web::Data<MySqlPool>,
     journey_id: web::Path<i64>,
@@ -276,7 +970,7 @@ pub async fn get_journey_by_id(
         Ok(Some(journey)) => Ok(HttpResponse::Ok().json(journey)),
         Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Journey not found" }))),
         Err(e) => {
-            eprintln!("Error fetching journey: {:?}", e);
+            tracing::error!("Error fetching journey: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch journey",
                 "details": e.to_string()
@@ -286,10 +980,17 @@ pub async fn get_journey_by_id(
 }
 
 
+// GET /api/journeys/search?source_station_id=..&destination_station_id=..&journey_date=..&flex_days=..
+// `journey_date` is the local (IST) boarding date; flex_days widens it to ±N days.
+// Optional filters: train_type, reservation_category, depart_after/depart_before,
+// arrive_after/arrive_before (local HH:MM:SS), only_available; sort = departure | arrival | duration | fare
+#[tracing::instrument(skip_all, fields(from = ?query.source_station_id, to = ?query.destination_station_id))]
 pub async fn get_journey_by_stations(
     pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
     query: web::Query<QueryParams>,
 ) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["journeys"]).start_timer();
     let start_station_id = query.source_station_id.unwrap_or(0);
     let end_station_id = query.destination_station_id.unwrap_or(0);
     let date_of_journey = query.journey_date.unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
@@ -299,13 +1000,36 @@ pub async fn get_journey_by_stations(
         return Ok(HttpResponse::BadRequest().body("Missing or invalid station IDs"));
     }
 
-    let result = sqlx::query_as!(
-        JourneyBetweenStations,
+    let flex_days = query.flex_days.unwrap_or(0);
+    if !(0..=MAX_FLEX_DAYS).contains(&flex_days) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("flex_days must be between 0 and {}", MAX_FLEX_DAYS)
+        })));
+    }
+    let (window_start, window_end) = local_day_range(
+        date_of_journey - Duration::days(flex_days),
+        date_of_journey + Duration::days(flex_days),
+    );
+
+    let sort = match query.sort.as_deref() {
+        None | Some("departure") => "departure",
+        Some("arrival") => "arrival",
+        Some("duration") => "duration",
+        Some("fare") => "fare",
+        Some(_) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "sort must be one of departure, arrival, duration, fare"
+            })));
+        }
+    };
+
+    let result = sqlx::query_as::<_, JourneyBetweenStations>(
         r#"
         SELECT
             j.journey_id,
             t.train_id,
             t.train_name,
+            t.train_type,
             s1.station_id AS start_station_id,
             sched_start.sched_id AS start_schedule_id,
             s1.station_name AS start_station,
@@ -316,67 +1040,262 @@ pub async fn get_journey_by_stations(
             sched_end.sched_toa AS end_time,
             sched_start.stop_number AS start_stop_number,
             sched_end.stop_number AS end_stop_number,
-            TIME_TO_SEC(TIMEDIFF(sched_end.sched_toa, sched_start.sched_toa)) AS travel_time
+            TIME_TO_SEC(TIMEDIFF(sched_end.sched_toa, sched_start.sched_toa)) AS travel_time,
+            j.start_time AS journey_start_time
         FROM
             journey j
         JOIN
             train t ON j.train_id = t.train_id
         JOIN
-            (
-                SELECT
-                    sched_id,
-                    journey_id,
-                    station_id,
-                    sched_toa,
-                    sched_tod,
-                    stop_number
-                FROM
-                    schedule
-                WHERE
-                    station_id = ?
-                    AND DATE(sched_toa) = ?
-            ) sched_start ON j.journey_id = sched_start.journey_id
+            schedule sched_start ON j.journey_id = sched_start.journey_id
         JOIN
-            (
-                SELECT
-                    sched_id,
-                    journey_id,
-                    station_id,
-                    sched_toa,
-                    sched_tod,
-                    stop_number
-                FROM
-                    schedule
-                WHERE
-                    station_id = ?
-            ) sched_end ON j.journey_id = sched_end.journey_id
+            schedule sched_end ON j.journey_id = sched_end.journey_id
         JOIN
             station s1 ON sched_start.station_id = s1.station_id
         JOIN
             station s2 ON sched_end.station_id = s2.station_id
         WHERE
-            sched_start.stop_number < sched_end.stop_number
+            sched_start.station_id = ?
+            AND sched_start.sched_toa >= ?
+            AND sched_start.sched_toa < ?
+            AND sched_end.station_id = ?
+            AND sched_start.stop_number < sched_end.stop_number
+            AND j.journey_status <> 'CANCELLED'
+            AND (? IS NULL OR t.train_type = ?)
         "#,
-        &start_station_id,
-        date_of_journey,
-        &end_station_id,
     )
+    .bind(start_station_id)
+    .bind(window_start)
+    .bind(window_end)
+    .bind(end_station_id)
+    .bind(&query.train_type)
+    .bind(&query.train_type)
     .fetch_all(pool.get_ref())
     .await;
 
-    match result {
-        Ok(journeys) => Ok(HttpResponse::Ok().json({
-            serde_json::json!({
-                "page": 1,
-                "data": journeys,
-                "offset": 0,
-                "total": journeys.len(),
-                "limit": journeys.len(),
+    let journeys = match result {
+        Ok(journeys) => journeys,
+        Err(e) => {
+            tracing::error!("Database query error: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Error fetching journeys"));
+        }
+    };
+
+    let in_window = |time: Option<DateTime<Utc>>, after: Option<NaiveTime>, before: Option<NaiveTime>| {
+        let Some(time) = time.map(|t| t.with_timezone(&station_offset()).time()) else {
+            return after.is_none() && before.is_none();
+        };
+        after.is_none_or(|a| time >= a) && before.is_none_or(|b| time <= b)
+    };
+    let journeys: Vec<JourneyBetweenStations> = journeys
+        .into_iter()
+        .filter(|j| in_window(j.start_time, query.depart_after, query.depart_before))
+        .filter(|j| in_window(j.end_time, query.arrive_after, query.arrive_before))
+        .collect();
+
+    let segments: Vec<JourneySegment> = journeys
+        .iter()
+        .filter_map(|j| {
+            Some(JourneySegment {
+                journey_id: j.journey_id,
+                train_id: j.train_id?,
+                segment: (j.start_stop_number?, j.end_stop_number?),
             })
-        })),
+        })
+        .collect();
+
+    let lookups = async {
+        let availability = batch_availability(pool.get_ref(), &segments).await?;
+        let fares = fares_for_journeys(pool.get_ref(), &segments).await?;
+        Ok::<_, sqlx::Error>((availability, fares))
+    };
+    let (mut availability, fares) = match lookups.await {
+        Ok(lookups) => lookups,
         Err(e) => {
-            eprintln!("Database query error: {:?}", e);
-            Ok(HttpResponse::InternalServerError().body("Error fetching journeys"))
+            tracing::error!("Error computing search availability: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Error fetching journeys"));
         }
+    };
+
+    let only_available = query.only_available.unwrap_or(false);
+    let mut results: Vec<JourneySearchResult> = journeys
+        .into_iter()
+        .filter_map(|journey| {
+            let train_id = journey.train_id.unwrap_or_default();
+            let classes: Vec<JourneyClass> = availability
+                .remove(&journey.journey_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|c| query.reservation_category.as_ref().is_none_or(|class| &c.reservation_category == class))
+                .filter(|c| !only_available || c.is_available())
+                .map(|c| JourneyClass {
+                    fare: fares.get(&(train_id, c.reservation_category.clone())).copied(),
+                    availability: c,
+                })
+                .collect();
+
+            // A class filter or "only available" drops trains left without a class
+            let filtered = query.reservation_category.is_some() || only_available;
+            let origin = journey.journey_start_time.or(journey.start_time);
+            let day_of = |time: Option<DateTime<Utc>>| Some(journey_day(origin?, time?));
+            (!filtered || !classes.is_empty()).then(|| JourneySearchResult {
+                travel_date: journey.start_time.map(local_date),
+                boarding_day: day_of(journey.start_time),
+                alighting_day: day_of(journey.end_time),
+                journey,
+                classes,
+            })
+        })
+        .collect();
+
+    match sort {
+        "arrival" => results.sort_by_key(|r| r.journey.end_time),
+        "duration" => results.sort_by_key(|r| r.journey.travel_time.unwrap_or(i64::MAX)),
+        "fare" => results.sort_by(|a, b| {
+            let (a, b) = (a.cheapest_fare(), b.cheapest_fare());
+            a.unwrap_or(f32::MAX).total_cmp(&b.unwrap_or(f32::MAX))
+        }),
+        _ => results.sort_by_key(|r| r.journey.start_time),
+    }
+
+    Ok(HttpResponse::Ok().json({
+        serde_json::json!({
+            "page": 1,
+            "data": results,
+            "offset": 0,
+            "total": results.len(),
+            "limit": results.len(),
+        })
+    }))
+}
+
+const MAX_FLEX_DAYS: i64 = 3;
+const DEFAULT_CALENDAR_DAYS: i64 = 30;
+const MAX_CALENDAR_DAYS: i64 = 60;
+
+// GET /api/journeys/calendar?source_station_id=..&destination_station_id=..&reservation_category=..&journey_date=..&days=..
+// Availability and fare of one class on every journey between two stations, per
+// day, for `days` days starting at `journey_date` (default today).
+#[tracing::instrument(skip_all, fields(from = ?query.source_station_id, to = ?query.destination_station_id))]
+pub async fn get_journey_calendar(
+    pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["calendar"]).start_timer();
+    let (Some(source), Some(destination), Some(class)) =
+        (query.source_station_id, query.destination_station_id, query.reservation_category.clone())
+    else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "source_station_id, destination_station_id and reservation_category are required"
+        })));
+    };
+
+    let days = query.days.unwrap_or(DEFAULT_CALENDAR_DAYS);
+    if !(1..=MAX_CALENDAR_DAYS).contains(&days) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("days must be between 1 and {}", MAX_CALENDAR_DAYS)
+        })));
     }
+
+    let first_date = query.journey_date.unwrap_or_else(|| local_date(Utc::now()));
+    let last_date = first_date + Duration::days(days - 1);
+    let (window_start, window_end) = local_day_range(first_date, last_date);
+
+    let rows = sqlx::query_as::<_, JourneyCalendarRow>(
+        r#"
+        SELECT
+            j.journey_id,
+            t.train_id,
+            t.train_name,
+            ss.sched_toa AS start_time,
+            es.sched_toa AS end_time,
+            ss.stop_number AS start_stop_number,
+            es.stop_number AS end_stop_number
+        FROM journey j
+        JOIN train t ON t.train_id = j.train_id
+        JOIN schedule ss ON ss.journey_id = j.journey_id AND ss.station_id = ?
+        JOIN schedule es ON es.journey_id = j.journey_id AND es.station_id = ?
+        WHERE ss.sched_toa >= ? AND ss.sched_toa < ?
+        AND ss.stop_number < es.stop_number
+        AND j.journey_status <> 'CANCELLED'
+        ORDER BY ss.sched_toa
+        "#,
+    )
+    .bind(source)
+    .bind(destination)
+    .bind(window_start)
+    .bind(window_end)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Error fetching calendar journeys: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch journeys",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    let segments: Vec<JourneySegment> = rows
+        .iter()
+        .map(|r| JourneySegment {
+            journey_id: r.journey_id,
+            train_id: r.train_id,
+            segment: (r.start_stop_number, r.end_stop_number),
+        })
+        .collect();
+
+    let lookups = async {
+        let availability = batch_availability(pool.get_ref(), &segments).await?;
+        let fares = fares_for_journeys(pool.get_ref(), &segments).await?;
+        Ok::<_, sqlx::Error>((availability, fares))
+    };
+    let (mut availability, fares) = match lookups.await {
+        Ok(lookups) => lookups,
+        Err(e) => {
+            tracing::error!("Error computing calendar availability: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to compute availability",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    // Every day of the window is listed, including days without a train
+    let mut calendar: BTreeMap<NaiveDate, Vec<CalendarJourney>> =
+        (0..days).map(|offset| (first_date + Duration::days(offset), Vec::new())).collect();
+
+    for row in rows {
+        let class_availability = availability
+            .remove(&row.journey_id)
+            .and_then(|classes| classes.into_iter().find(|c| c.reservation_category == class));
+
+        let travel_date = row.start_time.map(local_date);
+        if let Some(day) = travel_date.and_then(|date| calendar.get_mut(&date)) {
+            day.push(CalendarJourney {
+                journey_id: row.journey_id,
+                train_id: row.train_id,
+                train_name: row.train_name,
+                start_time: row.start_time,
+                end_time: row.end_time,
+                fare: fares.get(&(row.train_id, class.clone())).copied(),
+                availability: class_availability,
+            });
+        }
+    }
+
+    let data: Vec<CalendarDay> = calendar.into_iter().map(|(date, journeys)| CalendarDay { date, journeys }).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "source_station_id": source,
+        "destination_station_id": destination,
+        "reservation_category": class,
+        "from_date": first_date,
+        "to_date": last_date,
+        "data": data
+    })))
 }
@@ -0,0 +1,23 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use sqlx::MySqlPool;
+
+use crate::services::metrics::Metrics;
+
+// GET /metrics
+pub async fn get_metrics(
+    pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
+) -> Result<impl Responder, Error> {
+    match metrics.render(pool.get_ref()) {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body)),
+        Err(e) => {
+            tracing::error!("Error encoding metrics: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to encode metrics",
+                "details": e
+            })))
+        }
+    }
+}
@@ -12,4 +12,10 @@ pub mod transaction_handler;
 pub mod user_handler;
 pub mod auth_handler;
 pub mod stats_handler;
+pub mod ticket_handler;
+pub mod tte_handler;
+pub mod promo_handler;
+pub mod traveller_handler;
+pub mod security_handler;
+pub mod metrics_handler;
 mod utils;
\ No newline at end of file
@@ -42,7 +42,7 @@ pub async fn get_pnr_status(
             "error": "PNR not found"
         }))),
         Err(e) => {
-            eprintln!("Database error fetching PNR: {:?}", e);
+            tracing::error!("Database error fetching PNR: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch PNR status",
                 "details": e.to_string()
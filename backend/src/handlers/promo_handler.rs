@@ -0,0 +1,177 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use sqlx::{types::Json, MySqlPool};
+
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::promo::{ApplyPromoCode, CreatePromoCode, SetPromoActive};
+use crate::services::promo::{evaluate, fetch_all_promos};
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Only admins can manage promo codes"
+    }))
+}
+
+// GET /api/promos
+pub async fn get_promo_codes(pool: web::Data<MySqlPool>, user: AuthUser) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(forbidden());
+    }
+
+    match fetch_all_promos(pool.get_ref()).await {
+        Ok(promos) => Ok(HttpResponse::Ok().json(promos)),
+        Err(e) => {
+            tracing::error!("Error fetching promo codes: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch promo codes",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/promos/add
+pub async fn create_promo_code(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    payload: web::Json<CreatePromoCode>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(forbidden());
+    }
+
+    let payload = payload.into_inner();
+    let code = payload.code.trim().to_uppercase();
+    let discount_type = payload.discount_type.to_uppercase();
+
+    let invalid = if code.is_empty() {
+        Some("code must not be empty")
+    } else if discount_type != "PERCENT" && discount_type != "FLAT" {
+        Some("discount_type must be PERCENT or FLAT")
+    } else if payload.discount_value <= 0.0 || (discount_type == "PERCENT" && payload.discount_value > 100.0) {
+        Some("discount_value must be positive, and at most 100 for PERCENT")
+    } else if payload.valid_from >= payload.valid_until {
+        Some("valid_from must be before valid_until")
+    } else {
+        None
+    };
+    if let Some(reason) = invalid {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO promo_code
+            (code, description, discount_type, discount_value, max_discount, min_amount, valid_from, valid_until,
+             train_types, classes, route_ids, usage_limit, per_user_limit)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&code)
+    .bind(&payload.description)
+    .bind(&discount_type)
+    .bind(payload.discount_value)
+    .bind(payload.max_discount)
+    .bind(payload.min_amount)
+    .bind(payload.valid_from)
+    .bind(payload.valid_until)
+    .bind(payload.train_types.map(Json))
+    .bind(payload.classes.map(Json))
+    .bind(payload.route_ids.map(Json))
+    .bind(payload.usage_limit)
+    .bind(payload.per_user_limit)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) => Ok(HttpResponse::Created().json(serde_json::json!({
+            "message": "Promo code created",
+            "promo_id": result.last_insert_id(),
+            "code": code
+        }))),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "A promo code with this code already exists"
+        }))),
+        Err(e) => {
+            tracing::error!("Error creating promo code: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create promo code",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// PUT /api/promos/{code}/active
+pub async fn set_promo_active(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    code: web::Path<String>,
+    payload: web::Json<SetPromoActive>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(forbidden());
+    }
+
+    let code = code.into_inner().to_uppercase();
+
+    let result = sqlx::query("UPDATE promo_code SET active = ? WHERE code = ?")
+        .bind(payload.active)
+        .bind(&code)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Promo code not found"
+        }))),
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "code": code,
+            "active": payload.active
+        }))),
+        Err(e) => {
+            tracing::error!("Error updating promo code: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update promo code",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/promos/apply
+// Previews the discount a code would give; nothing is redeemed
+pub async fn apply_promo_code(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    payload: web::Json<ApplyPromoCode>,
+) -> Result<impl Responder, Error> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let evaluated = evaluate(
+        &mut tx,
+        &payload.code,
+        &user.email,
+        payload.journey_id,
+        &payload.reservation_category,
+        payload.amount,
+    )
+    .await;
+
+    // Dropping the transaction releases the lock on the code
+    match evaluated {
+        Ok(Ok(discount)) => Ok(HttpResponse::Ok().json(discount)),
+        Ok(Err(reason)) => Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": reason
+        }))),
+        Err(e) => {
+            tracing::error!("Error checking promo code: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check promo code",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
@@ -1,8 +1,110 @@
+use std::collections::{BTreeMap, HashSet};
+
 use actix_web::{web, Error, HttpResponse, Responder};
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, Transaction};
 use serde_json::json;
-use crate::models::{route::{AddIntermediateStation, CreateRoute, RelativeStation, RouteDetailResponse, RouteResponse, RouteStation}, schedule::RoutesBetweenStations};
+use crate::models::{route::{AddIntermediateStation, CreateRoute, RelativeStation, ReorderRouteStations, RouteDetailResponse, RouteResponse, RouteStation, RouteStationRow}, schedule::RoutesBetweenStations};
 use crate::handlers::utils::QueryParams;
+use crate::services::metrics::Metrics;
+use crate::services::rail_network::{self, SharedRailNetwork};
+
+// Checks a route's station list (in stop order) before it is written to distance_map:
+// the source comes first at distance 0, stations appear once and distances strictly increase.
+fn validate_station_order(stations: &[(i64, f32)]) -> Result<(), String> {
+    let Some(&(_, first_distance)) = stations.first() else {
+        return Err("Route must have at least one station".to_string());
+    };
+
+    if first_distance != 0.0 {
+        return Err("Source station must be at distance 0".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for (station_id, _) in stations {
+        if !seen.insert(*station_id) {
+            return Err(format!("Station {} appears more than once on the route", station_id));
+        }
+    }
+
+    for pair in stations.windows(2) {
+        let ((_, prev), (station_id, distance)) = (pair[0], pair[1]);
+        if distance <= prev {
+            return Err(format!(
+                "Distance of station {} ({}) must be greater than the previous station's ({})",
+                station_id, distance, prev
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Stations of a route in stop order
+async fn fetch_route_stations<'e, E>(executor: E, route_id: i64) -> Result<Vec<RouteStation>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query_as::<_, RouteStation>(
+        r#"
+        SELECT dm.route_id, dm.station_id, s.station_name AS source_station_name, dm.distance, dm.order_from_start
+        FROM distance_map dm
+        LEFT JOIN station s ON s.station_id = dm.station_id
+        WHERE dm.route_id = ?
+        ORDER BY dm.distance
+        "#,
+    )
+    .bind(route_id)
+    .fetch_all(executor)
+    .await
+}
+
+// Recomputes order_from_start for every station of a route from their distances
+async fn renumber_route<'e, E>(executor: E, route_id: i64) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query(
+        r#"
+        UPDATE distance_map dm
+        JOIN (
+            SELECT station_id, ROW_NUMBER() OVER (ORDER BY distance) AS stop_order
+            FROM distance_map
+            WHERE route_id = ?
+        ) o ON o.station_id = dm.station_id
+        SET dm.order_from_start = o.stop_order
+        WHERE dm.route_id = ?
+        "#,
+    )
+    .bind(route_id)
+    .bind(route_id)
+    .execute(executor)
+    .await
+    .map(|_| ())
+}
+
+// Locks the route so edits of its stations apply one at a time, and returns its source station
+async fn lock_route(tx: &mut Transaction<'_, MySql>, route_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<i64>>("SELECT source_station_id FROM route WHERE route_id = ? FOR UPDATE")
+        .bind(route_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map(|source| source.flatten())
+}
+
+// Journeys still to run, or running, whose schedule follows the route
+async fn count_live_schedules(tx: &mut Transaction<'_, MySql>, route_id: i64) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(DISTINCT sc.journey_id)
+        FROM schedule sc
+        JOIN journey j ON j.journey_id = sc.journey_id
+        WHERE sc.route_id = ? AND j.journey_status IN ('SCHEDULED', 'DEPARTED')
+        "#,
+    )
+    .bind(route_id)
+    .fetch_one(&mut **tx)
+    .await
+}
 
 pub async fn get_routes(
     pool: web::Data<MySqlPool>,
@@ -44,7 +146,7 @@ pub async fn get_routes(
         .fetch_one(pool.get_ref())
         .await
         .map_err(|err| {
-            eprintln!("Count query failed: {}", err);
+            tracing::error!("Count query failed: {}", err);
             actix_web::error::ErrorInternalServerError("Failed to count routes")
         })?;
 
@@ -70,13 +172,13 @@ pub async fn get_routes(
         where_sql, limit, offset
     );
 
-    println!("Data SQL: {}", data_sql);
+    tracing::debug!("Data SQL: {}", data_sql);
 
     let routes: Vec<RouteResponse> = sqlx::query_as::<_, RouteResponse>(&data_sql)
         .fetch_all(pool.get_ref())
         .await
         .map_err(|err| {
-            eprintln!("Select query failed: {}", err);
+            tracing::error!("Select query failed: {}", err);
             actix_web::error::ErrorInternalServerError("Failed to fetch routes")
         })?;
 
@@ -94,10 +196,11 @@ pub async fn get_routes(
 
 pub async fn create_route(
     pool: web::Data<MySqlPool>,
+    network: web::Data<SharedRailNetwork>,
     route: web::Json<CreateRoute>,
 ) -> Result<impl Responder, Error> {
     let mut tx = pool.begin().await.map_err(|err| {
-        eprintln!("Failed to start transaction: {}", err);
+        tracing::error!("Failed to start transaction: {}", err);
         actix_web::error::ErrorInternalServerError("Transaction start failed")
     })?;
 
@@ -114,7 +217,7 @@ pub async fn create_route(
     let route_id = match insert_route_result {
         Ok(res) => res.last_insert_id() as i64,
         Err(err) => {
-            eprintln!("Failed to insert route: {}", err);
+            tracing::error!("Failed to insert route: {}", err);
             return Ok(HttpResponse::InternalServerError().json(json!({
                 "error": "Failed to insert route"
             })));
@@ -123,17 +226,18 @@ pub async fn create_route(
 
     // Step 2: Insert source station into distance_map
     let insert_source = sqlx::query!(
-        "INSERT INTO distance_map (route_id, station_id, distance)
-         VALUES (?, ?, ?)",
+        "INSERT INTO distance_map (route_id, station_id, distance, order_from_start)
+         VALUES (?, ?, ?, ?)",
         route_id,
         route.source_station_id,
-        0
+        0,
+        1
     )
     .execute(&mut *tx)
     .await;
 
     if let Err(err) = insert_source {
-        eprintln!("Failed to insert source into distance_map: {}", err);
+        tracing::error!("Failed to insert source into distance_map: {}", err);
         tx.rollback().await.ok();
         return Ok(HttpResponse::InternalServerError().json(json!({
             "error": "Failed to insert source station distance"
@@ -142,12 +246,14 @@ pub async fn create_route(
 
     // Commit transaction
     if let Err(err) = tx.commit().await {
-        eprintln!("Transaction commit failed: {}", err);
+        tracing::error!("Transaction commit failed: {}", err);
         return Ok(HttpResponse::InternalServerError().json(json!({
             "error": "Failed to commit transaction"
         })));
     }
 
+    rail_network::refresh(pool.get_ref(), &network).await;
+
     Ok(HttpResponse::Created().json(json!({
         "message": "Route created successfully",
         "route_id": route_id
@@ -156,32 +262,298 @@ pub async fn create_route(
 
 pub async fn add_intermediate_station(
     pool: web::Data<MySqlPool>,
+    network: web::Data<SharedRailNetwork>,
     path: web::Path<i64>, // route_id
     station: web::Json<AddIntermediateStation>,
 ) -> Result<impl Responder, Error> {
     let route_id = path.into_inner();
 
-    let result = sqlx::query!(
+    let mut tx = pool.begin().await.map_err(|err| {
+        tracing::error!("Failed to start transaction: {}", err);
+        actix_web::error::ErrorInternalServerError("Transaction start failed")
+    })?;
+
+    let source = lock_route(&mut tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to fetch route: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to fetch route")
+    })?;
+
+    if source.is_none() {
+        return Ok(HttpResponse::NotFound().json(json!({ "error": "Route not found" })));
+    }
+
+    let existing = fetch_route_stations(&mut *tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to fetch stations: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stations")
+    })?;
+
+    if existing.iter().any(|s| s.station_id == Some(station.station_id)) {
+        return Ok(HttpResponse::Conflict().json(json!({
+            "error": "Station is already on this route"
+        })));
+    }
+
+    if station.distance <= 0.0 {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "Distance of an intermediate station must be greater than 0"
+        })));
+    }
+
+    // The new station slots in by distance, so the combined list must still be strictly ordered
+    let mut stations: Vec<(i64, f32)> = existing
+        .iter()
+        .filter_map(|s| Some((s.station_id?, s.distance.unwrap_or(0.0))))
+        .collect();
+    stations.push((station.station_id, station.distance));
+    stations.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    if let Err(message) = validate_station_order(&stations) {
+        return Ok(HttpResponse::BadRequest().json(json!({ "error": message })));
+    }
+
+    let result = sqlx::query(
         "INSERT INTO distance_map (route_id, station_id, distance)
          VALUES (?, ?, ?)",
-        route_id,
-        station.station_id,
-        station.distance
     )
-    .execute(pool.get_ref())
+    .bind(route_id)
+    .bind(station.station_id)
+    .bind(station.distance)
+    .execute(&mut *tx)
     .await;
 
-    match result {
-        Ok(_) => Ok(HttpResponse::Created().json(json!({
-            "message": "Intermediate station added successfully"
-        }))),
+    if let Err(err) = result {
+        tracing::error!("Database error while inserting intermediate station: {}", err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to add intermediate station"
+        })));
+    }
+
+    if let Err(err) = renumber_route(&mut *tx, route_id).await {
+        tracing::error!("Failed to renumber route {}: {}", route_id, err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update station order"
+        })));
+    }
+
+    if let Err(err) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {}", err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to commit transaction"
+        })));
+    }
+
+    rail_network::refresh(pool.get_ref(), &network).await;
+
+    let order_from_start = stations
+        .iter()
+        .position(|(id, _)| *id == station.station_id)
+        .map(|i| i as i32 + 1);
+
+    Ok(HttpResponse::Created().json(json!({
+        "message": "Intermediate station added successfully",
+        "order_from_start": order_from_start
+    })))
+}
+
+pub async fn remove_route_station(
+    pool: web::Data<MySqlPool>,
+    network: web::Data<SharedRailNetwork>,
+    path: web::Path<(i64, i64)>, // route_id, station_id
+) -> Result<impl Responder, Error> {
+    let (route_id, station_id) = path.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|err| {
+        tracing::error!("Failed to start transaction: {}", err);
+        actix_web::error::ErrorInternalServerError("Transaction start failed")
+    })?;
+
+    let source = lock_route(&mut tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to fetch route: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to fetch route")
+    })?;
+
+    let Some(source_station_id) = source else {
+        return Ok(HttpResponse::NotFound().json(json!({ "error": "Route not found" })));
+    };
+
+    if source_station_id == station_id {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "The source station cannot be removed from its route"
+        })));
+    }
+
+    let live = count_live_schedules(&mut tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to check schedules: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to check schedules")
+    })?;
+
+    if live > 0 {
+        return Ok(HttpResponse::Conflict().json(json!({
+            "error": "Route has journeys scheduled on it; stations cannot be removed",
+            "journeys": live
+        })));
+    }
+
+    // Schedules built on this route still stop at the station
+    let scheduled: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM schedule WHERE route_id = ? AND station_id = ?",
+    )
+    .bind(route_id)
+    .bind(station_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| {
+        tracing::error!("Failed to check schedules: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to check schedules")
+    })?;
+
+    if scheduled > 0 {
+        return Ok(HttpResponse::Conflict().json(json!({
+            "error": "Station is used by scheduled journeys on this route",
+            "schedules": scheduled
+        })));
+    }
+
+    let deleted = sqlx::query("DELETE FROM distance_map WHERE route_id = ? AND station_id = ?")
+        .bind(route_id)
+        .bind(station_id)
+        .execute(&mut *tx)
+        .await;
+
+    match deleted {
+        Ok(res) if res.rows_affected() == 0 => {
+            return Ok(HttpResponse::NotFound().json(json!({
+                "error": "Station is not on this route"
+            })));
+        }
+        Ok(_) => {}
         Err(err) => {
-            eprintln!("Database error while inserting intermediate station: {}", err);
-            Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to add intermediate station"
-            })))
+            tracing::error!("Failed to remove station from route: {}", err);
+            return Ok(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to remove station"
+            })));
         }
     }
+
+    if let Err(err) = renumber_route(&mut *tx, route_id).await {
+        tracing::error!("Failed to renumber route {}: {}", route_id, err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update station order"
+        })));
+    }
+
+    if let Err(err) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {}", err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to commit transaction"
+        })));
+    }
+
+    rail_network::refresh(pool.get_ref(), &network).await;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Station removed from route"
+    })))
+}
+
+pub async fn reorder_route_stations(
+    pool: web::Data<MySqlPool>,
+    network: web::Data<SharedRailNetwork>,
+    path: web::Path<i64>, // route_id
+    payload: web::Json<ReorderRouteStations>,
+) -> Result<impl Responder, Error> {
+    let route_id = path.into_inner();
+
+    let mut tx = pool.begin().await.map_err(|err| {
+        tracing::error!("Failed to start transaction: {}", err);
+        actix_web::error::ErrorInternalServerError("Transaction start failed")
+    })?;
+
+    let source = lock_route(&mut tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to fetch route: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to fetch route")
+    })?;
+
+    let Some(source_station_id) = source else {
+        return Ok(HttpResponse::NotFound().json(json!({ "error": "Route not found" })));
+    };
+
+    // Scheduled stop times were worked out from the current order and distances
+    let live = count_live_schedules(&mut tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to check schedules: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to check schedules")
+    })?;
+
+    if live > 0 {
+        return Ok(HttpResponse::Conflict().json(json!({
+            "error": "Route has journeys scheduled on it; stations cannot be reordered",
+            "journeys": live
+        })));
+    }
+
+    let existing = fetch_route_stations(&mut *tx, route_id).await.map_err(|err| {
+        tracing::error!("Failed to fetch stations: {}", err);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stations")
+    })?;
+
+    let stations: Vec<(i64, f32)> = payload
+        .stations
+        .iter()
+        .map(|s| (s.station_id, s.distance))
+        .collect();
+
+    let current: HashSet<i64> = existing.iter().filter_map(|s| s.station_id).collect();
+    let requested: HashSet<i64> = stations.iter().map(|(id, _)| *id).collect();
+
+    if current != requested || stations.len() != existing.len() {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "Reorder must list exactly the stations currently on the route"
+        })));
+    }
+
+    if stations.first().map(|(id, _)| *id) != Some(source_station_id) {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "The route's source station must stay first"
+        })));
+    }
+
+    if let Err(message) = validate_station_order(&stations) {
+        return Ok(HttpResponse::BadRequest().json(json!({ "error": message })));
+    }
+
+    for (index, (station_id, distance)) in stations.iter().enumerate() {
+        let res = sqlx::query(
+            "UPDATE distance_map SET distance = ?, order_from_start = ?
+             WHERE route_id = ? AND station_id = ?",
+        )
+        .bind(distance)
+        .bind(index as i32 + 1)
+        .bind(route_id)
+        .bind(station_id)
+        .execute(&mut *tx)
+        .await;
+
+        if let Err(err) = res {
+            tracing::error!("Failed to update station {} on route {}: {}", station_id, route_id, err);
+            return Ok(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to reorder stations"
+            })));
+        }
+    }
+
+    if let Err(err) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {}", err);
+        return Ok(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to commit transaction"
+        })));
+    }
+
+    rail_network::refresh(pool.get_ref(), &network).await;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Route stations reordered successfully",
+        "total_stations": stations.len()
+    })))
 }
 
 
@@ -201,23 +573,15 @@ pub async fn get_route_stations(
     .fetch_one(pool.get_ref())
     .await
     .map_err(|err| {
-        eprintln!("Failed to fetch route: {}", err);
+        tracing::error!("Failed to fetch route: {}", err);
         actix_web::error::ErrorInternalServerError("Failed to fetch route")
     })?;
 
     // 2. Get station details for this route
-    let stations: Vec<RouteStation> = sqlx::query_as!(
-        RouteStation,
-        r#"SELECT route_id, station_id, (SELECT station_name FROM station s WHERE s.station_id = dm.station_id) AS source_station_name,
-        distance FROM distance_map dm
-        WHERE route_id = ?
-        ORDER BY distance"#,
-        route_id
-    )
-    .fetch_all(pool.get_ref())
+    let stations: Vec<RouteStation> = fetch_route_stations(pool.get_ref(), route_id)
     .await
     .map_err(|err| {
-        eprintln!("Failed to fetch stations: {}", err);
+        tracing::error!("Failed to fetch stations: {}", err);
         actix_web::error::ErrorInternalServerError("Failed to fetch stations")
     })?;
 
@@ -243,8 +607,10 @@ pub async fn get_route_stations(
 
 pub async fn get_routes_between_stations(
     pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
     query: web::Query<QueryParams>,
 ) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["routes"]).start_timer();
     let Some(source_station_id) = query.source_station_id else {
         return Ok(HttpResponse::BadRequest().body("Missing source_station_id"));
     };
@@ -253,37 +619,87 @@ pub async fn get_routes_between_stations(
         return Ok(HttpResponse::BadRequest().body("Missing destination_station_id"));
     };
 
-    let routes: Vec<RoutesBetweenStations> = sqlx::query_as!(
-        RoutesBetweenStations,
-        r#"SELECT 
-            r.route_id,
-            r.route_name,
-            s1.station_id AS source_station_id,
-            s2.station_id AS destination_station_id,
-            ABS(s2.distance - s1.distance) AS distance
-        FROM 
-            route r
-        JOIN 
-            distance_map s1 ON r.route_id = s1.route_id
-        JOIN 
-            distance_map s2 ON r.route_id = s2.route_id
-        WHERE 
-            s1.station_id = ? AND 
-            s2.station_id = ?"#,
-        source_station_id,
-        destination_station_id
+    if source_station_id == destination_station_id {
+        return Ok(HttpResponse::BadRequest().body("Source and destination must be different"));
+    }
+
+    // Every station of every route that contains both stations
+    let rows: Vec<RouteStationRow> = sqlx::query_as::<_, RouteStationRow>(
+        r#"
+        SELECT dm.route_id, r.route_name, dm.station_id, s.station_name, dm.distance, dm.order_from_start
+        FROM distance_map dm
+        JOIN route r ON r.route_id = dm.route_id
+        LEFT JOIN station s ON s.station_id = dm.station_id
+        WHERE dm.route_id IN (
+            SELECT a.route_id
+            FROM distance_map a
+            JOIN distance_map b ON a.route_id = b.route_id
+            WHERE a.station_id = ? AND b.station_id = ?
+        )
+        ORDER BY dm.route_id, dm.distance
+        "#,
     )
+    .bind(source_station_id)
+    .bind(destination_station_id)
     .fetch_all(pool.get_ref())
     .await
     .map_err(|err| {
-        eprintln!("Failed to fetch routes: {}", err);
+        tracing::error!("Failed to fetch routes: {}", err);
         actix_web::error::ErrorInternalServerError("Failed to fetch routes")
     })?;
 
+    let mut by_route: BTreeMap<i64, Vec<RouteStationRow>> = BTreeMap::new();
+    for row in rows {
+        by_route.entry(row.route_id).or_default().push(row);
+    }
+
+    let mut routes: Vec<RoutesBetweenStations> = by_route
+        .into_values()
+        .filter_map(|stations| routes_between(&stations, source_station_id, destination_station_id))
+        .collect();
+
+    routes.sort_by(|a, b| a.distance.unwrap_or(0.0).total_cmp(&b.distance.unwrap_or(0.0)));
+
     // Always return valid JSON (even if empty)
     Ok(HttpResponse::Ok().json(routes))
 }
 
+// Slices one route's stations (ordered by distance) between two of its stations, in travel order
+fn routes_between(stations: &[RouteStationRow], from: i64, to: i64) -> Option<RoutesBetweenStations> {
+    let from_index = stations.iter().position(|s| s.station_id == from)?;
+    let to_index = stations.iter().position(|s| s.station_id == to)?;
+
+    let (first, last) = (from_index.min(to_index), from_index.max(to_index));
+    let mut stops: Vec<RouteStation> = stations[first..=last]
+        .iter()
+        .map(|s| RouteStation {
+            route_id: s.route_id,
+            station_id: Some(s.station_id),
+            source_station_name: s.station_name.clone(),
+            distance: s.distance,
+            order_from_start: s.order_from_start,
+        })
+        .collect();
+    if from_index > to_index {
+        stops.reverse();
+    }
+
+    let from_row = &stations[from_index];
+    let to_row = &stations[to_index];
+    let distance = (to_row.distance.unwrap_or(0.0) - from_row.distance.unwrap_or(0.0)).abs();
+
+    Some(RoutesBetweenStations {
+        route_id: Some(from_row.route_id),
+        route_name: from_row.route_name.clone(),
+        source_station_id: Some(from),
+        destination_station_id: Some(to),
+        distance: Some(distance as f64),
+        start_order: from_row.order_from_start,
+        end_order: to_row.order_from_start,
+        stops,
+    })
+}
+
 
 pub async fn get_relative_stations(
     pool: web::Data<MySqlPool>,
@@ -315,9 +731,132 @@ pub async fn get_relative_stations(
     .fetch_all(pool.get_ref())
     .await
     .map_err(|err| {
-        eprintln!("Database query error: {}", err);
+        tracing::error!("Database query error: {}", err);
         actix_web::error::ErrorInternalServerError("Failed to fetch station distances")
     })?;
 
     Ok(HttpResponse::Ok().json(stations))
 }
+
+
+pub async fn get_shortest_path(
+    network: web::Data<SharedRailNetwork>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["path"]).start_timer();
+    let (Some(source_station_id), Some(destination_station_id)) = (query.source_station_id, query.destination_station_id) else {
+        return Ok(HttpResponse::BadRequest().body("Missing source_station_id or destination_station_id"));
+    };
+
+    let network = network.read().unwrap_or_else(|e| e.into_inner());
+
+    match network.shortest_path(source_station_id, destination_station_id) {
+        Some(path) => Ok(HttpResponse::Ok().json(path)),
+        None => Ok(HttpResponse::NotFound().json(json!({
+            "error": "No path between the given stations"
+        }))),
+    }
+}
+
+pub async fn get_k_shortest_paths(
+    network: web::Data<SharedRailNetwork>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["paths"]).start_timer();
+    let (Some(source_station_id), Some(destination_station_id)) = (query.source_station_id, query.destination_station_id) else {
+        return Ok(HttpResponse::BadRequest().body("Missing source_station_id or destination_station_id"));
+    };
+
+    let k = query.k.unwrap_or(3).clamp(1, 10);
+    let network = network.read().unwrap_or_else(|e| e.into_inner());
+    let paths = network.k_shortest_paths(source_station_id, destination_station_id, k);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "data": paths,
+        "total": paths.len(),
+        "k": k
+    })))
+}
+
+pub async fn get_reachable_stations(
+    network: web::Data<SharedRailNetwork>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["reachable"]).start_timer();
+    let Some(station_id) = query.station_id else {
+        return Ok(HttpResponse::BadRequest().body("Missing station_id"));
+    };
+
+    let network = network.read().unwrap_or_else(|e| e.into_inner());
+
+    if !network.contains(station_id) {
+        return Ok(HttpResponse::NotFound().json(json!({
+            "error": "Station is not on any route"
+        })));
+    }
+
+    // With a destination, answer whether the two stations are connected
+    if let Some(destination_station_id) = query.destination_station_id {
+        let path = network.shortest_path(station_id, destination_station_id);
+        return Ok(HttpResponse::Ok().json(json!({
+            "source_station_id": station_id,
+            "destination_station_id": destination_station_id,
+            "reachable": path.is_some(),
+            "distance": path.map(|p| p.total_distance)
+        })));
+    }
+
+    let stations = network.reachable_from(station_id, query.max_distance);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "data": stations,
+        "total": stations.len()
+    })))
+}
+
+pub async fn rebuild_rail_network(
+    pool: web::Data<MySqlPool>,
+    network: web::Data<SharedRailNetwork>,
+) -> Result<impl Responder, Error> {
+    rail_network::refresh(pool.get_ref(), &network).await;
+
+    let network = network.read().unwrap_or_else(|e| e.into_inner());
+
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Rail network rebuilt",
+        "stations": network.station_count(),
+        "edges": network.edge_count()
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_increasing_distances_from_zero() {
+        assert!(validate_station_order(&[(1, 0.0), (2, 12.5), (3, 40.0)]).is_ok());
+        assert!(validate_station_order(&[(1, 0.0)]).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_route_and_nonzero_source() {
+        assert!(validate_station_order(&[]).is_err());
+        assert!(validate_station_order(&[(1, 5.0), (2, 10.0)]).is_err());
+    }
+
+    #[test]
+    fn rejects_repeated_stations() {
+        let err = validate_station_order(&[(1, 0.0), (2, 10.0), (1, 20.0)]).unwrap_err();
+        assert!(err.contains("Station 1"));
+    }
+
+    #[test]
+    fn rejects_distances_that_do_not_increase() {
+        assert!(validate_station_order(&[(1, 0.0), (2, 10.0), (3, 10.0)]).is_err());
+        assert!(validate_station_order(&[(1, 0.0), (2, 10.0), (3, 5.0)]).is_err());
+    }
+}
@@ -1,6 +1,11 @@
+use std::collections::HashMap;
+
 use actix_web::{web, Error, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
 use sqlx::MySqlPool;
-use crate::models::schedule::{CreateSchedule, Schedule, ScheduleJourney, UpdateSchedule};
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::route::RouteStop;
+use crate::models::schedule::{CreateSchedule, GenerateSchedule, GeneratedStop, Schedule, ScheduleJourney, UpdateSchedule};
 
 // POST /schedule
 pub async fn create_schedule(
@@ -31,7 +36,7 @@ pub async fn create_schedule(
             })
         })),
         Err(e) => {
-            eprintln!("Error creating schedule: {:?}", e);
+            tracing::error!("Error creating schedule: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to create schedule",
@@ -100,7 +105,7 @@ pub async fn get_schedule_by_journey(
     match result {
         Ok(data) => Ok(HttpResponse::Ok().json(data)),
         Err(e) => {
-            eprintln!("Error fetching schedule for journey {}: {:?}", journey_id, e);
+            tracing::error!("Error fetching schedule for journey {}: {:?}", journey_id, e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch schedule for journey",
@@ -130,7 +135,7 @@ pub async fn get_all_schedules(
     match result {
         Ok(data) => Ok(HttpResponse::Ok().json(data)),
         Err(e) => {
-            eprintln!("Error fetching schedules: {:?}", e);
+            tracing::error!("Error fetching schedules: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch schedules",
@@ -163,7 +168,7 @@ pub async fn get_schedule_by_id(
         Ok(Some(data)) => Ok(HttpResponse::Ok().json(data)),
         Ok(None) => Ok(HttpResponse::NotFound().finish()),
         Err(e) => {
-            eprintln!("Error fetching schedule: {:?}", e);
+            tracing::error!("Error fetching schedule: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch schedule",
@@ -208,7 +213,7 @@ pub async fn update_schedule(
             })
         })),
         Err(e) => {
-            eprintln!("Error updating schedule: {:?}", e);
+            tracing::error!("Error updating schedule: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to update schedule",
@@ -247,7 +252,7 @@ pub async fn delete_schedule(
             }
         }
         Err(e) => {
-            eprintln!("Error deleting schedule: {:?}", e);
+            tracing::error!("Error deleting schedule: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to delete schedule",
@@ -256,4 +261,350 @@ pub async fn delete_schedule(
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+// Average running speed (km/h) used when the request doesn't specify one
+fn default_avg_speed(train_type: Option<&str>) -> f32 {
+    match train_type {
+        Some("VB") => 95.0,
+        Some("SF") => 80.0,
+        Some("EX") => 65.0,
+        Some("IN") => 60.0,
+        Some("ML") => 55.0,
+        Some("MM") => 45.0,
+        _ => 60.0,
+    }
+}
+
+// Halt duration (minutes) at intermediate stops, keyed by station_type
+fn default_dwell_minutes() -> HashMap<String, i64> {
+    HashMap::from([
+        ("JN".to_string(), 10),
+        ("TM".to_string(), 15),
+        ("ST".to_string(), 2),
+        ("HT".to_string(), 1),
+    ])
+}
+
+// Walks the route stops in travel order and computes arrival/departure for each one.
+// The first stop departs at `departure`, the last stop only has an arrival.
+fn plan_stops(
+    stops: &[RouteStop],
+    departure: DateTime<Utc>,
+    avg_speed: f32,
+    dwell_minutes: &HashMap<String, i64>,
+    skip_halts: bool,
+) -> Vec<GeneratedStop> {
+    let origin_distance = stops.first().and_then(|s| s.distance).unwrap_or(0.0);
+    let last_index = stops.len().saturating_sub(1);
+
+    let mut planned: Vec<GeneratedStop> = Vec::new();
+    let mut prev_distance = 0.0_f32;
+    let mut prev_departure = departure;
+
+    for (i, stop) in stops.iter().enumerate() {
+        let is_terminal = i == 0 || i == last_index;
+        if skip_halts && !is_terminal && stop.station_type.as_deref() == Some("HT") {
+            continue;
+        }
+
+        let distance = (stop.distance.unwrap_or(0.0) - origin_distance).abs();
+
+        let (sched_toa, sched_tod) = if i == 0 {
+            (departure, departure)
+        } else {
+            let run_seconds = ((distance - prev_distance) / avg_speed * 3600.0).round() as i64;
+            let arrival = prev_departure + Duration::seconds(run_seconds);
+            let dwell = if i == last_index {
+                0
+            } else {
+                stop.station_type
+                    .as_ref()
+                    .and_then(|t| dwell_minutes.get(t))
+                    .copied()
+                    .unwrap_or(0)
+            };
+            (arrival, arrival + Duration::minutes(dwell))
+        };
+
+        planned.push(GeneratedStop {
+            station_id: stop.station_id,
+            station_name: stop.station_name.clone(),
+            station_type: stop.station_type.clone(),
+            stop_number: planned.len() as i32 + 1,
+            distance,
+            sched_toa,
+            sched_tod,
+        });
+
+        prev_distance = distance;
+        prev_departure = sched_tod;
+    }
+
+    planned
+}
+
+// POST /schedules/journey/{journey_id}/generate
+pub async fn generate_schedule_from_route(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<i64>,
+    payload: web::Json<GenerateSchedule>
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can generate schedules"
+        })));
+    }
+
+    let journey_id = path.into_inner();
+    let payload = payload.into_inner();
+
+    let journey = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<i64>, Option<i64>, Option<String>)>(
+        r#"
+        SELECT j.start_time, j.start_station_id, j.end_station_id, t.train_type
+        FROM journey j
+        LEFT JOIN train t ON t.train_id = j.train_id
+        WHERE j.journey_id = ?
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| {
+        tracing::error!("Error fetching journey {}: {:?}", journey_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch journey")
+    })?;
+
+    let Some((start_time, Some(start_station_id), Some(end_station_id), train_type)) = journey else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Journey not found" })));
+    };
+
+    let Some(departure) = payload.departure_time.or(start_time) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "departure_time is required when the journey has no start_time"
+        })));
+    };
+
+    let avg_speed = payload.avg_speed.unwrap_or_else(|| default_avg_speed(train_type.as_deref()));
+    if avg_speed <= 0.0 {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "avg_speed must be greater than zero"
+        })));
+    }
+
+    let mut dwell_minutes = default_dwell_minutes();
+    if let Some(overrides) = payload.dwell_minutes {
+        dwell_minutes.extend(overrides);
+    }
+
+    let route_stops = sqlx::query_as::<_, RouteStop>(
+        r#"
+        SELECT dm.station_id, s.station_name, s.station_type, dm.distance
+        FROM distance_map dm
+        JOIN station s ON s.station_id = dm.station_id
+        WHERE dm.route_id = ?
+        ORDER BY dm.order_from_start, dm.distance
+        "#,
+    )
+    .bind(payload.route_id)
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| {
+        tracing::error!("Error fetching route {} stations: {:?}", payload.route_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch route stations")
+    })?;
+
+    let start_index = route_stops.iter().position(|s| s.station_id == start_station_id);
+    let end_index = route_stops.iter().position(|s| s.station_id == end_station_id);
+
+    let (Some(start_index), Some(end_index)) = (start_index, end_index) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Journey start and end stations must both be on the route"
+        })));
+    };
+
+    if start_index == end_index {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Journey start and end stations must be different"
+        })));
+    }
+
+    // Trains may run the route in either direction
+    let (first, last) = (start_index.min(end_index), start_index.max(end_index));
+    let mut journey_stops: Vec<RouteStop> = route_stops
+        .into_iter()
+        .skip(first)
+        .take(last - first + 1)
+        .collect();
+    if start_index > end_index {
+        journey_stops.reverse();
+    }
+
+    let planned = plan_stops(
+        &journey_stops,
+        departure,
+        avg_speed,
+        &dwell_minutes,
+        payload.skip_halts.unwrap_or(false),
+    );
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    // Passengers are booked against the current stops; retiming them goes through /reschedule
+    let booked: i64 = match sqlx::query_scalar(
+        "SELECT COUNT(*) FROM booking WHERE journey_id = ? AND booking_status IN ('CONFIRMED', 'PENDING') FOR UPDATE",
+    )
+    .bind(journey_id)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(booked) => booked,
+        Err(e) => {
+            tracing::error!("Error checking journey bookings: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check journey bookings",
+                "details": e.to_string()
+            })));
+        }
+    };
+    if booked > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Journey has bookings; use the reschedule endpoint to change it",
+            "bookings": booked
+        })));
+    }
+
+    // Replace whatever stops the journey already had (including the trigger-created ones)
+    if let Err(e) = sqlx::query("DELETE FROM schedule WHERE journey_id = ?")
+        .bind(journey_id)
+        .execute(&mut *tx)
+        .await
+    {
+        tracing::error!("Error clearing schedule for journey {}: {:?}", journey_id, e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to clear existing schedule",
+            "details": e.to_string()
+        })));
+    }
+
+    for stop in &planned {
+        let res = sqlx::query(
+            r#"
+            INSERT INTO schedule (station_id, sched_toa, sched_tod, journey_id, stop_number, route_id)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(stop.station_id)
+        .bind(stop.sched_toa)
+        .bind(stop.sched_tod)
+        .bind(journey_id)
+        .bind(stop.stop_number)
+        .bind(payload.route_id)
+        .execute(&mut *tx)
+        .await;
+
+        if let Err(e) = res {
+            tracing::error!("Error inserting generated schedule: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to insert generated schedule",
+                "details": e.to_string()
+            })));
+        }
+    }
+
+    // Keep the journey's own times in line with the generated stops
+    let end_time = planned.last().map(|s| s.sched_toa).unwrap_or(departure);
+    if let Err(e) = sqlx::query("UPDATE journey SET start_time = ?, end_time = ? WHERE journey_id = ?")
+        .bind(departure)
+        .bind(end_time)
+        .bind(journey_id)
+        .execute(&mut *tx)
+        .await
+    {
+        tracing::error!("Error updating journey times: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to update journey times",
+            "details": e.to_string()
+        })));
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "message": "Schedule generated successfully",
+        "journey_id": journey_id,
+        "route_id": payload.route_id,
+        "avg_speed": avg_speed,
+        "total_stops": planned.len(),
+        "data": planned
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn stop(station_id: i64, station_type: &str, distance: f32) -> RouteStop {
+        RouteStop {
+            station_id,
+            station_name: None,
+            station_type: Some(station_type.to_string()),
+            distance: Some(distance),
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 10, hour, minute, 0).unwrap()
+    }
+
+    fn route() -> Vec<RouteStop> {
+        vec![stop(1, "JN", 0.0), stop(2, "ST", 60.0), stop(3, "HT", 90.0), stop(4, "TM", 150.0)]
+    }
+
+    #[test]
+    fn times_each_stop_from_distance_speed_and_dwell() {
+        let planned = plan_stops(&route(), at(10, 0), 60.0, &default_dwell_minutes(), false);
+
+        let times: Vec<_> = planned.iter().map(|s| (s.station_id, s.sched_toa, s.sched_tod)).collect();
+        assert_eq!(
+            times,
+            vec![
+                (1, at(10, 0), at(10, 0)),
+                (2, at(11, 0), at(11, 2)),
+                (3, at(11, 32), at(11, 33)),
+                (4, at(12, 33), at(12, 33)),
+            ]
+        );
+        assert_eq!(planned.last().unwrap().distance, 150.0);
+    }
+
+    #[test]
+    fn skipping_halts_keeps_terminals_and_renumbers() {
+        let planned = plan_stops(&route(), at(10, 0), 60.0, &default_dwell_minutes(), true);
+
+        let stops: Vec<_> = planned.iter().map(|s| (s.station_id, s.stop_number)).collect();
+        assert_eq!(stops, vec![(1, 1), (2, 2), (4, 3)]);
+        assert_eq!(planned[2].sched_toa, at(12, 32));
+    }
+
+    #[test]
+    fn distances_are_relative_to_the_first_stop() {
+        let stops = vec![stop(7, "ST", 100.0), stop(8, "ST", 160.0)];
+        let planned = plan_stops(&stops, at(10, 0), 60.0, &HashMap::new(), false);
+
+        assert_eq!(planned[0].distance, 0.0);
+        assert_eq!(planned[1].distance, 60.0);
+        assert_eq!(planned[1].sched_toa, at(11, 0));
+    }
+}
@@ -2,9 +2,11 @@ use actix_web::{
     web::{self, Json, Path},
     HttpResponse, Responder, Error
 };
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
 
-use crate::models::seat::{CreateSeat, Seat, SeatCategory, SeatCount, SeatType};
+use crate::models::seat::{CreateSeat, Seat, SeatCategory, SeatMapEntry, SeatOccupancy, SeatType, SetSeatQuota};
+use crate::services::occupancy::{fetch_journey_stops, fetch_segment_bookings, overlaps, push_id_list, seat_occupancy, segment_between};
+use crate::services::quota::{is_quota, GENERAL};
 
 use super::utils::QueryParams;
 
@@ -12,15 +14,23 @@ pub async fn create_seat(
     pool: web::Data<MySqlPool>,
     payload: Json<CreateSeat>,
 ) -> Result<impl Responder, Error> {
+    let quota = payload.quota.as_deref().unwrap_or(GENERAL).to_uppercase();
+    if !is_quota(&quota) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown quota"
+        })));
+    }
+
     let result = sqlx::query!(
         r#"
-        INSERT INTO seat (seat_no, seat_type, coach_id, seat_category)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO seat (seat_no, seat_type, coach_id, seat_category, quota)
+        VALUES (?, ?, ?, ?, ?)
         "#,
         payload.seat_no,
         &payload.seat_type as &SeatType,
         payload.coach_id,
         &payload.seat_category as &SeatCategory,
+        quota,
     )
     .execute(pool.get_ref())
     .await;
@@ -28,7 +38,7 @@ pub async fn create_seat(
     match result {
         Ok(_) => Ok(HttpResponse::Created().finish()),
         Err(err) => {
-            eprintln!("Error creating seat: {:?}", err);
+            tracing::error!("Error creating seat: {:?}", err);
             Ok(HttpResponse::InternalServerError().finish())
         }
     }
@@ -49,7 +59,7 @@ pub async fn get_seats_by_coach(
     let seats = sqlx::query_as!(
         Seat,
         r#"
-        SELECT seat_id, seat_no, seat_type, coach_id, seat_category
+        SELECT seat_id, seat_no, seat_type, coach_id, seat_category, quota
         FROM seat
         WHERE coach_id = ?
         LIMIT ? OFFSET ?
@@ -83,88 +93,169 @@ pub async fn get_seats_by_coach(
             })))
         },
         (Err(e), _) | (_, Err(e)) => {
-            eprintln!("Error fetching seats: {:?}", e);
+            tracing::error!("Error fetching seats: {:?}", e);
             Ok(HttpResponse::InternalServerError().finish())
         }
     }
 }
 
-pub async fn get_cnf_seat_count_by_coach_category(
+// PUT /api/seats/quota
+// Sets the default quota of seats on every journey of their coach
+pub async fn set_seat_quota(
     pool: web::Data<MySqlPool>,
-    train_id: Path<i64>,
+    payload: Json<SetSeatQuota>,
 ) -> Result<impl Responder, Error> {
-    let train_id = train_id.into_inner();
+    let quota = payload.quota.to_uppercase();
+    if !is_quota(&quota) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown quota"
+        })));
+    }
 
-    let results = sqlx::query_as!(
-        SeatCount,
-        r#"
-        SELECT
-            ct.coach_type AS reservation_category,
-            COUNT(s.seat_id) AS seat_count
-        FROM (
-            SELECT 'SL' AS coach_type
-            UNION ALL SELECT 'AC3'
-            UNION ALL SELECT 'AC2'
-            UNION ALL SELECT 'AC1'
-            UNION ALL SELECT 'CC'
-            UNION ALL SELECT 'FC'
-            UNION ALL SELECT '2S'
-        ) ct
-        LEFT JOIN coach c ON c.coach_type = ct.coach_type AND c.train_id = ?
-        LEFT JOIN seat s ON s.coach_id = c.coach_id AND s.seat_category = 'CNF'
-        GROUP BY ct.coach_type
-        ORDER BY ct.coach_type
-        "#,
-        train_id
-    )
-    .fetch_all(pool.get_ref())
-    .await;
+    let result = match (payload.coach_id, payload.seat_ids.is_empty()) {
+        (Some(coach_id), true) => {
+            sqlx::query("UPDATE seat SET quota = ? WHERE coach_id = ?")
+                .bind(&quota)
+                .bind(coach_id)
+                .execute(pool.get_ref())
+                .await
+        }
+        (None, false) => {
+            let mut query: QueryBuilder<MySql> = QueryBuilder::new("UPDATE seat SET quota = ");
+            query.push_bind(&quota);
+            query.push(" WHERE seat_id IN (");
+            push_id_list(&mut query, &payload.seat_ids);
+            query.build().execute(pool.get_ref()).await
+        }
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Provide either coach_id or seat_ids"
+            })));
+        }
+    };
 
-    match results {
-        Ok(counts) => Ok(HttpResponse::Ok().json(counts)),
+    match result {
+        Ok(result) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "quota": quota,
+            "updated": result.rows_affected()
+        }))),
         Err(e) => {
-            eprintln!("Error fetching CNF seat count by coach category: {:?}", e);
-            Ok(HttpResponse::InternalServerError().finish())
+            tracing::error!("Error setting seat quota: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to set seat quota",
+                "details": e.to_string()
+            })))
         }
     }
 }
 
-pub async fn get_rac_seat_count_by_coach_category(
+// GET /api/journeys/{journey_id}/coaches/{coach_id}/seatmap?from=..&to=..
+#[tracing::instrument(skip_all, fields(journey_id = path.0, coach_id = path.1))]
+pub async fn get_journey_seat_map(
     pool: web::Data<MySqlPool>,
-    train_id: Path<i64>,
+    path: Path<(i64, i64)>,
+    query: web::Query<QueryParams>,
 ) -> Result<impl Responder, Error> {
-    let train_id = train_id.into_inner();
+    let (journey_id, coach_id) = path.into_inner();
 
-    let results = sqlx::query_as!(
-        SeatCount,
+    // The coach must belong to the train running this journey
+    let coach = sqlx::query_as::<_, (Option<String>, Option<String>)>(
         r#"
-        SELECT
-            ct.coach_type AS reservation_category,
-            COUNT(s.seat_id) AS seat_count
-        FROM (
-            SELECT 'SL' AS coach_type
-            UNION ALL SELECT 'AC3'
-            UNION ALL SELECT 'AC2'
-            UNION ALL SELECT 'AC1'
-            UNION ALL SELECT 'CC'
-            UNION ALL SELECT 'FC'
-            UNION ALL SELECT '2S'
-        ) ct
-        LEFT JOIN coach c ON c.coach_type = ct.coach_type AND c.train_id = ?
-        LEFT JOIN seat s ON s.coach_id = c.coach_id AND s.seat_category = 'RAC'
-        GROUP BY ct.coach_type
-        ORDER BY ct.coach_type
+        SELECT c.coach_name, c.coach_type
+        FROM coach c
+        JOIN journey j ON j.train_id = c.train_id
+        WHERE j.journey_id = ? AND c.coach_id = ?
         "#,
-        train_id
     )
-    .fetch_all(pool.get_ref())
+    .bind(journey_id)
+    .bind(coach_id)
+    .fetch_optional(pool.get_ref())
     .await;
 
-    match results {
-        Ok(counts) => Ok(HttpResponse::Ok().json(counts)),
+    let (coach_name, coach_type) = match coach {
+        Ok(Some(coach)) => coach,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Coach is not part of this journey's train"
+            })));
+        }
         Err(e) => {
-            eprintln!("Error fetching RAC seat count by coach category: {:?}", e);
-            Ok(HttpResponse::InternalServerError().finish())
+            tracing::error!("Error fetching coach for seat map: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().finish());
         }
-    }
+    };
+
+    let stops = match fetch_journey_stops(pool.get_ref(), journey_id).await {
+        Ok(stops) => stops,
+        Err(e) => {
+            tracing::error!("Error fetching journey stops: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    let Some(segment) = segment_between(&stops, query.from, query.to) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "from and to must be stops of this journey, in travel order"
+        })));
+    };
+
+    let seats = sqlx::query_as::<_, Seat>(
+        r#"
+        SELECT seat_id, seat_no, seat_type, coach_id, seat_category, seat_quota(?, seat_id) AS quota
+        FROM seat
+        WHERE coach_id = ?
+        ORDER BY seat_no
+        "#,
+    )
+    .bind(journey_id)
+    .bind(coach_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let bookings = fetch_segment_bookings(pool.get_ref(), journey_id, Some(coach_id)).await;
+
+    let (seats, bookings) = match (seats, bookings) {
+        (Ok(seats), Ok(bookings)) => (seats, bookings),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Error building seat map: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().finish());
+        }
+    };
+
+    let seat_map: Vec<SeatMapEntry> = seats
+        .into_iter()
+        .map(|seat| {
+            let occupants: Vec<_> = bookings
+                .iter()
+                .filter(|b| b.seat_id == Some(seat.seat_id) && overlaps(b, segment))
+                .collect();
+            SeatMapEntry {
+                seat_id: seat.seat_id,
+                seat_no: seat.seat_no,
+                occupancy: seat_occupancy(seat.seat_category.as_deref(), &occupants),
+                occupants: occupants.len(),
+                seat_type: seat.seat_type,
+                seat_category: seat.seat_category,
+                quota: seat.quota,
+            }
+        })
+        .collect();
+
+    let count = |state: SeatOccupancy| seat_map.iter().filter(|s| s.occupancy == state).count();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "journey_id": journey_id,
+        "coach_id": coach_id,
+        "coach_name": coach_name,
+        "coach_type": coach_type,
+        "from_stop": segment.0,
+        "to_stop": segment.1,
+        "summary": {
+            "free": count(SeatOccupancy::Free),
+            "booked": count(SeatOccupancy::Booked),
+            "rac_shared": count(SeatOccupancy::RacShared),
+            "held": count(SeatOccupancy::Held),
+        },
+        "data": seat_map,
+    })))
 }
@@ -0,0 +1,144 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::security::{LoginFailure, SecurityEvent, SecurityEventQuery};
+use crate::services::login_guard::{self, ACCOUNT, IP};
+
+fn admin_only() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Only an admin can manage sign-in security"
+    }))
+}
+
+// GET /api/security/events
+pub async fn get_security_events(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    query: web::Query<SecurityEventQuery>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(admin_only());
+    }
+
+    let mut builder = QueryBuilder::<MySql>::new(
+        "SELECT event_id, event_type, email, client_ip, details, created_at FROM security_event WHERE 1 = 1",
+    );
+    if let Some(email) = &query.email {
+        builder.push(" AND email = ").push_bind(email);
+    }
+    if let Some(client_ip) = &query.client_ip {
+        builder.push(" AND client_ip = ").push_bind(client_ip);
+    }
+    if let Some(event_type) = &query.event_type {
+        builder.push(" AND event_type = ").push_bind(event_type.to_uppercase());
+    }
+    builder
+        .push(" ORDER BY created_at DESC, event_id DESC LIMIT ")
+        .push_bind(query.limit.unwrap_or(100).min(1000));
+
+    match builder.build_query_as::<SecurityEvent>().fetch_all(pool.get_ref()).await {
+        Ok(events) => Ok(HttpResponse::Ok().json(events)),
+        Err(e) => {
+            tracing::error!("Error fetching security events: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch security events",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// GET /api/security/lockouts
+pub async fn get_lockouts(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(admin_only());
+    }
+
+    let lockouts = sqlx::query_as::<_, LoginFailure>(
+        r#"
+        SELECT scope, subject, failures, first_failure_at, last_failure_at, locked_until
+        FROM login_failure
+        WHERE locked_until > NOW()
+        ORDER BY locked_until DESC
+        "#,
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match lockouts {
+        Ok(lockouts) => Ok(HttpResponse::Ok().json(lockouts)),
+        Err(e) => {
+            tracing::error!("Error fetching lockouts: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch lockouts",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+async fn unlock(pool: &MySqlPool, admin: &AuthUser, scope: &str, subject: &str) -> HttpResponse {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let cleared = login_guard::clear(&mut *tx, scope, subject).await?;
+        if cleared {
+            let (event_type, email, client_ip) = if scope == ACCOUNT {
+                ("ACCOUNT_UNLOCKED", Some(subject), None)
+            } else {
+                ("IP_UNLOCKED", None, Some(subject))
+            };
+            login_guard::audit(&mut *tx, event_type, email, client_ip, serde_json::json!({ "by": admin.email })).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(cleared)
+    }
+    .await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Unlocked",
+            "scope": scope,
+            "subject": subject
+        })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No failed sign-ins recorded",
+            "scope": scope,
+            "subject": subject
+        })),
+        Err(e) => {
+            tracing::error!("Error unlocking {} {}: {:?}", scope, subject, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to unlock",
+                "details": e.to_string()
+            }))
+        }
+    }
+}
+
+// POST /api/security/accounts/{email}/unlock
+pub async fn unlock_account(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(admin_only());
+    }
+    Ok(unlock(pool.get_ref(), &user, ACCOUNT, &path.into_inner()).await)
+}
+
+// POST /api/security/ips/{ip}/unlock
+pub async fn unlock_ip(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    if !user.is_admin() {
+        return Ok(admin_only());
+    }
+    Ok(unlock(pool.get_ref(), &user, IP, &path.into_inner()).await)
+}
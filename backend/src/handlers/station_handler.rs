@@ -1,32 +1,232 @@
+use std::collections::HashMap;
+
 use actix_web::{web, HttpResponse, Responder, Error};
 
-use sqlx::{mysql::MySqlArguments, query, Arguments, MySqlPool};
+use sqlx::{mysql::MySqlArguments, Arguments, MySql, MySqlPool, QueryBuilder};
 
-use crate::models::station::{CreateStation, StationResponse};
+use crate::models::station::{CreateStation, NearbyStation, StationAlias, StationResponse, UpdateStation};
+use crate::services::metrics::Metrics;
+use crate::services::station_search::{haversine_km, rank_stations};
 
 use super::utils::QueryParams;
 
+const STATION_COLUMNS: &str = "station_id, station_name, station_type, station_code, latitude, longitude, state, zone";
+
+// Most stations ranked for one suggestion request
+const SUGGEST_CANDIDATES: i64 = 200;
+
+// Codes are short uppercase identifiers such as "NDLS" or "R"
+fn normalize_station_code(code: &str) -> Result<String, String> {
+    let code = code.trim().to_uppercase();
+    if code.is_empty() || code.len() > 8 || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("station_code must be 1-8 letters or digits".to_string());
+    }
+    Ok(code)
+}
+
+fn validate_coordinates(latitude: Option<f64>, longitude: Option<f64>) -> Result<(), String> {
+    if latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat)) {
+        return Err("latitude must be between -90 and 90".to_string());
+    }
+    if longitude.is_some_and(|lon| !(-180.0..=180.0).contains(&lon)) {
+        return Err("longitude must be between -180 and 180".to_string());
+    }
+    Ok(())
+}
+
+// Escapes LIKE wildcards so user input only ever matches literally
+fn like_prefix(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("{}%", escaped)
+}
+
+async fn fetch_aliases(pool: &MySqlPool, station_ids: &[i64]) -> Result<HashMap<i64, Vec<String>>, sqlx::Error> {
+    if station_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut builder = QueryBuilder::<MySql>::new("SELECT station_id, alias_name FROM station_alias WHERE station_id IN (");
+    let mut ids = builder.separated(", ");
+    for id in station_ids {
+        ids.push_bind(*id);
+    }
+    builder.push(") ORDER BY alias_name");
+    let rows = builder.build_query_as::<StationAlias>().fetch_all(pool).await?;
+
+    let mut aliases: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        aliases.entry(row.station_id).or_default().push(row.alias_name);
+    }
+    Ok(aliases)
+}
+
 // POST /api/station/add
 pub async fn create_station(
     pool: web::Data<MySqlPool>,
     station: web::Json<CreateStation>,
 ) -> Result<impl Responder, Error> {
+    let station = station.into_inner();
+
+    let station_code = match station.station_code.as_deref().map(normalize_station_code).transpose() {
+        Ok(code) => code,
+        Err(message) => return Ok(HttpResponse::BadRequest().body(message)),
+    };
+
+    if let Err(message) = validate_coordinates(station.latitude, station.longitude) {
+        return Ok(HttpResponse::BadRequest().body(message));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = sqlx::query(
+        r#"
+        INSERT INTO station (station_name, station_type, station_code, latitude, longitude, state, zone)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&station.station_name)
+    .bind(&station.station_type)
+    .bind(&station_code)
+    .bind(station.latitude)
+    .bind(station.longitude)
+    .bind(&station.state)
+    .bind(&station.zone)
+    .execute(&mut *tx)
+    .await;
+
+    let station_id = match res {
+        Ok(res) => res.last_insert_id() as i64,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(HttpResponse::Conflict().body("A station with this station_code already exists"));
+        }
+        Err(e) => {
+            tracing::error!("Error creating station: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Failed to create station"));
+        }
+    };
+
+    for alias in station.aliases.iter().flatten() {
+        let res = sqlx::query("INSERT INTO station_alias (station_id, alias_name) VALUES (?, ?)")
+            .bind(station_id)
+            .bind(alias.trim())
+            .execute(&mut *tx)
+            .await;
+
+        if let Err(e) = res {
+            tracing::error!("Error adding station alias: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Failed to add station alias"));
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().body("Failed to create station"));
+    }
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "message": "Station created successfully",
+        "station_id": station_id,
+        "station_code": station_code
+    })))
+}
+
+// PUT /api/station/id/{station_id}
+pub async fn update_station(
+    pool: web::Data<MySqlPool>,
+    path: web::Path<i64>,
+    payload: web::Json<UpdateStation>,
+) -> Result<impl Responder, Error> {
+    let station_id = path.into_inner();
+    let payload = payload.into_inner();
+
+    let station_code = match payload.station_code.as_deref().map(normalize_station_code).transpose() {
+        Ok(code) => code,
+        Err(message) => return Ok(HttpResponse::BadRequest().body(message)),
+    };
+
+    if let Err(message) = validate_coordinates(payload.latitude, payload.longitude) {
+        return Ok(HttpResponse::BadRequest().body(message));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
 
-    let res = sqlx::query!(
-        "INSERT INTO station (station_name, station_type) VALUES (?, ?)",
-        &station.station_name,
-        &station.station_type
+    let res = sqlx::query(
+        r#"
+        UPDATE station
+        SET station_name = COALESCE(?, station_name),
+            station_type = COALESCE(?, station_type),
+            station_code = COALESCE(?, station_code),
+            latitude = COALESCE(?, latitude),
+            longitude = COALESCE(?, longitude),
+            state = COALESCE(?, state),
+            zone = COALESCE(?, zone)
+        WHERE station_id = ?
+        "#,
     )
-    .execute(pool.get_ref())
+    .bind(&payload.station_name)
+    .bind(&payload.station_type)
+    .bind(&station_code)
+    .bind(payload.latitude)
+    .bind(payload.longitude)
+    .bind(&payload.state)
+    .bind(&payload.zone)
+    .bind(station_id)
+    .execute(&mut *tx)
     .await;
 
     match res {
-        Ok(_) => Ok(HttpResponse::Created().finish()),
+        Ok(res) if res.rows_affected() == 0 => {
+            return Ok(HttpResponse::NotFound().body("Station not found"));
+        }
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(HttpResponse::Conflict().body("A station with this station_code already exists"));
+        }
         Err(e) => {
-            eprintln!("Error creating station: {:?}", e);
-            Ok(HttpResponse::InternalServerError().body("Failed to create station"))
+            tracing::error!("Error updating station: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Failed to update station"));
         }
     }
+
+    if let Some(aliases) = &payload.aliases {
+        let cleared = sqlx::query("DELETE FROM station_alias WHERE station_id = ?")
+            .bind(station_id)
+            .execute(&mut *tx)
+            .await;
+
+        if let Err(e) = cleared {
+            tracing::error!("Error clearing station aliases: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Failed to update station aliases"));
+        }
+
+        for alias in aliases {
+            let res = sqlx::query("INSERT INTO station_alias (station_id, alias_name) VALUES (?, ?)")
+                .bind(station_id)
+                .bind(alias.trim())
+                .execute(&mut *tx)
+                .await;
+
+            if let Err(e) = res {
+                tracing::error!("Error adding station alias: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().body("Failed to update station aliases"));
+            }
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().body("Failed to update station"));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Station updated successfully",
+        "station_id": station_id
+    })))
 }
 
 pub async fn get_all_stations(
@@ -42,20 +242,25 @@ pub async fn get_all_stations(
 
     if let Some(station_id) = query.station_id {
         conditions.push("station_id = ?");
-        args.add(station_id);
+        let _ = args.add(station_id);
     }
 
     if let Some(name) = &query.station_name {
         conditions.push("station_name LIKE ?");
-        args.add(format!("%{}%", name));
+        let _ = args.add(format!("%{}%", name));
     }
 
     if let Some(station_type) = &query.station_type {
         conditions.push("station_type = ?");
-        args.add(station_type);
+        let _ = args.add(station_type);
     }
 
-    let mut base_query = String::from("SELECT station_id, station_name, station_type FROM station");
+    if let Some(station_code) = &query.station_code {
+        conditions.push("station_code = ?");
+        let _ = args.add(station_code.to_uppercase());
+    }
+
+    let mut base_query = format!("SELECT {} FROM station", STATION_COLUMNS);
 
     if !conditions.is_empty() {
         base_query.push_str(" WHERE ");
@@ -71,8 +276,8 @@ pub async fn get_all_stations(
 
     // Add pagination
     base_query.push_str(" LIMIT ? OFFSET ?");
-    args.add(limit);
-    args.add(offset);
+    let _ = args.add(limit);
+    let _ = args.add(offset);
 
     // Execute both queries
     let stations = sqlx::query_as_with::<_, StationResponse, _>(&base_query, args.clone())
@@ -95,7 +300,7 @@ pub async fn get_all_stations(
             HttpResponse::Ok().json(response)
         }
         (Err(e), _) | (_, Err(e)) => {
-            eprintln!("Error fetching stations: {:?}", e);
+            tracing::error!("Error fetching stations: {:?}", e);
             HttpResponse::InternalServerError().body("Failed to fetch stations")
         }
     }
@@ -114,10 +319,16 @@ pub async fn get_station_by_name(
     let station_search = station_name.unwrap();
     let station_name = format!("%{}%", station_search);
 
+    // Codes and alias names find the station as well
     let res = sqlx::query_as!(
         StationResponse,
-        r#"SELECT station_id, station_name, station_type FROM station
-        WHERE station_name LIKE ?"#,
+        r#"SELECT station_id, station_name, station_type, station_code, latitude, longitude, state, zone
+        FROM station
+        WHERE station_name LIKE ?
+        OR station_code = ?
+        OR station_id IN (SELECT station_id FROM station_alias WHERE alias_name LIKE ?)"#,
+        station_name,
+        station_search.to_uppercase(),
         station_name
     )
     .fetch_all(pool.get_ref())
@@ -140,9 +351,127 @@ pub async fn get_station_by_name(
             }
         }
         Err(e) => {
-            eprintln!("Error fetching station: {:?}", e);
+            tracing::error!("Error fetching station: {:?}", e);
             Ok(HttpResponse::InternalServerError().body("Failed to fetch station"))
         }
     }
 
-}
\ No newline at end of file
+}
+
+// GET /api/station/suggest?search=..&limit=..
+pub async fn suggest_stations(
+    pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let _timer = metrics.search_duration.with_label_values(&["stations"]).start_timer();
+    let Some(search) = query.search.clone().filter(|s| !s.trim().is_empty()) else {
+        return Ok(HttpResponse::BadRequest().body("search is required"));
+    };
+    let limit = query.limit.unwrap_or(10).clamp(1, 50) as usize;
+
+    // Candidates share the query's first two characters as a prefix of the code,
+    // the name, a word of the name or an alias; ranking (and misspelling
+    // tolerance past those characters) happens in rank_stations
+    let key = like_prefix(&search.trim().to_lowercase().chars().take(2).collect::<String>());
+    let word_key = format!("% {}", key);
+    let stations = sqlx::query_as::<_, StationResponse>(&format!(
+        r#"
+        SELECT {} FROM station
+        WHERE station_code LIKE ?
+        OR station_name LIKE ?
+        OR station_name LIKE ?
+        OR station_id IN (SELECT station_id FROM station_alias WHERE alias_name LIKE ? OR alias_name LIKE ?)
+        ORDER BY station_name
+        LIMIT ?
+        "#,
+        STATION_COLUMNS
+    ))
+    .bind(&key)
+    .bind(&key)
+    .bind(&word_key)
+    .bind(&key)
+    .bind(&word_key)
+    .bind(SUGGEST_CANDIDATES)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let stations = match stations {
+        Ok(stations) => stations,
+        Err(e) => {
+            tracing::error!("Error fetching station suggestions: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().body("Failed to fetch station suggestions"));
+        }
+    };
+    let station_ids: Vec<i64> = stations.iter().map(|s| s.station_id).collect();
+
+    match fetch_aliases(pool.get_ref(), &station_ids).await {
+        Ok(aliases) => {
+            let suggestions = rank_stations(&search, stations, &aliases, limit);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "data": suggestions,
+                "total": suggestions.len()
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Error fetching station suggestions: {:?}", e);
+            Ok(HttpResponse::InternalServerError().body("Failed to fetch station suggestions"))
+        }
+    }
+}
+
+// GET /api/station/nearby?latitude=..&longitude=..&radius_km=..
+pub async fn get_nearby_stations(
+    pool: web::Data<MySqlPool>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    let (Some(latitude), Some(longitude)) = (query.latitude, query.longitude) else {
+        return Ok(HttpResponse::BadRequest().body("latitude and longitude are required"));
+    };
+
+    if let Err(message) = validate_coordinates(Some(latitude), Some(longitude)) {
+        return Ok(HttpResponse::BadRequest().body(message));
+    }
+
+    let radius_km = query.radius_km.unwrap_or(50.0).clamp(0.1, 1000.0);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100) as usize;
+
+    // Bounding box first so the geo index narrows the rows, exact distance after
+    let lat_delta = radius_km / 111.0;
+    let lon_delta = radius_km / (111.0 * latitude.to_radians().cos().abs().max(0.01));
+
+    let stations = sqlx::query_as::<_, StationResponse>(&format!(
+        "SELECT {} FROM station WHERE latitude BETWEEN ? AND ? AND longitude BETWEEN ? AND ?",
+        STATION_COLUMNS
+    ))
+    .bind(latitude - lat_delta)
+    .bind(latitude + lat_delta)
+    .bind(longitude - lon_delta)
+    .bind(longitude + lon_delta)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match stations {
+        Ok(stations) => {
+            let mut nearby: Vec<NearbyStation> = stations
+                .into_iter()
+                .filter_map(|station| {
+                    let distance_km = haversine_km(latitude, longitude, station.latitude?, station.longitude?);
+                    (distance_km <= radius_km).then_some(NearbyStation { station, distance_km })
+                })
+                .collect();
+            nearby.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+            nearby.truncate(limit);
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "data": nearby,
+                "total": nearby.len(),
+                "radius_km": radius_km
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Error fetching nearby stations: {:?}", e);
+            Ok(HttpResponse::InternalServerError().body("Failed to fetch nearby stations"))
+        }
+    }
+}
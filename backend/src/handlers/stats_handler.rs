@@ -18,7 +18,7 @@ pub async fn total_number_of_journeys(
             "total_journeys": total_journeys
         }))),
         Err(e) => {
-            eprintln!("Error fetching total number of journeys: {:?}", e);
+            tracing::error!("Error fetching total number of journeys: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch total number of journeys",
                 "details": e.to_string()
@@ -51,7 +51,7 @@ pub async fn busiest_route(
             "total_bookings": row.total_bookings
         }))),
         Err(e) => {
-            eprintln!("Error fetching busiest route: {:?}", e);
+            tracing::error!("Error fetching busiest route: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch busiest route",
                 "details": e.to_string()
@@ -83,7 +83,7 @@ pub async fn total_passengers_traveling(
             })
         }).collect::<Vec<_>>())),
         Err(e) => {
-            eprintln!("Error fetching total passengers traveling: {:?}", e);
+            tracing::error!("Error fetching total passengers traveling: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch total passengers traveling",
                 "details": e.to_string()
@@ -119,7 +119,7 @@ pub async fn gender_distribution(
             Ok(HttpResponse::Ok().json(response))
         }
         Err(e) => {
-            eprintln!("Error fetching gender distribution: {:?}", e);
+            tracing::error!("Error fetching gender distribution: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch gender distribution",
                 "details": e.to_string()
@@ -151,7 +151,7 @@ pub async fn busiest_station(
             "total_bookings": row.total_bookings
         }))),
         Err(e) => {
-            eprintln!("Error fetching busiest station: {:?}", e);
+            tracing::error!("Error fetching busiest station: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch busiest station",
                 "details": e.to_string()
@@ -185,7 +185,7 @@ pub async fn rank_running_trains_by_bookings(
             })
         }).collect::<Vec<_>>())),
         Err(e) => {
-            eprintln!("Error ranking running trains: {:?}", e);
+            tracing::error!("Error ranking running trains: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to rank running trains",
                 "details": e.to_string()
@@ -216,7 +216,7 @@ pub async fn busiest_time_period(
             })
         }).collect::<Vec<_>>())),
         Err(e) => {
-            eprintln!("Error fetching busiest time period: {:?}", e);
+            tracing::error!("Error fetching busiest time period: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch busiest time period",
                 "details": e.to_string()
@@ -246,7 +246,7 @@ pub async fn reservation_status_distribution(
             })
         }).collect::<Vec<_>>())),
         Err(e) => {
-            eprintln!("Error fetching reservation status distribution: {:?}", e);
+            tracing::error!("Error fetching reservation status distribution: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch reservation status distribution",
                 "details": e.to_string()
@@ -254,3 +254,64 @@ pub async fn reservation_status_distribution(
         }
     }
 }
+
+// Fares, discounts and refunds over paid bookings, with the discount given per promo code
+pub async fn revenue_summary(
+    pool: web::Data<MySqlPool>,
+) -> Result<impl Responder, Error> {
+    let res = async {
+        let totals = sqlx::query_as::<_, (Option<f64>, Option<f64>, Option<f64>, Option<f64>)>(
+            r#"
+            SELECT
+                SUM(b.amount + b.discount) AS gross_fares,
+                SUM(b.discount) AS discounts,
+                SUM(b.amount) AS collected,
+                SUM(cr.refund_amount) AS refunds
+            FROM booking b
+            JOIN payment_transaction pt ON pt.txn_id = b.txn_id AND pt.txn_status = 'COMPLETE'
+            LEFT JOIN cancellation_record cr ON cr.booking_id = b.booking_id
+            "#,
+        )
+        .fetch_one(pool.get_ref())
+        .await?;
+
+        let by_promo = sqlx::query_as::<_, (String, i64, Option<f64>)>(
+            r#"
+            SELECT p.code, COUNT(r.redemption_id), SUM(r.discount)
+            FROM promo_code p
+            JOIN promo_redemption r ON r.promo_id = p.promo_id
+            GROUP BY p.promo_id, p.code
+            ORDER BY SUM(r.discount) DESC
+            "#,
+        )
+        .fetch_all(pool.get_ref())
+        .await?;
+
+        Ok::<_, sqlx::Error>((totals, by_promo))
+    }
+    .await;
+
+    match res {
+        Ok(((gross_fares, discounts, collected, refunds), by_promo)) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "gross_fares": gross_fares.unwrap_or_default(),
+            "discounts": discounts.unwrap_or_default(),
+            "collected": collected.unwrap_or_default(),
+            "refunds": refunds.unwrap_or_default(),
+            "net_revenue": collected.unwrap_or_default() - refunds.unwrap_or_default(),
+            "promo_codes": by_promo.into_iter().map(|(code, redemptions, discount)| {
+                serde_json::json!({
+                    "code": code,
+                    "redemptions": redemptions,
+                    "discount": discount.unwrap_or_default()
+                })
+            }).collect::<Vec<_>>()
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching revenue summary: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch revenue summary",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use chrono::{Duration, Utc};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::ticket::{Ticket, TicketClaims, TicketPassenger, TicketRow, VerifyTicket};
+use crate::services::eticket::{render_html, render_pdf, TicketSigner};
+
+use super::utils::QueryParams;
+
+// A ticket stays checkable this long after the scheduled arrival
+const VALID_AFTER_ARRIVAL_HOURS: i64 = 6;
+
+enum TicketKey {
+    Pnr(i64),
+    Txn(i64),
+}
+
+async fn fetch_ticket_rows(pool: &MySqlPool, key: &TicketKey) -> Result<Vec<TicketRow>, sqlx::Error> {
+    let (filter, id) = match key {
+        TicketKey::Pnr(pnr) => ("b.pnr = ?", *pnr),
+        TicketKey::Txn(txn_id) => ("b.txn_id = ?", *txn_id),
+    };
+
+    let sql = format!(
+        r#"
+        SELECT
+            p.pnr, p.pass_name, p.email, p.age, p.sex,
+            b.booking_status, b.amount,
+            pt.txn_id, pt.payment_mode,
+            rs.reservation_status, rs.reservation_category,
+            s.seat_no, s.seat_type,
+            c.coach_name,
+            t.train_id, t.train_name,
+            j.journey_id,
+            st1.station_name AS start_station,
+            st2.station_name AS end_station,
+            COALESCE(ss.sched_tod, ss.sched_toa) AS departure_time,
+            COALESCE(es.sched_toa, es.sched_tod) AS arrival_time
+        FROM booking b
+        JOIN passenger p ON p.pnr = b.pnr
+        LEFT JOIN payment_transaction pt ON pt.txn_id = b.txn_id
+        LEFT JOIN reservation_status rs ON rs.pnr = b.pnr
+        LEFT JOIN seat s ON s.seat_id = b.seat_id
+        LEFT JOIN coach c ON c.coach_id = s.coach_id
+        LEFT JOIN journey j ON j.journey_id = b.journey_id
+        LEFT JOIN train t ON t.train_id = j.train_id
+        LEFT JOIN station st1 ON st1.station_id = b.start_station_id
+        LEFT JOIN station st2 ON st2.station_id = b.end_station_id
+        LEFT JOIN schedule ss ON ss.journey_id = b.journey_id AND ss.station_id = b.start_station_id
+        LEFT JOIN schedule es ON es.journey_id = b.journey_id AND es.station_id = b.end_station_id
+        WHERE {} AND b.booking_status <> 'CANCELLED'
+        ORDER BY p.pnr
+        "#,
+        filter
+    );
+
+    sqlx::query_as::<_, TicketRow>(&sql).bind(id).fetch_all(pool).await
+}
+
+// Only paid passengers holding a berth (confirmed or RAC) travel on the ticket
+fn ticketable(row: &TicketRow) -> bool {
+    row.booking_status.as_deref() == Some("CONFIRMED")
+        && matches!(row.reservation_status.as_deref(), Some("CNF" | "RAC"))
+}
+
+fn build_ticket(rows: Vec<TicketRow>, signer: &TicketSigner) -> Ticket {
+    let first = rows[0].clone();
+    let issued_at = Utc::now();
+
+    let claims = TicketClaims {
+        pnrs: rows.iter().map(|r| r.pnr).collect(),
+        txn_id: first.txn_id,
+        journey_id: first.journey_id,
+        train_id: first.train_id,
+        from: first.start_station.clone(),
+        to: first.end_station.clone(),
+        departure: first.departure_time,
+        seats: rows
+            .iter()
+            .map(|r| match (&r.coach_name, r.seat_no) {
+                (Some(coach), Some(seat_no)) => format!("{}/{}", coach, seat_no),
+                _ => r.reservation_status.clone().unwrap_or_else(|| "WL".to_string()),
+            })
+            .collect(),
+        statuses: rows
+            .iter()
+            .map(|r| r.booking_status.clone().unwrap_or_default())
+            .collect(),
+        issued_at,
+        exp: first
+            .arrival_time
+            .or(first.departure_time)
+            .unwrap_or(issued_at)
+            + Duration::hours(VALID_AFTER_ARRIVAL_HOURS),
+    };
+
+    Ticket {
+        txn_id: first.txn_id,
+        payment_mode: first.payment_mode,
+        journey_id: first.journey_id,
+        train_id: first.train_id,
+        train_name: first.train_name,
+        reservation_category: first.reservation_category,
+        from_station: first.start_station,
+        to_station: first.end_station,
+        departure_time: first.departure_time,
+        arrival_time: first.arrival_time,
+        total_fare: rows.iter().filter_map(|r| r.amount).sum(),
+        passengers: rows
+            .into_iter()
+            .map(|r| TicketPassenger {
+                pnr: r.pnr,
+                name: r.pass_name,
+                age: r.age,
+                sex: r.sex,
+                booking_status: r.booking_status,
+                reservation_status: r.reservation_status,
+                coach_name: r.coach_name,
+                seat_no: r.seat_no,
+                seat_type: r.seat_type,
+                fare: r.amount,
+            })
+            .collect(),
+        issued_at,
+        signed_payload: signer.sign(&claims),
+    }
+}
+
+async fn ticket_response(
+    pool: &MySqlPool,
+    signer: &TicketSigner,
+    user: &AuthUser,
+    key: TicketKey,
+    format: Option<&str>,
+) -> Result<HttpResponse, Error> {
+    let rows = match fetch_ticket_rows(pool, &key).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Error fetching ticket: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch ticket",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if rows.is_empty() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No active booking found"
+        })));
+    }
+    if !rows.iter().all(|r| r.email.as_deref().is_some_and(|email| user.can_manage(email))) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only view your own tickets"
+        })));
+    }
+    let rows: Vec<TicketRow> = rows.into_iter().filter(ticketable).collect();
+    if rows.is_empty() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "The e-ticket is available once payment is complete and a berth is confirmed or RAC"
+        })));
+    }
+
+    let ticket = build_ticket(rows, signer);
+    let name = match key {
+        TicketKey::Pnr(pnr) => format!("ticket-pnr-{}", pnr),
+        TicketKey::Txn(txn_id) => format!("ticket-txn-{}", txn_id),
+    };
+
+    let rendered = match format.unwrap_or("pdf") {
+        "json" => return Ok(HttpResponse::Ok().json(ticket)),
+        "html" => render_html(&ticket).map(|html| ("text/html; charset=utf-8", html.into_bytes(), "html")),
+        "pdf" => render_pdf(&ticket).map(|pdf| ("application/pdf", pdf, "pdf")),
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "format must be one of pdf, html, json"
+            })));
+        }
+    };
+
+    match rendered {
+        Ok((content_type, body, extension)) => Ok(HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(("Content-Disposition", format!("inline; filename=\"{}.{}\"", name, extension)))
+            .body(body)),
+        Err(e) => {
+            tracing::error!("Error rendering ticket: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to render ticket",
+                "details": e
+            })))
+        }
+    }
+}
+
+// GET /api/tickets/pnr/{pnr}?format=pdf|html|json
+pub async fn get_ticket_by_pnr(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    signer: web::Data<TicketSigner>,
+    pnr: web::Path<i64>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    ticket_response(pool.get_ref(), signer.get_ref(), &user, TicketKey::Pnr(pnr.into_inner()), query.format.as_deref()).await
+}
+
+// GET /api/tickets/txn/{txn_id}?format=pdf|html|json
+pub async fn get_ticket_by_txn(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    signer: web::Data<TicketSigner>,
+    txn_id: web::Path<i64>,
+    query: web::Query<QueryParams>,
+) -> Result<impl Responder, Error> {
+    ticket_response(pool.get_ref(), signer.get_ref(), &user, TicketKey::Txn(txn_id.into_inner()), query.format.as_deref()).await
+}
+
+// GET /api/tickets/public-key
+// Ed25519 key (base64url) that offline checkers use to verify QR payloads
+pub async fn get_ticket_public_key(signer: web::Data<TicketSigner>) -> Result<impl Responder, Error> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "algorithm": "Ed25519",
+        "public_key": signer.public_key()
+    })))
+}
+
+// Current booking status of each PNR
+async fn fetch_booking_statuses(pool: &MySqlPool, pnrs: &[i64]) -> Result<Vec<(i64, Option<String>)>, sqlx::Error> {
+    let mut builder = QueryBuilder::<MySql>::new("SELECT pnr, booking_status FROM booking WHERE pnr IN (");
+    let mut ids = builder.separated(", ");
+    for pnr in pnrs {
+        ids.push_bind(*pnr);
+    }
+    builder.push(")");
+    builder.build_query_as::<(i64, Option<String>)>().fetch_all(pool).await
+}
+
+// POST /api/tickets/verify
+// A ticket is valid when the signature matches, it has not expired and every
+// booking on it still has the status it was issued with.
+pub async fn verify_ticket(
+    pool: web::Data<MySqlPool>,
+    signer: web::Data<TicketSigner>,
+    payload: web::Json<VerifyTicket>,
+) -> Result<impl Responder, Error> {
+    let claims = match signer.verify(&payload.signed_payload) {
+        Ok(claims) => claims,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "valid": false,
+                "error": e
+            })));
+        }
+    };
+
+    if claims.exp <= Utc::now() {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "valid": false,
+            "error": "Ticket has expired",
+            "claims": claims
+        })));
+    }
+
+    let current = match fetch_booking_statuses(pool.get_ref(), &claims.pnrs).await {
+        Ok(current) => current,
+        Err(e) => {
+            tracing::error!("Error fetching booking statuses: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify ticket",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    let changed: Vec<_> = claims
+        .pnrs
+        .iter()
+        .zip(&claims.statuses)
+        .filter_map(|(pnr, issued)| {
+            let status = current.iter().find(|(p, _)| p == pnr).and_then(|(_, s)| s.clone());
+            (status.as_deref() != Some(issued.as_str()) || issued == "CANCELLED")
+                .then(|| serde_json::json!({ "pnr": pnr, "issued": issued, "current": status }))
+        })
+        .collect();
+    let complete = claims.statuses.len() == claims.pnrs.len();
+
+    if !complete || !changed.is_empty() {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "valid": false,
+            "error": "Booking status has changed since the ticket was issued",
+            "changed": changed,
+            "claims": claims
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "valid": true,
+        "claims": claims
+    })))
+}
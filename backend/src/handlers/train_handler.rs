@@ -26,7 +26,7 @@ pub async fn get_train_by_id(
     match result {
         Ok(train) => Ok(HttpResponse::Ok().json(train)),
         Err(e) => {
-            eprintln!("Error fetching train: {:?}", e);
+            tracing::error!("Error fetching train: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch train",
@@ -67,7 +67,7 @@ pub async fn create_train(
             })
         },
         Err(e) => {
-            eprintln!("Error inserting train: {:?}", e);
+            tracing::error!("Error inserting train: {:?}", e);
             HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to create train",
@@ -128,7 +128,7 @@ pub async fn get_trains(
     let total: i64 = match total_query.fetch_one(pool.get_ref()).await {
         Ok(count) => count,
         Err(e) => {
-            eprintln!("Error fetching count: {:?}", e);
+            tracing::error!("Error fetching count: {:?}", e);
             return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch total count",
                 "details": e.to_string()
@@ -165,7 +165,7 @@ pub async fn get_trains(
             "total": total
         }))),
         Err(e) => {
-            eprintln!("Error fetching trains: {:?}", e);
+            tracing::error!("Error fetching trains: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Something went wrong",
                 "details": e.to_string()
@@ -247,7 +247,7 @@ pub async fn get_trains_detailed(
             })
         })),
         (Err(e), _) | (_, Err(e)) => {
-            eprintln!("Error executing raw SQL: {:?}", e);
+            tracing::error!("Error executing raw SQL: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch train data",
                 "details": e.to_string()
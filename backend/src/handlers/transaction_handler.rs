@@ -1,11 +1,14 @@
 use actix_web::{web, HttpResponse, Responder, Error};
-use chrono::Utc;
 use sqlx::mysql::MySqlPool;
 
-use crate::models::transaction::{CancelBookingRequest, CreateTransaction, Transaction, UpdateTransactionStatus};
+use crate::models::transaction::{CreateTransaction, Transaction, UpdateTransactionStatus};
+use crate::services::metrics::Metrics;
+use crate::services::notifications::{enqueue_all, Notification, PAYMENT_FAILED};
+use crate::services::promo::release;
 
 pub async fn create_payment_transaction(
     pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
     payment: web::Json<CreateTransaction>,
 ) -> Result<impl Responder, actix_web::Error> {
     // Step 1: Call the stored procedure to insert the payment transaction
@@ -29,6 +32,7 @@ pub async fn create_payment_transaction(
 
     match txn_id_result {
         Ok(txn_id) => {
+            metrics.record_payment(&payment.txn_status);
             // Send back the payment information with the transaction ID
             let response = serde_json::json!({
                 "txn_id": txn_id,
@@ -39,7 +43,7 @@ pub async fn create_payment_transaction(
             Ok(HttpResponse::Created().json(response))
         },
         Err(e) => {
-            eprintln!("Error retrieving transaction ID: {:?}", e);
+            tracing::error!("Error retrieving transaction ID: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to retrieve transaction ID",
                 "details": e.to_string()
@@ -51,6 +55,7 @@ pub async fn create_payment_transaction(
 
 pub async fn update_payment_transaction_status(
     pool: web::Data<MySqlPool>,
+    metrics: web::Data<Metrics>,
     transaction: web::Json<UpdateTransactionStatus>,
 ) -> Result<impl Responder, Error> {
 
@@ -58,27 +63,76 @@ pub async fn update_payment_transaction_status(
     let transaction_id = transaction.txn_id;
     let transaction_status = transaction.txn_status.clone();
 
-    println!("Transaction ID: {:?}", transaction_id);
-    println!("Transaction Status: {:?}", transaction_status);
+    tracing::debug!("Transaction ID: {:?}", transaction_id);
+    tracing::debug!("Transaction Status: {:?}", transaction_status);
 
-    let result = sqlx::query!(
-        r#"
-        UPDATE payment_transaction
-        SET txn_status = ?
-        WHERE txn_id = ?
-        "#,
-        transaction.txn_status,
-        transaction.txn_id
-    )
-    .execute(pool.get_ref())
+    // The status change and the failure notice commit together
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let previous = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT txn_status FROM payment_transaction WHERE txn_id = ? FOR UPDATE",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(previous) = previous else {
+            return Ok(false);
+        };
+
+        sqlx::query("UPDATE payment_transaction SET txn_status = ? WHERE txn_id = ?")
+            .bind(&transaction_status)
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if transaction_status.as_deref() == Some("FAILED") && previous.as_deref() != Some("FAILED") {
+            // Addressed like BOOKING_CONFIRMED: every passenger on each booking
+            let recipients = sqlx::query_as::<_, (Option<String>, Option<i64>, i64, Option<i64>)>(
+                r#"
+                SELECT p.email, b.pnr, b.booking_id, b.journey_id
+                FROM booking b
+                JOIN passenger p ON p.pnr = b.pnr
+                WHERE b.txn_id = ?
+                "#,
+            )
+            .bind(transaction_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let notifications: Vec<Notification> = recipients
+                .into_iter()
+                .map(|(email, pnr, booking_id, journey_id)| Notification {
+                    email,
+                    pnr,
+                    event_type: PAYMENT_FAILED,
+                    payload: serde_json::json!({
+                        "booking_id": booking_id,
+                        "journey_id": journey_id,
+                        "txn_id": transaction_id,
+                    }),
+                })
+                .collect();
+            enqueue_all(&mut tx, &notifications).await?;
+            release(&mut tx, transaction_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
     .await;
 
     match result {
-        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
-            "message": "Transaction status updated"
-        }))),
+        Ok(updated) => {
+            if let (true, Some(status)) = (updated, &transaction_status) {
+                metrics.record_payment(status);
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Transaction status updated"
+            })))
+        }
         Err(e) => {
-            eprintln!("Error updating payment transaction: {:?}", e);
+            tracing::error!("Error updating payment transaction: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update payment transaction",
                 "details": e.to_string()
@@ -103,7 +157,7 @@ pub async fn get_all_transactions(
     match transactions {
         Ok(transactions) => {
             // Check if there are any transactions, if yes return them, else return an empty array
-            let response_data = if transactions.len() > 0 {
+            let response_data = if !transactions.is_empty() {
                 transactions
             } else {
                 Vec::new()  // Return an empty vector if no transactions are found
@@ -112,7 +166,7 @@ pub async fn get_all_transactions(
             Ok(HttpResponse::Ok().json(response_data))
         },
         Err(e) => {
-            eprintln!("Error fetching transactions: {:?}", e);
+            tracing::error!("Error fetching transactions: {:?}", e);
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch transactions",
                 "details": e.to_string()
@@ -0,0 +1,246 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use sqlx::MySqlPool;
+
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::traveller::{SaveTraveller, Traveller};
+use crate::services::travellers::validate;
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "You can only manage your own saved travellers"
+    }))
+}
+
+async fn fetch_traveller(pool: &MySqlPool, email: &str, traveller_id: i64) -> Result<Option<Traveller>, sqlx::Error> {
+    sqlx::query_as::<_, Traveller>(
+        r#"
+        SELECT traveller_id, email, name, date_of_birth, sex, disability, id_type, id_number
+        FROM traveller
+        WHERE email = ? AND traveller_id = ?
+        "#,
+    )
+    .bind(email)
+    .bind(traveller_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// GET /api/users/{email}/travellers
+pub async fn get_travellers(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let email = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(forbidden());
+    }
+
+    let travellers = sqlx::query_as::<_, Traveller>(
+        r#"
+        SELECT traveller_id, email, name, date_of_birth, sex, disability, id_type, id_number
+        FROM traveller
+        WHERE email = ?
+        ORDER BY name
+        "#,
+    )
+    .bind(&email)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match travellers {
+        Ok(travellers) => Ok(HttpResponse::Ok().json(travellers)),
+        Err(e) => {
+            tracing::error!("Error fetching travellers: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch travellers",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// GET /api/users/{email}/travellers/{traveller_id}
+pub async fn get_traveller(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<(String, i64)>,
+) -> Result<impl Responder, Error> {
+    let (email, traveller_id) = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(forbidden());
+    }
+
+    match fetch_traveller(pool.get_ref(), &email, traveller_id).await {
+        Ok(Some(traveller)) => Ok(HttpResponse::Ok().json(traveller)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Traveller not found"
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching traveller: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch traveller",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/users/{email}/travellers
+pub async fn create_traveller(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+    payload: web::Json<SaveTraveller>,
+) -> Result<impl Responder, Error> {
+    let email = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(forbidden());
+    }
+    let mut traveller = payload.into_inner();
+    if let Err(reason) = validate(&mut traveller) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO traveller (email, name, date_of_birth, sex, disability, id_type, id_number)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&email)
+    .bind(&traveller.name)
+    .bind(traveller.date_of_birth)
+    .bind(&traveller.sex)
+    .bind(traveller.disability)
+    .bind(&traveller.id_type)
+    .bind(&traveller.id_number)
+    .execute(pool.get_ref())
+    .await;
+
+    let traveller_id = match result {
+        Ok(result) => result.last_insert_id() as i64,
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            })));
+        }
+        Err(e) => {
+            tracing::error!("Error creating traveller: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create traveller",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    match fetch_traveller(pool.get_ref(), &email, traveller_id).await {
+        Ok(Some(traveller)) => Ok(HttpResponse::Created().json(traveller)),
+        Ok(None) => Ok(HttpResponse::Created().json(serde_json::json!({ "traveller_id": traveller_id }))),
+        Err(e) => {
+            tracing::error!("Error fetching traveller: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch traveller",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// PUT /api/users/{email}/travellers/{traveller_id}
+pub async fn update_traveller(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<(String, i64)>,
+    payload: web::Json<SaveTraveller>,
+) -> Result<impl Responder, Error> {
+    let (email, traveller_id) = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(forbidden());
+    }
+    let mut traveller = payload.into_inner();
+    if let Err(reason) = validate(&mut traveller) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE traveller
+        SET name = ?, date_of_birth = ?, sex = ?, disability = ?, id_type = ?, id_number = ?
+        WHERE email = ? AND traveller_id = ?
+        "#,
+    )
+    .bind(&traveller.name)
+    .bind(traveller.date_of_birth)
+    .bind(&traveller.sex)
+    .bind(traveller.disability)
+    .bind(&traveller.id_type)
+    .bind(&traveller.id_number)
+    .bind(&email)
+    .bind(traveller_id)
+    .execute(pool.get_ref())
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("Error updating traveller: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to update traveller",
+            "details": e.to_string()
+        })));
+    }
+
+    match fetch_traveller(pool.get_ref(), &email, traveller_id).await {
+        Ok(Some(traveller)) => Ok(HttpResponse::Ok().json(traveller)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Traveller not found"
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching traveller: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch traveller",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// DELETE /api/users/{email}/travellers/{traveller_id}
+// Past bookings keep their passenger details; only the link to the traveller is dropped
+pub async fn delete_traveller(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<(String, i64)>,
+) -> Result<impl Responder, Error> {
+    let (email, traveller_id) = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(forbidden());
+    }
+
+    let result = sqlx::query("DELETE FROM traveller WHERE email = ? AND traveller_id = ?")
+        .bind(&email)
+        .bind(traveller_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Traveller deleted",
+            "traveller_id": traveller_id
+        }))),
+        Ok(_) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Traveller not found"
+        }))),
+        Err(e) => {
+            tracing::error!("Error deleting traveller: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete traveller",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
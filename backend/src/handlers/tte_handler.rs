@@ -0,0 +1,472 @@
+use actix_web::{web, Error, HttpResponse, Responder, Result};
+use chrono::Utc;
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::handlers::auth_handler::AuthUser;
+use crate::models::tte::{ChartEntry, MarkPassenger, ReallocateBerth, RunningEvent, VerifyOnboard};
+use crate::services::eticket::TicketSigner;
+use crate::services::metrics::Metrics;
+
+// Live passengers of a journey with their berth and stop range; callers add conditions
+const CHART_SELECT: &str = r#"
+    SELECT
+        p.pnr, p.pass_name, p.age, p.sex,
+        b.booking_id, b.booking_status, b.onboard_status,
+        rs.reservation_status, rs.reservation_category,
+        b.seat_id, s.seat_no, s.seat_type,
+        c.coach_id, c.coach_name,
+        b.start_station_id, b.end_station_id,
+        ss.stop_number AS from_stop,
+        es.stop_number AS to_stop
+    FROM booking b
+    JOIN passenger p ON p.pnr = b.pnr
+    LEFT JOIN reservation_status rs ON rs.pnr = b.pnr
+    LEFT JOIN seat s ON s.seat_id = b.seat_id
+    LEFT JOIN coach c ON c.coach_id = s.coach_id
+    LEFT JOIN schedule ss ON ss.journey_id = b.journey_id AND ss.station_id = b.start_station_id
+    LEFT JOIN schedule es ON es.journey_id = b.journey_id AND es.station_id = b.end_station_id
+    WHERE b.journey_id = ? AND b.booking_status = 'CONFIRMED'
+"#;
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Only ticket examiners can do this"
+    }))
+}
+
+async fn fetch_chart_entry(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+    pnr: i64,
+) -> Result<Option<ChartEntry>, sqlx::Error> {
+    let sql = format!("{} AND b.pnr = ? FOR UPDATE", CHART_SELECT);
+    sqlx::query_as::<_, ChartEntry>(&sql)
+        .bind(journey_id)
+        .bind(pnr)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+async fn record_event(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+    station_id: Option<i64>,
+    event_type: &str,
+    entry: &ChartEntry,
+    (seat_id, previous_seat_id): (Option<i64>, Option<i64>),
+    examiner_email: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO running_event
+            (journey_id, station_id, event_type, pnr, booking_id, seat_id, previous_seat_id, examiner_email)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(journey_id)
+    .bind(station_id)
+    .bind(event_type)
+    .bind(entry.pnr)
+    .bind(entry.booking_id)
+    .bind(seat_id)
+    .bind(previous_seat_id)
+    .bind(examiner_email)
+    .execute(&mut **tx)
+    .await
+    .map(|_| ())
+}
+
+// POST /api/tte/verify
+// Checks a scanned QR payload (or a typed PNR) against the journey being worked.
+pub async fn verify_onboard(
+    pool: web::Data<MySqlPool>,
+    signer: web::Data<TicketSigner>,
+    user: AuthUser,
+    payload: web::Json<VerifyOnboard>,
+) -> Result<impl Responder, Error> {
+    let payload = payload.into_inner();
+
+    if !user.is_examiner() {
+        return Ok(forbidden());
+    }
+
+    let pnrs = match (&payload.signed_payload, payload.pnr) {
+        (Some(signed), _) => match signer.verify(signed) {
+            Ok(claims) if claims.exp <= Utc::now() => {
+                return Ok(HttpResponse::Ok().json(serde_json::json!({
+                    "valid": false,
+                    "error": "Ticket has expired"
+                })));
+            }
+            Ok(claims) if claims.journey_id == Some(payload.journey_id) => claims.pnrs,
+            Ok(_) => {
+                return Ok(HttpResponse::Ok().json(serde_json::json!({
+                    "valid": false,
+                    "error": "Ticket is for a different journey"
+                })));
+            }
+            Err(e) => {
+                return Ok(HttpResponse::Ok().json(serde_json::json!({
+                    "valid": false,
+                    "error": e
+                })));
+            }
+        },
+        (None, Some(pnr)) => vec![pnr],
+        (None, None) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Provide signed_payload or pnr"
+            })));
+        }
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        let mut passengers = Vec::new();
+        for pnr in pnrs {
+            if let Some(entry) = fetch_chart_entry(&mut tx, payload.journey_id, pnr).await? {
+                record_event(
+                    &mut tx,
+                    payload.journey_id,
+                    payload.station_id,
+                    "VERIFIED",
+                    &entry,
+                    (entry.seat_id, None),
+                    &user.email,
+                )
+                .await?;
+                passengers.push(entry);
+            }
+        }
+        Ok::<_, sqlx::Error>(passengers)
+    }
+    .await;
+
+    let passengers = match res {
+        Ok(passengers) => passengers,
+        Err(e) => {
+            tracing::error!("Error verifying ticket: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify ticket",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    // A genuine ticket whose bookings were cancelled is not valid for travel
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "valid": !passengers.is_empty(),
+        "passengers": passengers
+    })))
+}
+
+// GET /api/tte/journeys/{journey_id}/coaches/{coach_id}/chart
+// Passengers holding berths in the coach, plus RAC/WL passengers of the same class
+// still waiting for one.
+#[tracing::instrument(skip_all, fields(journey_id = path.0, coach_id = path.1))]
+pub async fn get_coach_chart(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<(i64, i64)>,
+) -> Result<impl Responder, Error> {
+    if !user.is_examiner() {
+        return Ok(forbidden());
+    }
+    let (journey_id, coach_id) = path.into_inner();
+
+    let res = async {
+        let coach_sql = format!("{} AND c.coach_id = ? ORDER BY s.seat_no, ss.stop_number", CHART_SELECT);
+        let seated = sqlx::query_as::<_, ChartEntry>(&coach_sql)
+            .bind(journey_id)
+            .bind(coach_id)
+            .fetch_all(pool.get_ref())
+            .await?;
+
+        let waiting_sql = format!(
+            r#"{} AND rs.reservation_status IN ('RAC', 'WL')
+            AND rs.reservation_category = (SELECT coach_type FROM coach WHERE coach_id = ?)
+            ORDER BY rs.reservation_status, rs.booking_time"#,
+            CHART_SELECT
+        );
+        let waiting = sqlx::query_as::<_, ChartEntry>(&waiting_sql)
+            .bind(journey_id)
+            .bind(coach_id)
+            .fetch_all(pool.get_ref())
+            .await?;
+
+        Ok::<_, sqlx::Error>((seated, waiting))
+    }
+    .await;
+
+    match res {
+        Ok((seated, waiting)) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "journey_id": journey_id,
+            "coach_id": coach_id,
+            "passengers": seated,
+            "waiting": waiting
+        }))),
+        Err(e) => {
+            tracing::error!("Error fetching chart: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch chart",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/tte/journeys/{journey_id}/passengers/{pnr}/status
+pub async fn mark_passenger(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<(i64, i64)>,
+    payload: web::Json<MarkPassenger>,
+) -> Result<impl Responder, Error> {
+    let (journey_id, pnr) = path.into_inner();
+    let payload = payload.into_inner();
+    let status = payload.onboard_status.to_uppercase();
+
+    if status != "BOARDED" && status != "NO_SHOW" {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "onboard_status must be BOARDED or NO_SHOW"
+        })));
+    }
+
+    if !user.is_examiner() {
+        return Ok(forbidden());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        let Some(entry) = fetch_chart_entry(&mut tx, journey_id, pnr).await? else {
+            return Ok(None);
+        };
+        sqlx::query("UPDATE booking SET onboard_status = ? WHERE booking_id = ?")
+            .bind(&status)
+            .bind(entry.booking_id)
+            .execute(&mut *tx)
+            .await?;
+        record_event(&mut tx, journey_id, payload.station_id, &status, &entry, (entry.seat_id, None), &user.email)
+            .await?;
+        Ok::<_, sqlx::Error>(Some(entry))
+    }
+    .await;
+
+    match res {
+        Ok(Some(entry)) => {
+            if let Err(e) = tx.commit().await {
+                tracing::error!("Transaction commit failed: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Transaction commit failed",
+                    "details": e.to_string()
+                })));
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "pnr": entry.pnr,
+                "booking_id": entry.booking_id,
+                "onboard_status": status
+            })))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No confirmed booking for this PNR on the journey"
+        }))),
+        Err(e) => {
+            tracing::error!("Error marking passenger: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to mark passenger",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
+
+// POST /api/tte/journeys/{journey_id}/reallocate
+// Moves an RAC passenger onto the berth of a no-show. The RAC passenger's trip must
+// lie within the no-show's, and nobody else may hold the berth on that stretch.
+pub async fn reallocate_berth(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    metrics: web::Data<Metrics>,
+    journey_id: web::Path<i64>,
+    payload: web::Json<ReallocateBerth>,
+) -> Result<impl Responder, Error> {
+    let journey_id = journey_id.into_inner();
+    let payload = payload.into_inner();
+
+    if !user.is_examiner() {
+        return Ok(forbidden());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Transaction begin failed: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Transaction begin failed")
+    })?;
+
+    let res = async {
+        let no_show = fetch_chart_entry(&mut tx, journey_id, payload.no_show_pnr).await?;
+        let rac = fetch_chart_entry(&mut tx, journey_id, payload.rac_pnr).await?;
+
+        let (Some(no_show), Some(rac)) = (no_show, rac) else {
+            return Ok(Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Both PNRs must have confirmed bookings on this journey"
+            }))));
+        };
+        let Some(berth) = no_show.seat_id.filter(|_| no_show.onboard_status == "NO_SHOW") else {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "The first passenger must be marked NO_SHOW and hold a berth"
+            }))));
+        };
+        if rac.reservation_status.as_deref() != Some("RAC") || rac.reservation_category != no_show.reservation_category {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "The second passenger must be RAC in the same class"
+            }))));
+        }
+
+        let (Some(from), Some(to)) = (rac.from_stop, rac.to_stop) else {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "RAC passenger's stops are not on this journey"
+            }))));
+        };
+        let covered = no_show.from_stop.is_some_and(|s| s <= from) && no_show.to_stop.is_some_and(|s| s >= to);
+        if !covered {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "The no-show's berth is not free for the RAC passenger's whole trip"
+            }))));
+        }
+
+        let clashes: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM booking b
+            JOIN schedule ss ON ss.journey_id = b.journey_id AND ss.station_id = b.start_station_id
+            JOIN schedule es ON es.journey_id = b.journey_id AND es.station_id = b.end_station_id
+            WHERE b.journey_id = ? AND b.seat_id = ? AND b.booking_id <> ?
+            AND b.booking_status IN ('CONFIRMED', 'PENDING')
+            AND ss.stop_number < ? AND es.stop_number > ?
+            "#,
+        )
+        .bind(journey_id)
+        .bind(berth)
+        .bind(no_show.booking_id)
+        .bind(to)
+        .bind(from)
+        .fetch_one(&mut *tx)
+        .await?;
+        if clashes > 0 {
+            return Ok(Err(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "The berth is booked by someone else on part of the trip"
+            }))));
+        }
+
+        sqlx::query("UPDATE booking SET seat_id = NULL WHERE booking_id = ?")
+            .bind(no_show.booking_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE reservation_status SET seat_id = NULL WHERE pnr = ?")
+            .bind(no_show.pnr)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE booking SET seat_id = ? WHERE booking_id = ?")
+            .bind(berth)
+            .bind(rac.booking_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE reservation_status SET seat_id = ?, reservation_status = 'CNF' WHERE pnr = ?")
+            .bind(berth)
+            .bind(rac.pnr)
+            .execute(&mut *tx)
+            .await?;
+
+        record_event(
+            &mut tx,
+            journey_id,
+            payload.station_id,
+            "BERTH_REALLOCATED",
+            &rac,
+            (Some(berth), rac.seat_id),
+            &user.email,
+        )
+        .await?;
+
+        Ok::<_, sqlx::Error>(Ok((rac.pnr, berth)))
+    }
+    .await;
+
+    let (pnr, berth) = match res {
+        Ok(Ok(moved)) => moved,
+        Ok(Err(response)) => return Ok(response),
+        Err(e) => {
+            tracing::error!("Error reallocating berth: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reallocate berth",
+                "details": e.to_string()
+            })));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Transaction commit failed: {:?}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Transaction commit failed",
+            "details": e.to_string()
+        })));
+    }
+
+    metrics.waitlist_promotions.with_label_values(&["RAC", "CNF"]).inc();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Berth reallocated",
+        "pnr": pnr,
+        "seat_id": berth
+    })))
+}
+
+// GET /api/tte/journeys/{journey_id}/events
+pub async fn get_running_events(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    journey_id: web::Path<i64>,
+) -> Result<impl Responder, Error> {
+    if !user.is_examiner() {
+        return Ok(forbidden());
+    }
+    let events = sqlx::query_as::<_, RunningEvent>(
+        r#"
+        SELECT event_id, journey_id, station_id, event_type, pnr, booking_id, seat_id,
+               previous_seat_id, examiner_email, recorded_at
+        FROM running_event
+        WHERE journey_id = ?
+        ORDER BY recorded_at, event_id
+        "#,
+    )
+    .bind(journey_id.into_inner())
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match events {
+        Ok(events) => Ok(HttpResponse::Ok().json(events)),
+        Err(e) => {
+            tracing::error!("Error fetching running events: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch running events",
+                "details": e.to_string()
+            })))
+        }
+    }
+}
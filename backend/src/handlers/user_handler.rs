@@ -1,12 +1,42 @@
-use actix_web::{ web, Error, HttpResponse, Responder, Result};
+use actix_web::{ http::StatusCode, web, Error, HttpResponse, Responder, Result};
 use sqlx::MySqlPool;
-use crate::models::user::{ CreateUser, LoginUser, UpdateUser, UserResponse};
+use crate::config::BusinessConfig;
+use crate::handlers::auth_handler::{send_account_token, AuthUser};
+use crate::models::user::{ ChangePassword, CreateUser, LoginUser, NotificationPreference, UpdateNotificationPreference, UpdateUser, UserResponse};
+use crate::middleware::rate_limit::ClientIp;
+use crate::services::accounts::{create_session, hash_password, needs_rehash, set_password, validate_password, verify_password, VERIFY_EMAIL};
+use crate::services::login_guard;
+use crate::services::mailer::Mailer;
+use crate::services::notification_sinks::check_webhook_url;
+
+const ROLES: &[&str] = &["ADMIN", "USER", "TTE"];
 
 // POST /create_user
+// Anyone may sign up as a USER; other roles are granted by an admin.
 pub async fn create_user(
     pool: web::Data<MySqlPool>,
+    mailer: web::Data<dyn Mailer>,
+    caller: Option<AuthUser>,
     payload: web::Json<CreateUser>
 ) -> Result<impl Responder, Error> {
+    if !ROLES.contains(&payload.role.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("role must be one of {}", ROLES.join(", "))
+        })));
+    }
+    if payload.role != "USER" && !caller.as_ref().is_some_and(AuthUser::is_admin) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only an admin can create accounts with this role"
+        })));
+    }
+    if let Err(reason) = validate_password(&payload.password) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+    let password = hash_password(&payload.password).map_err(|e| {
+        tracing::error!("Error hashing password: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to create user")
+    })?;
+
     let res = sqlx::query!(
         r#"
         INSERT INTO users(email, name, password, role)
@@ -14,7 +44,7 @@ pub async fn create_user(
         "#,
         payload.email,
         payload.name,
-        payload.password,
+        password,
         payload.role
     )
     .execute(pool.get_ref())
@@ -22,9 +52,10 @@ pub async fn create_user(
 
     match res {
         Ok(res) => {
+            send_account_token(pool.get_ref(), mailer.get_ref(), &payload.email, VERIFY_EMAIL).await;
             Ok(HttpResponse::Created().json({
                 serde_json::json!({
-                    "message": "User created successfully",
+                    "message": "User created successfully. Check your email for a verification code.",
                     "name": payload.name,
                     "email": payload.email,
                     "role": payload.role,
@@ -33,7 +64,7 @@ pub async fn create_user(
             }))
         },
         Err(e)=> {
-            eprintln!(" Error inserting user: {:?}", e);
+            tracing::error!("Error inserting user: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to create user",
@@ -46,9 +77,16 @@ pub async fn create_user(
 
 // GET /users
 pub async fn get_users(
-    pool: web::Data<MySqlPool>
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
 ) -> Result<impl Responder, Error> {
 
+    if !user.is_admin() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only admins can list users"
+        })));
+    }
+
     let result: Result<Vec<UserResponse>, sqlx::Error> = sqlx::query_as!(
         UserResponse,
         "SELECT email, name, role
@@ -60,7 +98,7 @@ pub async fn get_users(
     match result {
         Ok(users) => Ok(HttpResponse::Ok().json(users)),
         Err(e) => {
-            eprintln!("Error fetching users: {:?}", e);
+            tracing::error!("Error fetching users: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch users",
@@ -74,10 +112,17 @@ pub async fn get_users(
 // GET /users/{id}
 pub async fn get_user_by_email(
     pool: web::Data<MySqlPool>,
+    user: AuthUser,
     path: web::Path<String>,
 ) -> Result<impl Responder, Error> {
     let email = path.into_inner();
 
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only view your own account"
+        })));
+    }
+
     let result = sqlx::query_as!(
         UserResponse,
         "SELECT email, name, role
@@ -92,7 +137,7 @@ pub async fn get_user_by_email(
         Ok(Some(user)) => Ok(HttpResponse::Ok().json(user)),
         Ok(None) => Ok(HttpResponse::NotFound().finish()),
         Err(e) => {
-            eprintln!("Error fetching user: {:?}", e);
+            tracing::error!("Error fetching user: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to fetch user",
@@ -103,71 +148,189 @@ pub async fn get_user_by_email(
     }
 }
 
-// GET /users?email={email}&pass={password}
+// POST /api/auth/login
 pub async fn get_user_by_email_and_password(
     pool: web::Data<MySqlPool>,
+    business: web::Data<BusinessConfig>,
+    ClientIp(ip): ClientIp,
     payload: web::Json<LoginUser>,
 ) -> Result<impl Responder, Error> {
 
     let email = &payload.email;
     let password = &payload.password;
 
+    let blocked = login_guard::check(pool.get_ref(), email, &ip).await.map_err(|e| {
+        tracing::error!("Error checking login failures: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to sign in")
+    })?;
+    if let Some(blocked) = blocked {
+        let mut response = if blocked.locked && blocked.scope == login_guard::ACCOUNT {
+            HttpResponse::build(StatusCode::LOCKED)
+        } else {
+            HttpResponse::TooManyRequests()
+        };
+        let error = match (blocked.locked, blocked.scope) {
+            (true, login_guard::ACCOUNT) => "Account temporarily locked after repeated failed sign-ins",
+            (true, _) => "Too many failed sign-ins from this address",
+            (false, _) => "Too many failed sign-ins, wait before trying again",
+        };
+        return Ok(response
+            .insert_header(("Retry-After", blocked.retry_after_secs.to_string()))
+            .json(serde_json::json!({
+                "error": error,
+                "retry_after_secs": blocked.retry_after_secs,
+            })));
+    }
 
-    let result = sqlx::query_as!(
-        UserResponse,
+    let result = sqlx::query_as::<_, (String, String, Option<String>, String, bool)>(
         r#"
-        SELECT email, name, role
+        SELECT email, name, role, password, email_verified
         FROM users
-        WHERE email = ? AND password = ?
+        WHERE email = ?
         "#,
-        email,
-        password
     )
+    .bind(email)
     .fetch_optional(pool.get_ref())
     .await;
 
-    match result {
-        Ok(Some(user)) => Ok(HttpResponse::Ok().json({
-            serde_json::json!({
-                "message": "User found",
-                "name": user.name,
-                "email": user.email,
-                "role": user.role,
-            })
-        })),
-        Ok(None) => Ok(HttpResponse::Unauthorized().json({
-            serde_json::json!({ "error": "Invalid email or password" })
-        })),
+    let user = match result {
+        Ok(user) => user,
         Err(e) => {
-            eprintln!("Database error: {:?}", e);
-            Ok(HttpResponse::InternalServerError().json({
+            tracing::error!("Database error: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Something went wrong",
                     "details": e.to_string()
                 })
-            }))
+            }));
+        }
+    };
+
+    // Argon2 is deliberately slow, so it runs off the async workers
+    let verified = match &user {
+        Some((_, _, _, stored, _)) => {
+            let (password, stored) = (password.clone(), stored.clone());
+            web::block(move || verify_password(&password, &stored)).await.map_err(|e| {
+                tracing::error!("Error verifying password: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to sign in")
+            })?
         }
+        None => false,
+    };
+
+    match user {
+        Some((email, name, role, stored, email_verified)) if verified => {
+            if let Err(e) = login_guard::record_success(pool.get_ref(), &payload.email, &ip).await {
+                tracing::error!("Error clearing login failures: {:?}", e);
+            }
+
+            // Accounts from before password hashing are upgraded on their next sign-in
+            if needs_rehash(&stored) {
+                let password = password.clone();
+                match web::block(move || hash_password(&password)).await {
+                    Ok(Ok(hash)) => {
+                        if let Err(e) = sqlx::query("UPDATE users SET password = ? WHERE email = ?")
+                            .bind(&hash)
+                            .bind(&email)
+                            .execute(pool.get_ref())
+                            .await
+                        {
+                            tracing::error!("Error upgrading password hash: {:?}", e);
+                        }
+                    }
+                    Ok(Err(e)) => tracing::error!("Error hashing password: {}", e),
+                    Err(e) => tracing::error!("Error hashing password: {:?}", e),
+                }
+            }
+
+            let token = create_session(pool.get_ref(), &email, business.session_days).await.map_err(|e| {
+                tracing::error!("Error creating session: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to sign in")
+            })?;
+
+            Ok(HttpResponse::Ok().json({
+                serde_json::json!({
+                    "message": "User found",
+                    "name": name,
+                    "email": email,
+                    "role": role,
+                    "email_verified": email_verified,
+                    "token": token,
+                    "expires_in_days": business.session_days,
+                })
+            }))
+        },
+        user => {
+            if let Err(e) = login_guard::record_failure(pool.get_ref(), email, &ip, user.is_some()).await {
+                tracing::error!("Error recording login failure: {:?}", e);
+            }
+            Ok(HttpResponse::Unauthorized().json({
+                serde_json::json!({ "error": "Invalid email or password" })
+            }))
+        },
     }
 }
 
 // PUT /users/{id}
+// Users may rename themselves; changing a role takes an admin.
 pub async fn update_user(
     pool: web::Data<MySqlPool>,
+    user: AuthUser,
     path: web::Path<String>,
     payload: web::Json<UpdateUser>
 ) -> Result<impl Responder, Error> {
 
     let email = path.into_inner();
 
-    let result = sqlx::query!(
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only update your own account"
+        })));
+    }
+
+    let current_role = match sqlx::query_scalar::<_, Option<String>>("SELECT role FROM users WHERE email = ?")
+        .bind(&email)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(role)) => role,
+        Ok(None) => return Ok(HttpResponse::NotFound().finish()),
+        Err(e) => {
+            tracing::error!("Error fetching user: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update user",
+                "details": e.to_string(),
+            })));
+        }
+    };
+
+    if let Some(role) = &payload.role {
+        if !ROLES.contains(&role.as_str()) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("role must be one of {}", ROLES.join(", "))
+            })));
+        }
+        if current_role.as_deref() != Some(role.as_str()) && !user.is_admin() {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only an admin can change roles"
+            })));
+        }
+    }
+    if payload.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "name cannot be empty"
+        })));
+    }
+
+    let result = sqlx::query(
         "UPDATE users SET
-        name = ?,
-        role = ?
+        name = COALESCE(?, name),
+        role = COALESCE(?, role)
         WHERE email = ?",
-        payload.name,
-        payload.role,
-        email
     )
+    .bind(payload.name.as_deref().map(str::trim))
+    .bind(&payload.role)
+    .bind(&email)
     .execute(pool.get_ref())
     .await;
 
@@ -177,13 +340,13 @@ pub async fn update_user(
                 serde_json::json!({
                     "message": "User updated successfully",
                     "name": payload.name,
-                    "role": payload.role,
+                    "role": payload.role.clone().or(current_role),
                     "rows_affected": result.rows_affected(),
                 })
             }))
         },
         Err(e) => {
-            eprintln!("Error updating user: {:?}", e);
+            tracing::error!("Error updating user: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to update user",
@@ -194,14 +357,93 @@ pub async fn update_user(
     }
 }
 
+// POST /users/{id}/password
+// Signs out every other session; the response carries a fresh token for this one.
+pub async fn change_password(
+    pool: web::Data<MySqlPool>,
+    business: web::Data<BusinessConfig>,
+    user: AuthUser,
+    path: web::Path<String>,
+    payload: web::Json<ChangePassword>
+) -> Result<impl Responder, Error> {
+    let email = path.into_inner();
+
+    if !user.email.eq_ignore_ascii_case(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only change your own password"
+        })));
+    }
+    if let Err(reason) = validate_password(&payload.new_password) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({ "error": reason })));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {:?}", e);
+        actix_web::error::ErrorInternalServerError("Failed to start transaction")
+    })?;
+
+    let result = async {
+        let stored = sqlx::query_scalar::<_, String>("SELECT password FROM users WHERE email = ? FOR UPDATE")
+            .bind(&user.email)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !verify_password(&payload.current_password, &stored) {
+            return Ok(Err(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Current password is incorrect"
+            }))));
+        }
+        if let Err(e) = set_password(&mut tx, &user.email, &payload.new_password).await? {
+            tracing::error!("Error hashing password: {}", e);
+            return Ok(Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to change password"
+            }))));
+        }
+        Ok::<_, sqlx::Error>(Ok(()))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            tx.commit().await.map_err(|e| {
+                tracing::error!("Failed to commit transaction: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Failed to change password")
+            })?;
+            let token = create_session(pool.get_ref(), &user.email, business.session_days).await.map_err(|e| {
+                tracing::error!("Error creating session: {:?}", e);
+                actix_web::error::ErrorInternalServerError("Password changed, but failed to sign in again")
+            })?;
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Password changed",
+                "token": token,
+                "expires_in_days": business.session_days,
+            })))
+        }
+        Ok(Err(response)) => Ok(response),
+        Err(e) => {
+            tracing::error!("Error changing password: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to change password",
+                "details": e.to_string(),
+            })))
+        }
+    }
+}
+
 // DELETE /users/{id}
 pub async fn delete_user(
     pool: web::Data<MySqlPool>,
+    user: AuthUser,
     path: web::Path<String>,
 ) -> Result<impl Responder, Error> {
 
     let email = path.into_inner();
 
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only delete your own account"
+        })));
+    }
+
     let result = sqlx::query!(
         "DELETE FROM users WHERE email = ?",
         &email
@@ -223,7 +465,7 @@ pub async fn delete_user(
             }
         },
         Err(e) => {
-            eprintln!("Error deleting user: {:?}", e);
+            tracing::error!("Error deleting user: {:?}", e);
             Ok(HttpResponse::InternalServerError().json({
                 serde_json::json!({
                     "error": "Failed to delete user",
@@ -232,4 +474,128 @@ pub async fn delete_user(
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+async fn fetch_notification_preference(
+    pool: &MySqlPool,
+    email: &str,
+) -> Result<NotificationPreference, sqlx::Error> {
+    let preference = sqlx::query_as::<_, NotificationPreference>(
+        "SELECT email, email_enabled, webhook_enabled, webhook_url FROM notification_preference WHERE email = ?",
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+
+    // Users who never set preferences get email only
+    Ok(preference.unwrap_or_else(|| NotificationPreference {
+        email: email.to_string(),
+        email_enabled: true,
+        webhook_enabled: false,
+        webhook_url: None,
+    }))
+}
+
+// GET /users/{email}/notifications
+pub async fn get_notification_preferences(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let email = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only view your own notification preferences"
+        })));
+    }
+
+    match fetch_notification_preference(pool.get_ref(), &email).await {
+        Ok(preference) => Ok(HttpResponse::Ok().json(preference)),
+        Err(e) => {
+            tracing::error!("Error fetching notification preferences: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch notification preferences",
+                "details": e.to_string(),
+            })))
+        }
+    }
+}
+
+// PUT /users/{email}/notifications
+pub async fn update_notification_preferences(
+    pool: web::Data<MySqlPool>,
+    user: AuthUser,
+    path: web::Path<String>,
+    payload: web::Json<UpdateNotificationPreference>,
+) -> Result<impl Responder, Error> {
+    let email = path.into_inner();
+
+    if !user.can_manage(&email) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only change your own notification preferences"
+        })));
+    }
+
+    let current = match fetch_notification_preference(pool.get_ref(), &email).await {
+        Ok(current) => current,
+        Err(e) => {
+            tracing::error!("Error fetching notification preferences: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch notification preferences",
+                "details": e.to_string(),
+            })));
+        }
+    };
+
+    let webhook_url = payload.webhook_url.clone().or(current.webhook_url).filter(|url| !url.trim().is_empty());
+    let webhook_enabled = payload.webhook_enabled.unwrap_or(current.webhook_enabled);
+
+    if let Some(url) = &webhook_url
+        && let Err(reason) = check_webhook_url(url)
+    {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": reason
+        })));
+    }
+    if webhook_enabled && webhook_url.is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "webhook_url is required to enable webhooks"
+        })));
+    }
+
+    let preference = NotificationPreference {
+        email,
+        email_enabled: payload.email_enabled.unwrap_or(current.email_enabled),
+        webhook_enabled,
+        webhook_url,
+    };
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO notification_preference (email, email_enabled, webhook_enabled, webhook_url)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            email_enabled = VALUES(email_enabled),
+            webhook_enabled = VALUES(webhook_enabled),
+            webhook_url = VALUES(webhook_url)
+        "#,
+    )
+    .bind(&preference.email)
+    .bind(preference.email_enabled)
+    .bind(preference.webhook_enabled)
+    .bind(&preference.webhook_url)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::Ok().json(preference)),
+        Err(e) => {
+            tracing::error!("Error updating notification preferences: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update notification preferences",
+                "details": e.to_string(),
+            })))
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime};
 
 #[derive(serde::Deserialize, Debug)]
 pub struct QueryParams {
@@ -13,14 +13,23 @@ pub struct QueryParams {
     pub station_id: Option<i64>,
     pub station_name: Option<String>,
     pub station_type: Option<String>,
+    pub station_code: Option<String>,
+
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_km: Option<f64>,
     
     // Coach Specific
+    #[allow(dead_code)]
     pub coach_type: Option<String>,
+    #[allow(dead_code)]
     pub coach_name: Option<String>,
 
     pub route_id: Option<i64>,
     pub route_name: Option<String>,
     pub route_station: Option<i64>,
+    pub k: Option<usize>,
+    pub max_distance: Option<f64>,
 
     pub journey_id: Option<i64>,
     pub start_station_name: Option<String>,
@@ -29,9 +38,23 @@ pub struct QueryParams {
     pub source_station_id: Option<i64>,
     pub destination_station_id: Option<i64>,
 
+    // Journey segment, as station ids
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+
     pub journey_date: Option<NaiveDate>,
+    pub days: Option<i64>,
+    pub flex_days: Option<i64>,
+    pub depart_after: Option<NaiveTime>,
+    pub depart_before: Option<NaiveTime>,
+    pub arrive_after: Option<NaiveTime>,
+    pub arrive_before: Option<NaiveTime>,
+    pub only_available: Option<bool>,
+    pub reservation_category: Option<String>,
+    pub quota: Option<String>,
 
     pub email: Option<String>,
+    pub format: Option<String>,
 
     pub page: Option<u32>,
     pub limit: Option<u32>
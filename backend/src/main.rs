@@ -1,43 +1,105 @@
 mod config;
 mod db;
 mod errors;
+mod logging;
 mod models;
 mod handlers;
 mod routes;
 mod demo;
+mod services;
+mod middleware;
 
-use actix_web::{App, HttpServer};
-use actix_cors::Cors;
-use config::Config;
+use std::sync::{Arc, RwLock};
+
+use actix_web::{middleware::from_fn, web, App, HttpServer};
+use config::{Config, TlsConfig};
 use db::init_pool;
-use tracing_subscriber;
-use tracing;
+use middleware::{cors::cors, metrics::track_requests, rate_limit::{limit_requests, RateLimiter}, request_trace::trace_requests};
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
+use services::{eticket::TicketSigner, mailer, metrics::Metrics, notification_dispatcher, quota, rail_network::RailNetwork};
+
+fn tls_acceptor(config: &TlsConfig) -> std::io::Result<SslAcceptorBuilder> {
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+    builder.set_private_key_file(&config.key_path, SslFiletype::PEM)?;
+    builder.set_certificate_chain_file(&config.cert_path)?;
+    Ok(builder)
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
 
-    // Initialize tracing subscriber
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG)
-        .with_target(false)
-        .without_time()
-        .init();
-
     dotenv::dotenv().ok();
-    let config = Config::from_env().expect("Failed to load config");
-    println!("Server starting on {}:{}", config.host, config.port);
-    let db_pool = init_pool(&config.database_url)
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load config: {}", e);
+        std::process::exit(1);
+    });
+    logging::init(&config.logging);
+    tracing::debug!("Configuration: {:?}", config);
+
+    let scheme = if config.server.tls.is_some() { "https" } else { "http" };
+    tracing::info!("Server starting on {}://{}:{}", scheme, config.server.host, config.server.port);
+    let db_pool = init_pool(&config.database)
         .await
         .expect("Failed to create pool");
 
-    HttpServer::new(move || {
+    let rail_network = RailNetwork::load(&db_pool).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to build rail network, starting empty: {:?}", e);
+        RailNetwork::default()
+    });
+    let rail_network = web::Data::new(RwLock::new(rail_network));
+
+    let metrics = Arc::new(Metrics::new().expect("Failed to register metrics"));
+    notification_dispatcher::spawn(db_pool.clone(), config.notifications.clone());
+    quota::spawn_chart_preparation(db_pool.clone(), config.business.chart_lead_hours);
+
+    let ticket_signer = web::Data::new(TicketSigner::new(config.tickets.signing_key.as_deref()));
+    let mailer = web::Data::from(mailer::from_config(&config.notifications));
+    let rate_limiter = web::Data::new(RateLimiter::new(config.rate_limits.clone()));
+    let business = web::Data::new(config.business.clone());
+    let cors_config = config.cors.clone();
+    let logging_config = web::Data::new(config.logging.clone());
+    let metrics = web::Data::from(metrics);
+    let metrics_pool = web::Data::new(db_pool.clone());
+    let metrics_data = metrics.clone();
+
+    let server = HttpServer::new(move || {
         App::new()
-        .wrap(Cors::permissive())
+        .wrap(from_fn(limit_requests))
+        .wrap(from_fn(track_requests))
+        .wrap(cors(&cors_config))
+        .wrap(from_fn(trace_requests))
         .app_data(actix_web::web::Data::new(db_pool.clone()))
+        .app_data(rail_network.clone())
+        .app_data(ticket_signer.clone())
+        .app_data(mailer.clone())
+        .app_data(rate_limiter.clone())
+        .app_data(business.clone())
+        .app_data(logging_config.clone())
+        .app_data(metrics.clone())
         .configure(routes::init_routes)
+    });
+    let server = match config.server.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    let address = (config.server.host.as_str(), config.server.port);
+    let server = match &config.server.tls {
+        Some(tls) => server.bind_openssl(address, tls_acceptor(tls)?)?.run(),
+        None => server.bind(address)?.run(),
+    };
+
+    // Scrapes get their own listener so /metrics is neither public nor rate limited
+    tracing::info!("Metrics on http://{}:{}/metrics", config.server.metrics_host, config.server.metrics_port);
+    let metrics_server = HttpServer::new(move || {
+        App::new()
+        .app_data(metrics_pool.clone())
+        .app_data(metrics_data.clone())
+        .configure(routes::metrics::config)
     })
-    .bind((config.host.as_str(), config.port))?
-    .run()
-    .await
+    .workers(1)
+    .bind((config.server.metrics_host.as_str(), config.server.metrics_port))?
+    .run();
+
+    tokio::try_join!(server, metrics_server).map(|_| ())
 }
 
@@ -0,0 +1,63 @@
+// middleware/cors.rs
+//
+// CORS from the configured origin list. "*" allows any origin; an origin given
+// without a port also matches that host on any port, which suits local
+// frontends served from changing ports.
+
+use actix_cors::Cors;
+
+use crate::config::CorsConfig;
+
+fn origin_allowed(allowed: &[String], origin: &str) -> bool {
+    allowed.iter().any(|entry| {
+        origin == entry
+            || origin
+                .strip_prefix(entry.as_str())
+                .and_then(|rest| rest.strip_prefix(':'))
+                .is_some_and(|port| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+pub fn cors(config: &CorsConfig) -> Cors {
+    let cors = Cors::default()
+        .allow_any_method()
+        .allow_any_header()
+        .expose_headers(["Retry-After"])
+        .max_age(config.max_age_secs);
+
+    if config.allowed_origins.iter().any(|origin| origin == "*") {
+        return cors.allow_any_origin();
+    }
+    let allowed = config.allowed_origins.clone();
+    cors.allowed_origin_fn(move |origin, _| origin.to_str().is_ok_and(|origin| origin_allowed(&allowed, origin)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed() -> Vec<String> {
+        vec!["http://localhost".to_string(), "https://app.example.com:8443".to_string()]
+    }
+
+    #[test]
+    fn exact_origins_match() {
+        assert!(origin_allowed(&allowed(), "http://localhost"));
+        assert!(origin_allowed(&allowed(), "https://app.example.com:8443"));
+    }
+
+    #[test]
+    fn origin_without_port_matches_any_port() {
+        assert!(origin_allowed(&allowed(), "http://localhost:3000"));
+        assert!(!origin_allowed(&allowed(), "http://localhost:"));
+        assert!(!origin_allowed(&allowed(), "http://localhost:3000x"));
+    }
+
+    #[test]
+    fn other_hosts_and_ports_are_refused() {
+        assert!(!origin_allowed(&allowed(), "http://localhost.evil.com"));
+        assert!(!origin_allowed(&allowed(), "https://localhost"));
+        assert!(!origin_allowed(&allowed(), "https://app.example.com"));
+        assert!(!origin_allowed(&allowed(), "https://app.example.com:9000"));
+    }
+}
@@ -0,0 +1,40 @@
+// middleware/metrics.rs
+//
+// Request counts and latencies for Prometheus. Requests are labelled by the
+// matched route pattern rather than the raw path so ids in the URL don't create
+// a series each; anything that matched no route is labelled "unmatched".
+
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+
+use crate::services::metrics::Metrics;
+
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(metrics) = req.app_data::<web::Data<Metrics>>().cloned() else {
+        return next.call(req).await;
+    };
+    let method = req.method().to_string();
+
+    let started = Instant::now();
+    let result = next.call(req).await;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let (route, status) = match &result {
+        Ok(res) => (
+            res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string()),
+            res.status().as_u16().to_string(),
+        ),
+        Err(e) => ("unmatched".to_string(), e.as_response_error().status_code().as_u16().to_string()),
+    };
+    metrics.http_requests.with_label_values(&[&method, &route, &status]).inc();
+    metrics.http_request_duration.with_label_values(&[&method, &route]).observe(elapsed);
+
+    result
+}
@@ -0,0 +1,128 @@
+// middleware/rate_limit.rs
+//
+// Per-client rate limiting. Each client gets a token bucket per scope that
+// refills at the scope's per-minute rate; requests arriving with an empty
+// bucket get 429 with Retry-After.
+
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpRequest, HttpResponse};
+
+use crate::config::RateLimitConfig;
+
+// Buckets are pruned once this many are held
+const PRUNE_AT: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(usize, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn trust_proxy(&self) -> bool {
+        self.config.trust_proxy
+    }
+
+    // Scope index (the default scope is scopes.len()) and its limit for a path
+    fn scope_for(&self, path: &str) -> (usize, &str, u32) {
+        let matches = |prefix: &String| {
+            path.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        self.config
+            .scopes
+            .iter()
+            .enumerate()
+            .find(|(_, scope)| scope.prefixes.iter().any(matches))
+            .map(|(i, scope)| (i, scope.name.as_str(), scope.per_minute))
+            .unwrap_or((self.config.scopes.len(), "default", self.config.default_per_minute))
+    }
+
+    // Takes a token for the client, or returns the scope name and seconds until one is available
+    pub fn acquire(&self, path: &str, client_ip: &str) -> Result<(), (String, u64)> {
+        let (index, name, per_minute) = self.scope_for(path);
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = per_minute as f64;
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_AT {
+            // Buckets that would have refilled completely carry no state
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated).as_secs_f64() < 60.0);
+        }
+
+        let bucket = buckets
+            .entry((index, client_ip.to_string()))
+            .or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err((name.to_string(), ((1.0 - bucket.tokens) / per_second).ceil() as u64))
+        }
+    }
+}
+
+pub fn client_ip(req: &HttpRequest, trust_proxy: bool) -> String {
+    let info = req.connection_info();
+    let address = if trust_proxy { info.realip_remote_addr() } else { info.peer_addr() };
+    address.unwrap_or("unknown").to_string()
+}
+
+// The caller's address, honouring proxy headers only when configured to
+pub struct ClientIp(pub String);
+
+impl FromRequest for ClientIp {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let trust_proxy = req.app_data::<web::Data<RateLimiter>>().is_some_and(|limiter| limiter.trust_proxy());
+        ready(Ok(ClientIp(client_ip(req, trust_proxy))))
+    }
+}
+
+pub async fn limit_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(limiter) = req.app_data::<web::Data<RateLimiter>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+
+    let ip = client_ip(req.request(), limiter.trust_proxy());
+    match limiter.acquire(req.path(), &ip) {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+        Err((scope, retry_after)) => {
+            let response = HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(serde_json::json!({
+                    "error": "Too many requests",
+                    "scope": scope,
+                    "retry_after_secs": retry_after,
+                }));
+            Ok(req.into_response(response))
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// middleware/request_trace.rs
+//
+// One tracing span per request, tagged with a request id. The id is taken from
+// an incoming X-Request-Id header when it looks sane, otherwise generated, and is
+// echoed back on the response. Everything logged while handling the request,
+// including sqlx statement timings, is recorded inside the span.
+
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use tracing::field::Empty;
+use tracing::Instrument;
+
+use crate::config::LoggingConfig;
+use crate::logging::redact_query;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// The id of the request being handled, for handlers that want to report it
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RequestId(pub String);
+
+fn incoming_id(req: &ServiceRequest) -> Option<String> {
+    let id = req.headers().get(REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let valid = (1..=64).contains(&id.len())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then(|| id.to_string())
+}
+
+fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+pub async fn trace_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = incoming_id(&req).unwrap_or_else(new_id);
+    let slow_request_ms = req
+        .app_data::<web::Data<LoggingConfig>>()
+        .map_or(LoggingConfig::default().slow_request_ms, |config| config.slow_request_ms);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+        query = %redact_query(req.query_string()),
+        route = Empty,
+        status = Empty,
+        elapsed_ms = Empty,
+    );
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let started = Instant::now();
+    let result = next.call(req).instrument(span.clone()).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    span.record("elapsed_ms", elapsed_ms);
+
+    let _entered = span.enter();
+    match result {
+        Ok(mut res) => {
+            let status = res.status();
+            span.record("status", status.as_u16());
+            if let Some(route) = res.request().match_pattern() {
+                span.record("route", route.as_str());
+            }
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+
+            if status.is_server_error() {
+                tracing::error!(elapsed_ms, status = status.as_u16(), "request failed");
+            } else if elapsed_ms >= slow_request_ms {
+                tracing::warn!(elapsed_ms, threshold_ms = slow_request_ms, "slow request");
+            } else {
+                tracing::info!(elapsed_ms, status = status.as_u16(), "request completed");
+            }
+            Ok(res)
+        }
+        Err(e) => {
+            tracing::error!(elapsed_ms, error = %e, "request errored");
+            Err(e)
+        }
+    }
+}
@@ -7,6 +7,7 @@ use chrono::{DateTime, Utc};
 #[derive(Debug, Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "CHAR(3)")]
 #[serde(rename_all = "UPPERCASE")]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 pub enum BookingStatus {
     WAT,    // Waiting
     RAC,    // Reservation Against Cancellation
@@ -16,6 +17,7 @@ pub enum BookingStatus {
 }
 
 #[derive(Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct CreateBooking {
     pub pnr: i64,
     pub journey_id: i64,
@@ -27,6 +29,7 @@ pub struct CreateBooking {
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
+#[allow(dead_code)]
 pub struct Booking {
     pub booking_id: i64,
     pub booking_time: DateTime<Utc>,
@@ -52,6 +55,9 @@ pub struct GroupBookingRequest {
     pub txn_id: i64,
     pub email: String,
     pub reservation_category: String, // e.g. "SL", "AC3", etc.
+    pub quota: Option<String>,        // GN (default), LD, SS, TQ or EQ
+    pub quote_id: Option<i64>,        // Locked fare quote; required on flexi-priced classes
+    pub promo_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -70,6 +76,7 @@ pub struct BookingDetail {
     pub txn_status: Option<String>,
     pub reservation_status: Option<String>,
     pub reservation_category: Option<String>,
+    pub quota: Option<String>,
     pub seat_no: Option<i64>,
     pub seat_type: Option<String>,
     pub seat_category: Option<String>,
@@ -83,3 +90,14 @@ pub struct BookingDetail {
     pub start_station: Option<String>,
     pub end_station: Option<String>,
 }
+
+// A booking's seat and the stop range (schedule.stop_number) it occupies
+#[derive(Debug, Clone, FromRow)]
+pub struct SegmentBooking {
+    pub journey_id: Option<i64>,
+    pub seat_id: Option<i64>,
+    pub booking_status: Option<String>,
+    pub reservation_category: Option<String>,
+    pub from_stop: Option<i32>,
+    pub to_stop: Option<i32>,
+}
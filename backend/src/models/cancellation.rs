@@ -7,11 +7,13 @@ use sqlx::prelude::Type;
 #[derive(Debug, Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "CHAR(3)")]
 #[serde(rename_all = "UPPERCASE")]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 pub enum CancellationStatus {
     CNC, // Cancelled
     PND, // Pending
 }
 
+#[allow(dead_code, clippy::inherent_to_string, clippy::wrong_self_convention)]
 impl CancellationStatus {
     pub fn to_string(&self) -> String {
         match self {
@@ -22,6 +24,7 @@ impl CancellationStatus {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct CreateCancellation {
     pub status: CancellationStatus,
     pub refund_amount: f64,
@@ -29,6 +32,7 @@ pub struct CreateCancellation {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Cancellation {
     pub cid: i64,
     pub status: CancellationStatus,
@@ -24,3 +24,38 @@ pub struct CoachPricesByType {
     pub coach_type: Option<String>,
     pub fare: Option<f32>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct CompositionCoach {
+    pub coach_type: String,
+    pub coach_name: Option<String>, // Generated from the template prefix when missing
+    pub fare: f32,
+    pub capacity: Option<i64>, // Defaults to the template's capacity
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildComposition {
+    pub coaches: Vec<CompositionCoach>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderCoaches {
+    pub coach_ids: Vec<i64>, // Every coach of the train in the new order
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachCoach {
+    pub coach_id: i64,
+    pub position: Option<i32>, // Appended at the end when missing
+}
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct CompositionEntry {
+    pub coach_id: i64,
+    pub coach_name: Option<String>,
+    pub coach_type: Option<String>,
+    pub fare: Option<f32>,
+    pub position: Option<i32>,
+    pub total_seats: i64,
+    pub rac_seats: i64,
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FlexiFareStep {
+    pub sold_percent: i32,
+    pub multiplier: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplaceFlexiSteps {
+    pub reservation_category: Option<String>, // None sets the default for every class
+    pub steps: Vec<FlexiFareStep>,            // Empty turns flexi pricing off
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestFareQuote {
+    pub journey_id: i64,
+    pub reservation_category: String,
+    pub start_station_id: Option<i64>, // The journey's first stop when omitted
+    pub end_station_id: Option<i64>,   // The journey's last stop when omitted
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FareQuote {
+    pub quote_id: i64,
+    pub journey_id: i64,
+    pub reservation_category: String,
+    pub start_station_id: Option<i64>,
+    pub end_station_id: Option<i64>,
+    pub base_fare: f32,
+    pub multiplier: f32,
+    pub fare: f32,
+    pub expires_at: DateTime<Utc>,
+}
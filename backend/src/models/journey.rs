@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::services::availability::ClassAvailability;
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct JourneyDetailedResponse {
@@ -45,6 +47,7 @@ pub struct JourneyBetweenStations {
     pub journey_id: i64,
     pub train_id: Option<i64>,
     pub train_name: Option<String>,
+    pub train_type: Option<String>,
     pub start_station_id: Option<i64>,
     pub start_schedule_id: Option<i64>,
     pub start_station: Option<String>,
@@ -56,4 +59,118 @@ pub struct JourneyBetweenStations {
     pub start_stop_number: Option<i32>,
     pub end_stop_number: Option<i32>,
     pub travel_time: Option<i64>,
-}
\ No newline at end of file
+    pub journey_start_time: Option<DateTime<Utc>>, // Departure from the train's origin
+}
+
+// A class on a search result: segment availability plus the fare charged for it
+#[derive(Debug, Serialize)]
+pub struct JourneyClass {
+    #[serde(flatten)]
+    pub availability: ClassAvailability,
+    pub fare: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JourneySearchResult {
+    #[serde(flatten)]
+    pub journey: JourneyBetweenStations,
+    pub travel_date: Option<NaiveDate>, // Local date of boarding
+    pub boarding_day: Option<i64>,      // 1 = the day the train leaves its origin
+    pub alighting_day: Option<i64>,
+    pub classes: Vec<JourneyClass>,
+}
+
+impl JourneySearchResult {
+    pub fn cheapest_fare(&self) -> Option<f32> {
+        self.classes.iter().filter_map(|c| c.fare).min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+// A journey serving a station pair, as matched for the availability calendar
+#[derive(Debug, sqlx::FromRow)]
+pub struct JourneyCalendarRow {
+    pub journey_id: i64,
+    pub train_id: i64,
+    pub train_name: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub start_stop_number: i32,
+    pub end_stop_number: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarJourney {
+    pub journey_id: i64,
+    pub train_id: i64,
+    pub train_name: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub fare: Option<f32>,
+    pub availability: Option<ClassAvailability>, // None when the train has no such class
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub journeys: Vec<CalendarJourney>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelJourney {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateJourneyStatus {
+    pub journey_status: String, // 'DEPARTED', 'COMPLETED'
+}
+
+// A live booking on a journey, with what is needed to refund and notify its passenger
+#[derive(Debug, sqlx::FromRow)]
+pub struct AffectedBooking {
+    pub booking_id: i64,
+    pub pnr: Option<i64>,
+    pub email: Option<String>,
+    pub booking_status: Option<String>,
+    pub amount: Option<f32>,
+    pub txn_id: Option<i64>,
+    pub start_station_id: Option<i64>,
+    pub end_station_id: Option<i64>,
+}
+
+// One stop of a diverted journey; stops are given in travel order
+#[derive(Debug, Clone, Deserialize)]
+pub struct DivertedStop {
+    pub station_id: i64,
+    pub sched_toa: DateTime<Utc>,
+    pub sched_tod: DateTime<Utc>,
+    pub route_id: Option<i64>,
+}
+
+// Either shift every stop by `shift_minutes` or replace the stop list with `stops`
+#[derive(Debug, Deserialize)]
+pub struct RescheduleJourney {
+    pub shift_minutes: Option<i64>,
+    pub stops: Option<Vec<DivertedStop>>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ImpactKind {
+    StationRemoved, // Boarding or destination is no longer served (in that order)
+    TimeChanged,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookingImpact {
+    pub booking_id: i64,
+    pub pnr: Option<i64>,
+    pub start_station_id: Option<i64>,
+    pub end_station_id: Option<i64>,
+    pub impact: ImpactKind,
+    pub old_departure: Option<DateTime<Utc>>,
+    pub new_departure: Option<DateTime<Utc>>,
+    pub old_arrival: Option<DateTime<Utc>>,
+    pub new_arrival: Option<DateTime<Utc>>,
+}
@@ -9,4 +9,11 @@ pub mod transaction;
 pub mod journey;
 pub mod cancellation;
 pub mod coach;
-pub mod schedule;
\ No newline at end of file
+pub mod schedule;
+pub mod ticket;
+pub mod tte;
+pub mod tatkal;
+pub mod fare;
+pub mod promo;
+pub mod traveller;
+pub mod security;
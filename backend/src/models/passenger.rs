@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct CreatePassenger {
     pub name: String,
     pub age: i32,
@@ -11,6 +12,7 @@ pub struct CreatePassenger {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Passenger {
     pub pnr: i64,
     pub name: String,
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PromoCode {
+    pub promo_id: i64,
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String, // PERCENT or FLAT
+    pub discount_value: f32,
+    pub max_discount: Option<f32>,
+    pub min_amount: Option<f32>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: DateTime<Utc>,
+    pub train_types: Option<Json<Vec<String>>>,
+    pub classes: Option<Json<Vec<String>>>,
+    pub route_ids: Option<Json<Vec<i64>>>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub active: bool,
+    pub times_used: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePromoCode {
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String,
+    pub discount_value: f32,
+    pub max_discount: Option<f32>,
+    pub min_amount: Option<f32>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: DateTime<Utc>,
+    pub train_types: Option<Vec<String>>,
+    pub classes: Option<Vec<String>>,
+    pub route_ids: Option<Vec<i64>>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPromoActive {
+    pub active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyPromoCode {
+    pub code: String,
+    pub journey_id: i64,
+    pub reservation_category: String,
+    pub amount: f32, // Booking total before discount
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromoDiscount {
+    pub promo_id: i64,
+    pub code: String,
+    pub amount: f32,
+    pub discount: f32,
+    pub payable: f32,
+}
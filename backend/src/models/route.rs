@@ -25,12 +25,18 @@ pub struct AddIntermediateStation {
     pub distance: f32, // Distance from source station
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReorderRouteStations {
+    pub stations: Vec<AddIntermediateStation>, // Full station list in the new order, source first
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct RouteStation {
     pub route_id: i64,
     pub station_id: Option<i64>,
     pub source_station_name: Option<String>,
     pub distance: Option<f32>, // Distance from source station
+    pub order_from_start: Option<i32>,
 }
 
 #[derive(Serialize)]
@@ -49,4 +55,22 @@ pub struct RelativeStation {
     pub station_id: i64,
     pub station_name: Option<String>,
     pub distance_from_given_station: Option<f64>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, FromRow)]
+pub struct RouteStop {
+    pub station_id: i64,
+    pub station_name: Option<String>,
+    pub station_type: Option<String>,
+    pub distance: Option<f32>, // Distance from source station
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct RouteStationRow {
+    pub route_id: i64,
+    pub route_name: Option<String>,
+    pub station_id: i64,
+    pub station_name: Option<String>,
+    pub distance: Option<f32>, // Distance from source station
+    pub order_from_start: Option<i32>,
+}
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::models::route::RouteStation;
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Schedule {
     pub sched_id: i64,
@@ -44,11 +48,34 @@ pub struct UpdateSchedule {
     pub route_id: Option<i64>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Serialize)]
 pub struct RoutesBetweenStations {
     pub route_id: Option<i64>,
     pub route_name: Option<String>,
     pub source_station_id: Option<i64>,
     pub destination_station_id: Option<i64>,
     pub distance: Option<f64>,
+    pub start_order: Option<i32>,
+    pub end_order: Option<i32>,
+    pub stops: Vec<RouteStation>, // Stations between source and destination, in travel order
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateSchedule {
+    pub route_id: i64,
+    pub departure_time: Option<DateTime<Utc>>, // Defaults to the journey's start_time
+    pub avg_speed: Option<f32>, // km/h, defaults by train type
+    pub dwell_minutes: Option<HashMap<String, i64>>, // Keyed by station_type ("JN", "TM", "HT", "ST")
+    pub skip_halts: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeneratedStop {
+    pub station_id: i64,
+    pub station_name: Option<String>,
+    pub station_type: Option<String>,
+    pub stop_number: i32,
+    pub distance: f32, // Distance from the journey's start station
+    pub sched_toa: DateTime<Utc>,
+    pub sched_tod: DateTime<Utc>,
 }
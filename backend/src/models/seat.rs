@@ -3,6 +3,7 @@ use sqlx::FromRow;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "ENUM", rename_all = "UPPERCASE")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum SeatType {
     SL,
     SU,
@@ -15,6 +16,7 @@ pub enum SeatType {
 
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "ENUM", rename_all = "UPPERCASE")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum SeatCategory {
     CNF,
     RAC,
@@ -27,6 +29,7 @@ pub struct Seat {
     pub seat_type: Option<String>,
     pub coach_id: Option<i64>,
     pub seat_category: Option<String>,
+    pub quota: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,10 +38,42 @@ pub struct CreateSeat {
     pub seat_type: SeatType,
     pub coach_id: i64,
     pub seat_category: SeatCategory,
+    pub quota: Option<String>, // Defaults to GN
 }
 
-#[derive(serde::Serialize)]
-pub struct SeatCount {
-    pub reservation_category: Option<String>,
-    pub seat_count: Option<i64>,
+// Assigns a quota to the listed seats, or to every seat of `coach_id`
+#[derive(Debug, Deserialize)]
+pub struct SetSeatQuota {
+    pub quota: String,
+    #[serde(default)]
+    pub seat_ids: Vec<i64>,
+    pub coach_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct QuotaSeatCount {
+    pub coach_type: Option<String>,
+    pub quota: String,
+    pub seats: i64,
+    pub unsold: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatOccupancy {
+    Free,
+    Booked,
+    RacShared, // One of the two RAC passengers is on board
+    Held,      // Booking awaiting payment
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeatMapEntry {
+    pub seat_id: i64,
+    pub seat_no: Option<i64>,
+    pub seat_type: Option<String>,
+    pub seat_category: Option<String>,
+    pub quota: String,
+    pub occupancy: SeatOccupancy,
+    pub occupants: usize,
 }
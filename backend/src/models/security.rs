@@ -0,0 +1,34 @@
+// models/security.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+
+// Failed sign-in counter for an account or a client IP
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LoginFailure {
+    pub scope: String, // 'ACCOUNT', 'IP'
+    pub subject: String,
+    pub failures: i32,
+    pub first_failure_at: DateTime<Utc>,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct SecurityEvent {
+    pub event_id: i64,
+    pub event_type: String,
+    pub email: Option<String>,
+    pub client_ip: Option<String>,
+    pub details: Option<Json<serde_json::Value>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecurityEventQuery {
+    pub email: Option<String>,
+    pub client_ip: Option<String>,
+    pub event_type: Option<String>,
+    pub limit: Option<u32>,
+}
@@ -1,14 +1,59 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 pub struct StationResponse {
     pub station_id: i64,
     pub station_name: Option<String>,
     pub station_type: Option<String>,
+    pub station_code: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub state: Option<String>,
+    pub zone: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateStation {
     pub station_name: String,
     pub station_type: String, // Should be "JN", "TM", "HT", "ST"
-}
\ No newline at end of file
+    pub station_code: Option<String>, // e.g. "NDLS"
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub state: Option<String>,
+    pub zone: Option<String>, // Railway zone, e.g. "NR"
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStation {
+    pub station_name: Option<String>,
+    pub station_type: Option<String>,
+    pub station_code: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub state: Option<String>,
+    pub zone: Option<String>,
+    pub aliases: Option<Vec<String>>, // Replaces the existing aliases when present
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct StationAlias {
+    pub station_id: i64,
+    pub alias_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StationSuggestion {
+    #[serde(flatten)]
+    pub station: StationResponse,
+    pub aliases: Vec<String>,
+    pub matched_on: String, // "code", "name", "alias" or "fuzzy"
+    pub score: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearbyStation {
+    #[serde(flatten)]
+    pub station: StationResponse,
+    pub distance_km: f64,
+}
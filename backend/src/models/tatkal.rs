@@ -0,0 +1,42 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TatkalFare {
+    pub reservation_category: String,
+    pub premium_rate: f32,
+    pub min_premium: f32,
+    pub max_premium: f32,
+    pub opens_at: NaiveTime, // IST, on the day before departure from origin
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTatkalFare {
+    pub premium_rate: f32,
+    pub min_premium: f32,
+    pub max_premium: f32,
+    pub opens_at: NaiveTime,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TatkalWindow {
+    pub opens_at: DateTime<Utc>,
+    pub closes_at: DateTime<Utc>, // Chart preparation, or departure if that comes first
+}
+
+impl TatkalWindow {
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.opens_at <= now && now < self.closes_at
+    }
+}
+
+// Tatkal terms for one class of a journey
+#[derive(Debug, Serialize)]
+pub struct TatkalClassQuote {
+    pub reservation_category: String,
+    pub base_fare: f32,
+    pub premium: f32,
+    pub fare: f32,
+    pub window: TatkalWindow,
+}
@@ -0,0 +1,84 @@
+// models/ticket.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// One passenger's booking as printed on an e-ticket
+#[derive(Debug, Clone, FromRow)]
+pub struct TicketRow {
+    pub pnr: i64,
+    pub pass_name: Option<String>,
+    pub email: Option<String>, // Account that booked the passenger
+    pub age: Option<i32>,
+    pub sex: Option<String>,
+    pub booking_status: Option<String>,
+    pub amount: Option<f32>,
+    pub txn_id: Option<i64>,
+    pub payment_mode: Option<String>,
+    pub reservation_status: Option<String>,
+    pub reservation_category: Option<String>,
+    pub seat_no: Option<i64>,
+    pub seat_type: Option<String>,
+    pub coach_name: Option<String>,
+    pub train_id: Option<i64>,
+    pub train_name: Option<String>,
+    pub journey_id: Option<i64>,
+    pub start_station: Option<String>,
+    pub end_station: Option<String>,
+    pub departure_time: Option<DateTime<Utc>>, // From the boarding station
+    pub arrival_time: Option<DateTime<Utc>>,   // At the destination
+}
+
+#[derive(Debug, Serialize)]
+pub struct TicketPassenger {
+    pub pnr: i64,
+    pub name: Option<String>,
+    pub age: Option<i32>,
+    pub sex: Option<String>,
+    pub booking_status: Option<String>,
+    pub reservation_status: Option<String>,
+    pub coach_name: Option<String>,
+    pub seat_no: Option<i64>,
+    pub seat_type: Option<String>,
+    pub fare: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Ticket {
+    pub txn_id: Option<i64>,
+    pub payment_mode: Option<String>,
+    pub journey_id: Option<i64>,
+    pub train_id: Option<i64>,
+    pub train_name: Option<String>,
+    pub reservation_category: Option<String>,
+    pub from_station: Option<String>,
+    pub to_station: Option<String>,
+    pub departure_time: Option<DateTime<Utc>>,
+    pub arrival_time: Option<DateTime<Utc>>,
+    pub passengers: Vec<TicketPassenger>,
+    pub total_fare: f32,
+    pub issued_at: DateTime<Utc>,
+    pub signed_payload: String, // Also encoded in the QR code
+}
+
+// What the QR code vouches for. Kept small so the code stays scannable.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TicketClaims {
+    pub pnrs: Vec<i64>,
+    pub txn_id: Option<i64>,
+    pub journey_id: Option<i64>,
+    pub train_id: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub departure: Option<DateTime<Utc>>,
+    pub seats: Vec<String>, // "S3/21" per passenger, "WL" when unassigned
+    pub statuses: Vec<String>, // Booking status per passenger when issued
+    pub issued_at: DateTime<Utc>,
+    pub exp: DateTime<Utc>, // Checkers refuse the ticket after this
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyTicket {
+    pub signed_payload: String,
+}
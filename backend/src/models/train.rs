@@ -15,6 +15,9 @@ pub enum TrainType {
     IN // Intercity
 }
 
+// The TrainType codes, for validating train types given as text
+pub const TRAIN_TYPES: &[&str] = &["EX", "ML", "SF", "VB", "MM", "IN"];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub  struct Train {
     pub train_no: i64,
@@ -6,6 +6,7 @@ use sqlx::prelude::Type;
 #[derive(Debug, Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "CHAR(3)")]
 #[serde(rename_all = "UPPERCASE")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum PaymentMode {
     CSH,    // Cash
     CCD,    // Credit Card
@@ -38,6 +39,4 @@ pub struct UpdateTransactionStatus {
 #[derive(Debug, Deserialize)]
 pub struct CancelBookingRequest {
     pub booking_id: i64,
-    pub refund_amount: f64,
-    pub txn_id: i64,
 }
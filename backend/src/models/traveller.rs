@@ -0,0 +1,39 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::FromRow;
+
+// ID numbers are only ever shown with their last four characters
+fn serialize_masked<S: Serializer>(id_number: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match id_number {
+        Some(number) => {
+            let visible: String = number.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+            let hidden = number.chars().count().saturating_sub(visible.chars().count());
+            serializer.serialize_some(&format!("{}{}", "X".repeat(hidden), visible))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Traveller {
+    pub traveller_id: i64,
+    pub email: String,
+    pub name: String,
+    pub date_of_birth: NaiveDate,
+    pub sex: String,
+    pub disability: bool,
+    pub id_type: Option<String>,
+    #[serde(serialize_with = "serialize_masked")]
+    pub id_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveTraveller {
+    pub name: String,
+    pub date_of_birth: NaiveDate,
+    pub sex: String,
+    #[serde(default)]
+    pub disability: bool,
+    pub id_type: Option<String>,
+    pub id_number: Option<String>,
+}
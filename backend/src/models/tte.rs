@@ -0,0 +1,65 @@
+// models/tte.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Scan a ticket QR (`signed_payload`) or type a PNR
+#[derive(Debug, Deserialize)]
+pub struct VerifyOnboard {
+    pub journey_id: i64,
+    pub signed_payload: Option<String>,
+    pub pnr: Option<i64>,
+    pub station_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkPassenger {
+    pub onboard_status: String, // 'BOARDED', 'NO_SHOW'
+    pub station_id: Option<i64>,
+}
+
+// Give the berth of a no-show passenger to an RAC passenger
+#[derive(Debug, Deserialize)]
+pub struct ReallocateBerth {
+    pub no_show_pnr: i64,
+    pub rac_pnr: i64,
+    pub station_id: Option<i64>,
+}
+
+// A passenger on the chart of a journey
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ChartEntry {
+    pub pnr: i64,
+    pub pass_name: Option<String>,
+    pub age: Option<i32>,
+    pub sex: Option<String>,
+    pub booking_id: i64,
+    pub booking_status: Option<String>,
+    pub onboard_status: String,
+    pub reservation_status: Option<String>,
+    pub reservation_category: Option<String>,
+    pub seat_id: Option<i64>,
+    pub seat_no: Option<i64>,
+    pub seat_type: Option<String>,
+    pub coach_id: Option<i64>,
+    pub coach_name: Option<String>,
+    pub start_station_id: Option<i64>,
+    pub end_station_id: Option<i64>,
+    pub from_stop: Option<i32>,
+    pub to_stop: Option<i32>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct RunningEvent {
+    pub event_id: i64,
+    pub journey_id: i64,
+    pub station_id: Option<i64>,
+    pub event_type: String,
+    pub pnr: Option<i64>,
+    pub booking_id: Option<i64>,
+    pub seat_id: Option<i64>,
+    pub previous_seat_id: Option<i64>,
+    pub examiner_email: String,
+    pub recorded_at: Option<DateTime<Utc>>,
+}
@@ -1,9 +1,14 @@
 // models/user.rs
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::logging::REDACTED;
+
 #[derive(Debug, Serialize, FromRow)]
+#[allow(dead_code)]
 pub struct User {
     pub name: String,
     pub email: String,
@@ -17,7 +22,7 @@ pub struct UserResponse {
     pub role: Option<String>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct CreateUser {
     pub name: String,
     pub email: String,
@@ -25,14 +30,92 @@ pub struct CreateUser {
     pub role: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct LoginUser {
     pub email: String,
     pub password: String,
 }
 
+// Name can be changed by the user; role only by an admin
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateUser {
-    pub name: String,
-    pub role: String
-}
\ No newline at end of file
+    pub name: Option<String>,
+    pub role: Option<String>
+}
+
+#[derive(Deserialize)]
+pub struct ChangePassword {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountEmail {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmToken {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPassword {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct NotificationPreference {
+    pub email: String,
+    pub email_enabled: bool,
+    pub webhook_enabled: bool,
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationPreference {
+    pub email_enabled: Option<bool>,
+    pub webhook_enabled: Option<bool>,
+    pub webhook_url: Option<String>,
+}
+
+// Passwords and tokens never appear in Debug output
+
+impl fmt::Debug for CreateUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUser")
+            .field("name", &self.name)
+            .field("email", &self.email)
+            .field("password", &REDACTED)
+            .field("role", &self.role)
+            .finish()
+    }
+}
+
+impl fmt::Debug for LoginUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginUser").field("email", &self.email).field("password", &REDACTED).finish()
+    }
+}
+
+impl fmt::Debug for ChangePassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangePassword")
+            .field("current_password", &REDACTED)
+            .field("new_password", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ResetPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResetPassword").field("token", &REDACTED).field("new_password", &REDACTED).finish()
+    }
+}
+
+impl fmt::Debug for ConfirmToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfirmToken").field("token", &REDACTED).finish()
+    }
+}
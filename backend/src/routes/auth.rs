@@ -1,4 +1,5 @@
 use actix_web::web;
+use crate::handlers::auth_handler::*;
 use crate::handlers::user_handler::{create_user, get_user_by_email_and_password, get_user_by_email};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -7,5 +8,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/login", web::get().to(get_user_by_email))
             .route("/login", web::post().to(get_user_by_email_and_password))
             .route("/signup", web::post().to(create_user))
+            .route("/logout", web::post().to(logout))
+            .route("/verify-email/request", web::post().to(request_email_verification))
+            .route("/verify-email/confirm", web::post().to(confirm_email_verification))
+            .route("/password-reset/request", web::post().to(request_password_reset))
+            .route("/password-reset/confirm", web::post().to(confirm_password_reset))
     );
 }
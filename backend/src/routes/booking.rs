@@ -4,11 +4,14 @@ use crate::handlers::booking_handler::*;
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/booking")
-            .route("seat/cnf/{journey_id}", web::get().to(get_cnf_seat_count_by_coach_category))
-            .route("seat/rac/{journey_id}", web::get().to(get_rac_seat_count_by_coach_category))
-            .route("seat/wl/{journey_id}", web::get().to(get_wl_seat_count_by_coach_category))
+            .route("/availability/{journey_id}", web::get().to(get_journey_availability))
             .route("/book", web::post().to(create_group_booking_handler))
             .route("/details", web::get().to(get_booking_details_by_email))
             .route("/cancel", web::post().to(cancel_booking_handler))
+            .route("/quote", web::post().to(create_fare_quote)) // POST /api/booking/quote
+            .route("/flexi/{train_type}", web::get().to(get_flexi_steps)) // GET /api/booking/flexi/{train_type}
+            .route("/flexi/{train_type}", web::put().to(replace_flexi_steps)) // PUT /api/booking/flexi/{train_type}
+            .route("/tatkal/{journey_id}", web::get().to(get_tatkal_quotes)) // GET /api/booking/tatkal/{journey_id}
+            .route("/tatkal/fares/{reservation_category}", web::put().to(update_tatkal_fare)) // PUT /api/booking/tatkal/fares/{class}
     );
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use actix_web::web;
-use crate::handlers::{coach_handler::get_coach_prices, journey_handler::*};
+use crate::handlers::{coach_handler::get_coach_prices, journey_handler::*, seat_handler::get_journey_seat_map};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -9,8 +9,17 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/id/{journey_id}", web::get().to(get_journey_by_id)) // GET /api/journeys/{journey_id}
             .route("/id/{journey_id}/update", web::put().to(update_journey)) // PUT /api/journeys/{journey_id}/update
             .route("/id/{journey_id}/delete", web::delete().to(delete_journey)) // DELETE /api/journeys/{journey_id}/delete
+            .route("/id/{journey_id}/cancel", web::post().to(cancel_journey)) // POST /api/journeys/{journey_id}/cancel
+            .route("/id/{journey_id}/status", web::put().to(update_journey_status)) // PUT /api/journeys/{journey_id}/status
+            .route("/id/{journey_id}/reschedule/preview", web::post().to(preview_reschedule)) // POST /api/journeys/{journey_id}/reschedule/preview
+            .route("/id/{journey_id}/reschedule", web::post().to(apply_reschedule)) // POST /api/journeys/{journey_id}/reschedule
+            .route("/id/{journey_id}/quotas", web::get().to(get_journey_quotas)) // GET /api/journeys/{journey_id}/quotas
+            .route("/id/{journey_id}/quotas", web::put().to(set_journey_seat_quota)) // PUT /api/journeys/{journey_id}/quotas
+            .route("/id/{journey_id}/chart", web::post().to(prepare_journey_chart)) // POST /api/journeys/{journey_id}/chart
             .route("/train/id/{train_id}", web::get().to(get_journeys_by_train)) // GET /api/journeys/train/{train_id}
             .route("/search", web::get().to(get_journey_by_stations))
+            .route("/calendar", web::get().to(get_journey_calendar)) // GET /api/journeys/calendar?source_station_id=..&destination_station_id=..&reservation_category=..
             .route("/fare/{journey_id}", web::get().to(get_coach_prices)) // GET /api/journeys/coach/prices/{journey_id}
+            .route("/{journey_id}/coaches/{coach_id}/seatmap", web::get().to(get_journey_seat_map)) // GET /api/journeys/{journey_id}/coaches/{coach_id}/seatmap
     );
 }
\ No newline at end of file
@@ -0,0 +1,7 @@
+use actix_web::web;
+use crate::handlers::metrics_handler::*;
+
+// Mounted on the metrics listener only (see main.rs), never on the API server
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(get_metrics));
+}
@@ -11,6 +11,11 @@ pub mod booking;
 pub mod transaction;
 pub mod passenger;
 pub mod stat;
+pub mod ticket;
+pub mod tte;
+pub mod promo;
+pub mod security;
+pub mod metrics;
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     auth::config(cfg);
@@ -24,4 +29,8 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     transaction::config(cfg);
     passenger::config(cfg);
     stat::config(cfg);
+    ticket::config(cfg);
+    tte::config(cfg);
+    promo::config(cfg);
+    security::config(cfg);
 }
\ No newline at end of file
@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::handlers::promo_handler::*;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/promos")
+            .route("", web::get().to(get_promo_codes)) // GET /api/promos
+            .route("/add", web::post().to(create_promo_code)) // POST /api/promos/add
+            .route("/apply", web::post().to(apply_promo_code)) // POST /api/promos/apply
+            .route("/{code}/active", web::put().to(set_promo_active)) // PUT /api/promos/{code}/active
+    );
+}
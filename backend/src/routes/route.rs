@@ -8,7 +8,13 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/add", web::post().to(create_route))
             .route("/id/{route_id}/add", web::post().to(add_intermediate_station))
             .route("/id/{route_id}", web::get().to(get_route_stations))
+            .route("/id/{route_id}/order", web::put().to(reorder_route_stations))
+            .route("/id/{route_id}/station/{station_id}", web::delete().to(remove_route_station))
             .route("/between", web::get().to(get_routes_between_stations))
             .route("/relative", web::get().to(get_relative_stations))
+            .route("/path", web::get().to(get_shortest_path))
+            .route("/paths", web::get().to(get_k_shortest_paths))
+            .route("/reachable", web::get().to(get_reachable_stations))
+            .route("/network/rebuild", web::post().to(rebuild_rail_network))
     );
 }
\ No newline at end of file
@@ -10,5 +10,6 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/update/id/{id}", web::put().to(update_schedule))                    // PUT /api/schedules/{id}/update
             .route("/delete/id/{id}", web::delete().to(delete_schedule))                 // DELETE /api/schedules/{id}/delete
             .route("/journey/{journey_id}", web::get().to(get_schedule_by_journey))   // GET /api/schedules/journey/{journey_id}
+            .route("/journey/{journey_id}/generate", web::post().to(generate_schedule_from_route)) // POST /api/schedules/journey/{journey_id}/generate
     );
 }
\ No newline at end of file
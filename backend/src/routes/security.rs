@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::handlers::security_handler::*;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/security")
+            .route("/events", web::get().to(get_security_events))
+            .route("/lockouts", web::get().to(get_lockouts))
+            .route("/accounts/{email}/unlock", web::post().to(unlock_account))
+            .route("/ips/{ip}/unlock", web::post().to(unlock_ip))
+    );
+}
@@ -11,6 +11,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/busiest-station", web::get().to(busiest_station))
             .route("/rank-running-trains", web::get().to(rank_running_trains_by_bookings))
             .route("/busiest-time-period", web::get().to(busiest_time_period))
-            .route("/reservation-status-distribution", web::get().to(reservation_status_distribution)),
+            .route("/reservation-status-distribution", web::get().to(reservation_status_distribution))
+            .route("/revenue", web::get().to(revenue_summary)),
     );
 }
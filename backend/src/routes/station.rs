@@ -1,6 +1,6 @@
 use actix_web::web;
 use crate::handlers::station_handler::{
-    create_station, get_all_stations, get_station_by_name,
+    create_station, get_all_stations, get_nearby_stations, get_station_by_name, suggest_stations, update_station,
 };
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -8,6 +8,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("/api/station")
             .route("/add", web::post().to(create_station))
             .route("/all", web::get().to(get_all_stations))
+            .route("/suggest", web::get().to(suggest_stations))
+            .route("/nearby", web::get().to(get_nearby_stations))
+            .route("/id/{station_id}", web::put().to(update_station))
             .route("", web::get().to(get_station_by_name))
     );
 }
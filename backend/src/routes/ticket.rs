@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::handlers::ticket_handler::*;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/tickets")
+            .route("/pnr/{pnr}", web::get().to(get_ticket_by_pnr)) // GET /api/tickets/pnr/{pnr}?format=pdf|html|json
+            .route("/txn/{txn_id}", web::get().to(get_ticket_by_txn)) // GET /api/tickets/txn/{txn_id}?format=pdf|html|json
+            .route("/public-key", web::get().to(get_ticket_public_key))
+            .route("/verify", web::post().to(verify_ticket))
+    );
+}
@@ -13,18 +13,23 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/add", web::post().to(create_train)) // POST /api/trains/create
             .route("/detailed", web::get().to(get_trains_detailed)) // GET /api/trains/detailed
             .route("/coaches/id/{train_id}", web::get().to(get_coaches_for_train)) // GET /api/trains/{train_id}/coaches
+            .route("/composition/id/{train_id}", web::get().to(get_train_composition)) // GET /api/trains/composition/id/{train_id}
+            .route("/composition/id/{train_id}", web::post().to(build_train_composition)) // POST /api/trains/composition/id/{train_id}
+            .route("/composition/id/{train_id}/order", web::put().to(reorder_train_coaches))
+            .route("/composition/id/{train_id}/attach", web::post().to(attach_coach))
+            .route("/composition/id/{train_id}/detach/{coach_id}", web::post().to(detach_coach))
     );
 
     cfg.service(
         web::scope("/api/coaches")
             .route("/add", web::post().to(create_coach)) // POST /api/coaches/add
+            .route("/templates", web::get().to(get_coach_templates)) // GET /api/coaches/templates
             .route("/seats/id/{coach_id}", web::get().to(get_seats_by_coach)) // GET /api/coaches/{coach_id}/seats
     );
 
     cfg.service(
         web::scope("/api/seats")
             .route("/add", web::post().to(create_seat)) // POST /api/seats/add
-            .route("/total/cnf/{train_id}", web::get().to(get_cnf_seat_count_by_coach_category))
-            .route("/total/rac/{train_id}", web::get().to(get_rac_seat_count_by_coach_category))
+            .route("/quota", web::put().to(set_seat_quota)) // PUT /api/seats/quota
     );
 }
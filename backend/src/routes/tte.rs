@@ -0,0 +1,13 @@
+use actix_web::web;
+use crate::handlers::tte_handler::*;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/tte")
+            .route("/verify", web::post().to(verify_onboard)) // POST /api/tte/verify
+            .route("/journeys/{journey_id}/coaches/{coach_id}/chart", web::get().to(get_coach_chart))
+            .route("/journeys/{journey_id}/passengers/{pnr}/status", web::post().to(mark_passenger))
+            .route("/journeys/{journey_id}/reallocate", web::post().to(reallocate_berth))
+            .route("/journeys/{journey_id}/events", web::get().to(get_running_events))
+    );
+}
@@ -1,5 +1,5 @@
 use actix_web::web;
-use crate::handlers::user_handler::*;
+use crate::handlers::{traveller_handler::*, user_handler::*};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -9,5 +9,13 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .route("/{email}", web::get().to(get_user_by_email))
         .route("/{email}", web::put().to(update_user))
         .route("/{email}", web::delete().to(delete_user))
+        .route("/{email}/password", web::post().to(change_password))
+        .route("/{email}/notifications", web::get().to(get_notification_preferences))
+        .route("/{email}/notifications", web::put().to(update_notification_preferences))
+        .route("/{email}/travellers", web::get().to(get_travellers))
+        .route("/{email}/travellers", web::post().to(create_traveller))
+        .route("/{email}/travellers/{traveller_id}", web::get().to(get_traveller))
+        .route("/{email}/travellers/{traveller_id}", web::put().to(update_traveller))
+        .route("/{email}/travellers/{traveller_id}", web::delete().to(delete_traveller))
     );
 }
\ No newline at end of file
@@ -0,0 +1,193 @@
+// services/accounts.rs
+//
+// Passwords, login sessions and single-use account tokens. Passwords are Argon2
+// hashes; sessions and tokens are random strings handed to the client, of which
+// only the SHA-256 digest is stored.
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+pub const VERIFY_EMAIL: &str = "VERIFY_EMAIL";
+pub const PASSWORD_RESET: &str = "PASSWORD_RESET";
+
+// How long a token of each purpose stays usable
+pub fn token_lifetime(purpose: &str) -> Duration {
+    match purpose {
+        PASSWORD_RESET => Duration::hours(1),
+        _ => Duration::hours(24),
+    }
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("Password must be at least {} characters", MIN_PASSWORD_LEN));
+    }
+    if !password.chars().any(|c| c.is_ascii_alphabetic()) || !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err("Password must contain both letters and digits".to_string());
+    }
+    Ok(())
+}
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+// Checks a password against the stored value. Rows created before hashing hold
+// the plaintext; those still match, and the caller should re-hash them.
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    match PasswordHash::new(stored) {
+        Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok(),
+        Err(_) => password == stored,
+    }
+}
+
+pub fn needs_rehash(stored: &str) -> bool {
+    PasswordHash::new(stored).is_err()
+}
+
+fn new_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+pub fn token_hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+// Stores a new password and signs the user out everywhere
+pub async fn set_password(
+    tx: &mut Transaction<'_, MySql>,
+    email: &str,
+    password: &str,
+) -> Result<Result<(), String>, sqlx::Error> {
+    let hash = match hash_password(password) {
+        Ok(hash) => hash,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    sqlx::query("UPDATE users SET password = ?, password_changed_at = NOW() WHERE email = ?")
+        .bind(&hash)
+        .bind(email)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM user_session WHERE email = ?")
+        .bind(email)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(Ok(()))
+}
+
+// Returns the bearer token for a new session lasting `days`
+pub async fn create_session(pool: &MySqlPool, email: &str, days: i64) -> Result<String, sqlx::Error> {
+    let token = new_token();
+    sqlx::query("INSERT INTO user_session (token_hash, email, expires_at) VALUES (?, ?, ?)")
+        .bind(token_hash(&token))
+        .bind(email)
+        .bind(Utc::now() + Duration::days(days))
+        .execute(pool)
+        .await?;
+    Ok(token)
+}
+
+// Email and role of the user a bearer token belongs to, if the session is live
+pub async fn session_user(pool: &MySqlPool, token: &str) -> Result<Option<(String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT u.email, COALESCE(u.role, 'USER')
+        FROM user_session s
+        JOIN users u ON u.email = s.email
+        WHERE s.token_hash = ? AND s.expires_at > NOW()
+        "#,
+    )
+    .bind(token_hash(token))
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn end_session(pool: &MySqlPool, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_session WHERE token_hash = ? OR expires_at <= NOW()")
+        .bind(token_hash(token))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Issues a token for `purpose`, replacing any unused one issued before
+pub async fn issue_token(pool: &MySqlPool, email: &str, purpose: &str) -> Result<String, sqlx::Error> {
+    let token = new_token();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM account_token WHERE email = ? AND purpose = ? AND used_at IS NULL")
+        .bind(email)
+        .bind(purpose)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO account_token (token_hash, email, purpose, expires_at) VALUES (?, ?, ?, ?)")
+        .bind(token_hash(&token))
+        .bind(email)
+        .bind(purpose)
+        .bind(Utc::now() + token_lifetime(purpose))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(token)
+}
+
+// Marks a live token used and returns the email it was issued to. Expired, used
+// or unknown tokens give None.
+pub async fn consume_token(
+    tx: &mut Transaction<'_, MySql>,
+    token: &str,
+    purpose: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let hash = token_hash(token);
+    let email = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT email FROM account_token
+        WHERE token_hash = ? AND purpose = ? AND used_at IS NULL AND expires_at > NOW()
+        FOR UPDATE
+        "#,
+    )
+    .bind(&hash)
+    .bind(purpose)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    if email.is_some() {
+        sqlx::query("UPDATE account_token SET used_at = NOW() WHERE token_hash = ?")
+            .bind(&hash)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(email)
+}
+
+// Subject and body of the message carrying an account token
+pub fn render_token_mail(purpose: &str, token: &str) -> (String, String) {
+    let hours = token_lifetime(purpose).num_hours();
+    match purpose {
+        PASSWORD_RESET => (
+            "Reset your password".to_string(),
+            format!(
+                "Use this code to choose a new password: {}\nIt expires in {} hour(s) and can be used once. If you did not ask for a reset, ignore this message.",
+                token, hours
+            ),
+        ),
+        _ => (
+            "Verify your email address".to_string(),
+            format!("Use this code to verify your email address: {}\nIt expires in {} hours.", token, hours),
+        ),
+    }
+}
@@ -0,0 +1,353 @@
+// services/availability.rs
+//
+// Per-class seat availability for a journey segment, derived from the coaches
+// actually attached to the train and the bookings overlapping the segment.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+use crate::models::booking::SegmentBooking;
+use crate::services::quota::GENERAL;
+use crate::services::occupancy::{
+    fetch_bookings_for_journeys, fetch_journey_stops, overlaps, push_id_list, segment_between,
+};
+
+// Display order of classes, most premium first
+pub const CLASS_ORDER: &[&str] = &["AC1", "FC", "AC2", "AC3", "CC", "SL", "2S"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassAvailability {
+    pub reservation_category: String,
+    pub capacity: i64,      // Passengers the class can carry: CNF seats + 2 per RAC berth
+    pub cnf_seats: i64,
+    pub rac_berths: i64,
+    pub cnf_available: i64,
+    pub rac_available: i64, // Free RAC places (two per berth)
+    pub waitlist: i64,      // Current waitlist length for the segment
+    pub status: String,     // "AVAILABLE-12", "RAC-3" or "WL-5"
+}
+
+impl ClassAvailability {
+    pub fn is_available(&self) -> bool {
+        self.cnf_available > 0 || self.rac_available > 0
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct TrainSeat {
+    pub journey_id: i64,
+    pub seat_id: i64,
+    pub seat_category: Option<String>,
+    pub coach_type: Option<String>,
+    pub quota: Option<String>,
+}
+
+fn class_rank(class: &str) -> usize {
+    CLASS_ORDER.iter().position(|c| *c == class).unwrap_or(CLASS_ORDER.len())
+}
+
+// Availability status as shown to passengers: the next place they would get
+fn availability_status(cnf_available: i64, rac_available: i64, rac_berths: i64, waitlist: i64) -> String {
+    if cnf_available > 0 {
+        format!("AVAILABLE-{}", cnf_available)
+    } else if rac_available > 0 {
+        format!("RAC-{}", rac_berths * 2 - rac_available + 1)
+    } else {
+        format!("WL-{}", waitlist + 1)
+    }
+}
+
+// Availability of every class present in `seats` for the given stop range
+pub fn compute_availability(
+    seats: &[TrainSeat],
+    bookings: &[SegmentBooking],
+    segment: (i32, i32),
+) -> Vec<ClassAvailability> {
+    let overlapping: Vec<&SegmentBooking> = bookings.iter().filter(|b| overlaps(b, segment)).collect();
+
+    let mut occupants: BTreeMap<i64, i64> = BTreeMap::new();
+    for booking in &overlapping {
+        if let Some(seat_id) = booking.seat_id {
+            *occupants.entry(seat_id).or_default() += 1;
+        }
+    }
+
+    let mut classes: BTreeMap<String, ClassAvailability> = BTreeMap::new();
+    for seat in seats {
+        let Some(coach_type) = &seat.coach_type else { continue };
+        let entry = classes.entry(coach_type.clone()).or_insert_with(|| ClassAvailability {
+            reservation_category: coach_type.clone(),
+            capacity: 0,
+            cnf_seats: 0,
+            rac_berths: 0,
+            cnf_available: 0,
+            rac_available: 0,
+            waitlist: 0,
+            status: String::new(),
+        });
+
+        let taken = occupants.get(&seat.seat_id).copied().unwrap_or(0);
+        if seat.seat_category.as_deref() == Some("RAC") {
+            entry.rac_berths += 1;
+            entry.capacity += 2;
+            entry.rac_available += (2 - taken).max(0);
+        } else {
+            entry.cnf_seats += 1;
+            entry.capacity += 1;
+            if taken == 0 {
+                entry.cnf_available += 1;
+            }
+        }
+    }
+
+    for booking in overlapping.iter().filter(|b| b.seat_id.is_none()) {
+        if let Some(entry) = booking.reservation_category.as_ref().and_then(|c| classes.get_mut(c)) {
+            entry.waitlist += 1;
+        }
+    }
+
+    let mut result: Vec<ClassAvailability> = classes
+        .into_values()
+        .map(|mut c| {
+            c.status = availability_status(c.cnf_available, c.rac_available, c.rac_berths, c.waitlist);
+            c
+        })
+        .collect();
+    result.sort_by_key(|c| class_rank(&c.reservation_category));
+    result
+}
+
+// Seats of each journey's train with the quota each is sold under on that journey,
+// keyed by journey_id. `seat_quota` applies per-journey overrides and chart release.
+pub async fn fetch_journey_seats(
+    pool: &MySqlPool,
+    journey_ids: &[i64],
+) -> Result<HashMap<i64, Vec<TrainSeat>>, sqlx::Error> {
+    if journey_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+        r#"
+        SELECT j.journey_id, s.seat_id, s.seat_category, c.coach_type, seat_quota(j.journey_id, s.seat_id) AS quota
+        FROM seat s
+        JOIN coach c ON c.coach_id = s.coach_id
+        JOIN journey j ON j.train_id = c.train_id
+        WHERE j.journey_id IN ("#,
+    );
+    push_id_list(&mut query, journey_ids);
+    let seats = query.build_query_as::<TrainSeat>().fetch_all(pool).await?;
+
+    let mut seats_by_journey: HashMap<i64, Vec<TrainSeat>> = HashMap::new();
+    for seat in seats {
+        seats_by_journey.entry(seat.journey_id).or_default().push(seat);
+    }
+    Ok(seats_by_journey)
+}
+
+// Availability of the seats sold under `quota` for each (journey_id, stop range),
+// keyed by journey_id. One query for the seats and one for the bookings of every
+// journey; the single-journey and search paths both go through here.
+async fn quota_availability(
+    pool: &MySqlPool,
+    segments: &[(i64, (i32, i32))],
+    quota: &str,
+) -> Result<HashMap<i64, Vec<ClassAvailability>>, sqlx::Error> {
+    let journey_ids: Vec<i64> = segments.iter().map(|(journey_id, _)| *journey_id).collect();
+
+    let mut seats_by_journey = fetch_journey_seats(pool, &journey_ids).await?;
+    for seats in seats_by_journey.values_mut() {
+        seats.retain(|s| s.quota.as_deref() == Some(quota));
+    }
+
+    let mut bookings_by_journey: HashMap<i64, Vec<SegmentBooking>> = HashMap::new();
+    for booking in fetch_bookings_for_journeys(pool, &journey_ids).await? {
+        if let Some(journey_id) = booking.journey_id {
+            bookings_by_journey.entry(journey_id).or_default().push(booking);
+        }
+    }
+
+    Ok(segments
+        .iter()
+        .map(|(journey_id, segment)| {
+            let seats = seats_by_journey.get(journey_id).map(Vec::as_slice).unwrap_or_default();
+            let bookings = bookings_by_journey.get(journey_id).map(Vec::as_slice).unwrap_or_default();
+            (*journey_id, compute_availability(seats, bookings, *segment))
+        })
+        .collect())
+}
+
+// Availability of the seats sold under `quota`. Returns None when `from`/`to`
+// are not stops of the journey in travel order.
+#[tracing::instrument(skip(pool))]
+pub async fn journey_availability(
+    pool: &MySqlPool,
+    journey_id: i64,
+    from: Option<i64>,
+    to: Option<i64>,
+    quota: &str,
+) -> Result<Option<Vec<ClassAvailability>>, sqlx::Error> {
+    let stops = fetch_journey_stops(pool, journey_id).await?;
+    let Some(segment) = segment_between(&stops, from, to) else {
+        return Ok(None);
+    };
+
+    let mut availability = quota_availability(pool, &[(journey_id, segment)], quota).await?;
+    Ok(Some(availability.remove(&journey_id).unwrap_or_default()))
+}
+
+// A journey and the stop range a passenger would travel on it
+#[derive(Debug, Clone, Copy)]
+pub struct JourneySegment {
+    pub journey_id: i64,
+    pub train_id: i64,
+    pub segment: (i32, i32),
+}
+
+fn distinct_train_ids(journeys: &[JourneySegment]) -> Vec<i64> {
+    let mut train_ids: Vec<i64> = journeys.iter().map(|j| j.train_id).collect();
+    train_ids.sort_unstable();
+    train_ids.dedup();
+    train_ids
+}
+
+// General quota availability for many journeys at once, keyed by journey_id
+#[tracing::instrument(skip_all, fields(journeys = journeys.len()))]
+pub async fn batch_availability(
+    pool: &MySqlPool,
+    journeys: &[JourneySegment],
+) -> Result<HashMap<i64, Vec<ClassAvailability>>, sqlx::Error> {
+    if journeys.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let segments: Vec<(i64, (i32, i32))> = journeys.iter().map(|j| (j.journey_id, j.segment)).collect();
+    quota_availability(pool, &segments, GENERAL).await
+}
+
+// Base fare per (train_id, class). Coaches of one class normally share a fare;
+// if they don't, the cheapest is quoted.
+pub async fn fetch_class_fares(
+    pool: &MySqlPool,
+    train_ids: &[i64],
+) -> Result<HashMap<(i64, String), f32>, sqlx::Error> {
+    if train_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+        r#"
+        SELECT train_id, coach_type, MIN(fare)
+        FROM coach
+        WHERE train_id IS NOT NULL AND coach_type IS NOT NULL AND fare IS NOT NULL
+        AND train_id IN ("#,
+    );
+    push_id_list(&mut query, train_ids);
+    query.push(" GROUP BY train_id, coach_type");
+
+    let rows = query.build_query_as::<(i64, String, f32)>().fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|(train_id, class, fare)| ((train_id, class), fare)).collect())
+}
+
+pub async fn fares_for_journeys(
+    pool: &MySqlPool,
+    journeys: &[JourneySegment],
+) -> Result<HashMap<(i64, String), f32>, sqlx::Error> {
+    fetch_class_fares(pool, &distinct_train_ids(journeys)).await
+}
+
+// Share of the journey's distance covered between two stops (the first and last
+// stop when None). None if a station is not a stop; 1.0 if distances are unknown.
+pub fn distance_share(stops: &[(i64, Option<f64>)], from: Option<i64>, to: Option<i64>) -> Option<f64> {
+    let distance_at = |station_id: Option<i64>, fallback: Option<&(i64, Option<f64>)>| match station_id {
+        Some(id) => stops.iter().find(|(s, _)| *s == id).map(|(_, d)| *d),
+        None => fallback.map(|(_, d)| *d),
+    };
+    let from = distance_at(from, stops.first())?;
+    let to = distance_at(to, stops.last())?;
+
+    let total = match (stops.first(), stops.last()) {
+        (Some((_, Some(first))), Some((_, Some(last)))) => (last - first).abs(),
+        _ => 0.0,
+    };
+    Some(match (from, to) {
+        (Some(from), Some(to)) if total > 0.0 => ((to - from).abs() / total).min(1.0),
+        _ => 1.0,
+    })
+}
+
+// Base fare of a class for the booked segment: the class's coach fare, which is for
+// the whole journey, scaled by the share of the distance travelled. None if the
+// journey does not run the class or a station is not one of its stops.
+pub async fn segment_base_fare(
+    pool: &MySqlPool,
+    journey_id: i64,
+    reservation_category: &str,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Option<f32>, sqlx::Error> {
+    let class_fare = sqlx::query_scalar::<_, Option<f32>>(
+        r#"
+        SELECT MIN(c.fare)
+        FROM coach c
+        JOIN journey j ON j.train_id = c.train_id
+        WHERE j.journey_id = ? AND c.coach_type = ?
+        "#,
+    )
+    .bind(journey_id)
+    .bind(reservation_category)
+    .fetch_one(pool)
+    .await?;
+    let Some(class_fare) = class_fare else {
+        return Ok(None);
+    };
+
+    let stops = sqlx::query_as::<_, (i64, Option<f32>)>(
+        r#"
+        SELECT s.station_id, dm.distance
+        FROM schedule s
+        LEFT JOIN distance_map dm ON dm.route_id = s.route_id AND dm.station_id = s.station_id
+        WHERE s.journey_id = ? AND s.station_id IS NOT NULL
+        ORDER BY s.stop_number
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_all(pool)
+    .await?;
+    let stops: Vec<(i64, Option<f64>)> = stops.into_iter().map(|(s, d)| (s, d.map(f64::from))).collect();
+
+    Ok(distance_share(&stops, from, to).map(|share| (f64::from(class_fare) * share).round() as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops() -> Vec<(i64, Option<f64>)> {
+        vec![(1, Some(0.0)), (2, Some(100.0)), (3, Some(400.0))]
+    }
+
+    #[test]
+    fn whole_journey_is_the_full_fare() {
+        assert_eq!(distance_share(&stops(), None, None), Some(1.0));
+        assert_eq!(distance_share(&stops(), Some(1), Some(3)), Some(1.0));
+    }
+
+    #[test]
+    fn segments_pay_their_share_of_the_distance() {
+        assert_eq!(distance_share(&stops(), Some(1), Some(2)), Some(0.25));
+        assert_eq!(distance_share(&stops(), Some(2), None), Some(0.75));
+    }
+
+    #[test]
+    fn stations_off_the_journey_have_no_share() {
+        assert_eq!(distance_share(&stops(), Some(9), Some(3)), None);
+    }
+
+    #[test]
+    fn unknown_distances_charge_the_full_fare() {
+        assert_eq!(distance_share(&[(1, None), (2, None)], Some(1), Some(2)), Some(1.0));
+    }
+}
@@ -0,0 +1,83 @@
+// services/coach_layout.rs
+//
+// Standard seat layouts per coach_type, used to generate a coach's seats in bulk.
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct CoachTemplate {
+    pub coach_type: &'static str,
+    pub name_prefix: &'static str, // S1, B1, A1 ...
+    pub capacity: i64,
+    pub bay_pattern: &'static [&'static str], // Seat types of one bay, repeated along the coach
+    pub rac_seat_type: Option<&'static str>, // Berths shared by two RAC passengers
+}
+
+// Berths: LL lower, MD middle, UP upper, SL side lower, SU side upper.
+// Sitting classes use ST for a regular seat and FC for first-class cabins.
+pub const COACH_TEMPLATES: &[CoachTemplate] = &[
+    CoachTemplate {
+        coach_type: "SL",
+        name_prefix: "S",
+        capacity: 72,
+        bay_pattern: &["LL", "MD", "UP", "LL", "MD", "UP", "SL", "SU"],
+        rac_seat_type: Some("SL"),
+    },
+    CoachTemplate {
+        coach_type: "AC3",
+        name_prefix: "B",
+        capacity: 72,
+        bay_pattern: &["LL", "MD", "UP", "LL", "MD", "UP", "SL", "SU"],
+        rac_seat_type: Some("SL"),
+    },
+    CoachTemplate {
+        coach_type: "AC2",
+        name_prefix: "A",
+        capacity: 48,
+        bay_pattern: &["LL", "UP", "LL", "UP", "SL", "SU"],
+        rac_seat_type: Some("SL"),
+    },
+    CoachTemplate {
+        coach_type: "AC1",
+        name_prefix: "H",
+        capacity: 24,
+        bay_pattern: &["LL", "UP", "LL", "UP"],
+        rac_seat_type: None,
+    },
+    CoachTemplate {
+        coach_type: "FC",
+        name_prefix: "F",
+        capacity: 24,
+        bay_pattern: &["FC"],
+        rac_seat_type: None,
+    },
+    CoachTemplate {
+        coach_type: "CC",
+        name_prefix: "C",
+        capacity: 78,
+        bay_pattern: &["ST"],
+        rac_seat_type: None,
+    },
+    CoachTemplate {
+        coach_type: "2S",
+        name_prefix: "D",
+        capacity: 108,
+        bay_pattern: &["ST"],
+        rac_seat_type: None,
+    },
+];
+
+pub fn template_for(coach_type: &str) -> Option<&'static CoachTemplate> {
+    COACH_TEMPLATES.iter().find(|t| t.coach_type == coach_type)
+}
+
+// (seat_no, seat_type, seat_category) for every seat of a coach built from `template`
+pub fn layout_seats(template: &CoachTemplate, capacity: i64) -> Vec<(i64, &'static str, &'static str)> {
+    (0..capacity)
+        .map(|i| {
+            let seat_type = template.bay_pattern[i as usize % template.bay_pattern.len()];
+            let seat_category = if template.rac_seat_type == Some(seat_type) { "RAC" } else { "CNF" };
+            (i + 1, seat_type, seat_category)
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+// services/eticket.rs
+//
+// E-ticket rendering and signing. The QR code carries `TicketClaims` signed with
+// the server's Ed25519 key as "<claims>.<signature>" (both base64url), so a
+// checker holding only the public key can verify a ticket offline. Claims expire
+// a few hours after arrival, and POST /api/tickets/verify also checks that each
+// booking still has the status it was issued with.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use printpdf::{BuiltinFont, Color, Greyscale, Mm, PdfDocument, Rect};
+use qrcode::QrCode;
+
+use crate::models::ticket::{Ticket, TicketClaims};
+use crate::services::local_time::station_offset;
+
+pub struct TicketSigner {
+    key: SigningKey,
+}
+
+impl TicketSigner {
+    // `seed` is a base64 32-byte Ed25519 seed. Without one, a throwaway key is used
+    // and tickets stop verifying after a restart.
+    pub fn new(seed: Option<&str>) -> Self {
+        let decoded = seed.and_then(|s| base64::engine::general_purpose::STANDARD.decode(s.trim()).ok());
+        let seed: [u8; 32] = match decoded.and_then(|bytes| bytes.try_into().ok()) {
+            Some(seed) => seed,
+            None => {
+                tracing::warn!("TICKET_SIGNING_KEY missing or invalid; using a temporary e-ticket signing key");
+                rand::random()
+            }
+        };
+        Self { key: SigningKey::from_bytes(&seed) }
+    }
+
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.key.verifying_key().as_bytes())
+    }
+
+    pub fn sign(&self, claims: &TicketClaims) -> String {
+        let body = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).expect("claims serialize"));
+        let signature = self.key.sign(body.as_bytes());
+        format!("{}.{}", body, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+
+    pub fn verify(&self, signed: &str) -> Result<TicketClaims, String> {
+        let (body, signature) = signed.split_once('.').ok_or("Malformed ticket payload")?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or("Malformed ticket signature")?;
+
+        self.key
+            .verifying_key()
+            .verify(body.as_bytes(), &signature)
+            .map_err(|_| "Ticket signature does not match".to_string())?;
+
+        let claims = URL_SAFE_NO_PAD.decode(body).map_err(|_| "Malformed ticket payload")?;
+        serde_json::from_slice(&claims).map_err(|e| format!("Malformed ticket payload: {}", e))
+    }
+}
+
+// QR modules row by row, true = dark
+fn qr_modules(data: &str) -> Result<(usize, Vec<bool>), String> {
+    let code = QrCode::new(data.as_bytes()).map_err(|e| e.to_string())?;
+    let modules = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+    Ok((code.width(), modules))
+}
+
+fn local_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.with_timezone(&station_offset()).format("%d %b %Y %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn text(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("-")
+}
+
+fn seat_label(coach: &Option<String>, seat_no: Option<i64>, seat_type: &Option<String>) -> String {
+    match (coach, seat_no) {
+        (Some(coach), Some(seat_no)) => format!("{}/{} {}", coach, seat_no, seat_type.as_deref().unwrap_or("")).trim().to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn qr_svg(data: &str) -> Result<String, String> {
+    let (width, modules) = qr_modules(data)?;
+    let size = width + 8; // 4-module quiet zone on each side
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" width="240" height="240" shape-rendering="crispEdges"><rect width="{size}" height="{size}" fill="#fff"/>"##
+    );
+    for (i, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        svg.push_str(&format!(r#"<rect x="{}" y="{}" width="1" height="1"/>"#, i % width + 4, i / width + 4));
+    }
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+pub fn render_html(ticket: &Ticket) -> Result<String, String> {
+    let rows: String = ticket
+        .passengers
+        .iter()
+        .map(|p| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                p.pnr,
+                escape_html(text(&p.name)),
+                p.age.map(|a| a.to_string()).unwrap_or_default(),
+                escape_html(text(&p.reservation_status)),
+                escape_html(&seat_label(&p.coach_name, p.seat_no, &p.seat_type)),
+                p.fare.map(|f| format!("{:.2}", f)).unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>E-ticket {txn}</title>
+<style>body{{font-family:sans-serif;max-width:760px;margin:24px auto}}table{{border-collapse:collapse;width:100%}}td,th{{border:1px solid #ccc;padding:6px;text-align:left}}.qr{{float:right}}</style>
+</head><body>
+<div class="qr">{qr}</div>
+<h1>Electronic Reservation Slip</h1>
+<p><b>{train}</b> (journey {journey}) &middot; Class {class}</p>
+<p>{from} &rarr; {to}<br>Departure {departure} &middot; Arrival {arrival}</p>
+<p>Transaction {txn} &middot; {mode}</p>
+<table><tr><th>PNR</th><th>Name</th><th>Age</th><th>Status</th><th>Coach/Berth</th><th>Fare</th></tr>{rows}</table>
+<p><b>Total fare: {total:.2}</b></p>
+<p><small>Issued {issued}. Times are local (IST).</small></p>
+</body></html>"#,
+        qr = qr_svg(&ticket.signed_payload)?,
+        train = escape_html(text(&ticket.train_name)),
+        journey = ticket.journey_id.unwrap_or_default(),
+        class = escape_html(text(&ticket.reservation_category)),
+        from = escape_html(text(&ticket.from_station)),
+        to = escape_html(text(&ticket.to_station)),
+        departure = local_time(ticket.departure_time),
+        arrival = local_time(ticket.arrival_time),
+        txn = ticket.txn_id.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+        mode = escape_html(text(&ticket.payment_mode)),
+        total = ticket.total_fare,
+        issued = local_time(Some(ticket.issued_at)),
+    ))
+}
+
+pub fn render_pdf(ticket: &Ticket) -> Result<Vec<u8>, String> {
+    let (doc, page, layer) = PdfDocument::new("E-ticket", Mm(210.0), Mm(297.0), "Ticket");
+    let layer = doc.get_page(page).get_layer(layer);
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| e.to_string())?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|e| e.to_string())?;
+
+    let mut y = 275.0;
+    let mut line = |content: &str, size: f32, bold_font: bool, gap: f32| {
+        layer.use_text(content, size, Mm(15.0), Mm(y), if bold_font { &bold } else { &regular });
+        y -= gap;
+    };
+
+    line("Electronic Reservation Slip", 18.0, true, 12.0);
+    line(&format!("{} (journey {})", text(&ticket.train_name), ticket.journey_id.unwrap_or_default()), 12.0, true, 7.0);
+    line(&format!("Class {}", text(&ticket.reservation_category)), 11.0, false, 7.0);
+    line(&format!("{}  ->  {}", text(&ticket.from_station), text(&ticket.to_station)), 11.0, false, 7.0);
+    line(&format!("Departure {}", local_time(ticket.departure_time)), 11.0, false, 7.0);
+    line(&format!("Arrival   {}", local_time(ticket.arrival_time)), 11.0, false, 7.0);
+    line(
+        &format!(
+            "Transaction {}  ({})",
+            ticket.txn_id.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+            text(&ticket.payment_mode)
+        ),
+        11.0,
+        false,
+        12.0,
+    );
+
+    line("PNR        Name                      Age  Status  Coach/Berth       Fare", 10.0, true, 6.0);
+    for p in &ticket.passengers {
+        line(
+            &format!(
+                "{:<10} {:<25} {:<4} {:<7} {:<17} {}",
+                p.pnr,
+                text(&p.name).chars().take(25).collect::<String>(),
+                p.age.map(|a| a.to_string()).unwrap_or_default(),
+                text(&p.reservation_status),
+                seat_label(&p.coach_name, p.seat_no, &p.seat_type),
+                p.fare.map(|f| format!("{:.2}", f)).unwrap_or_default(),
+            ),
+            10.0,
+            false,
+            6.0,
+        );
+    }
+    line(&format!("Total fare: {:.2}", ticket.total_fare), 11.0, true, 8.0);
+    line(&format!("Issued {} (times are local, IST)", local_time(Some(ticket.issued_at))), 8.0, false, 6.0);
+
+    // QR code in the top right corner, 1.2 mm per module
+    let (width, modules) = qr_modules(&ticket.signed_payload)?;
+    let module = 1.2;
+    let (left, top) = (195.0 - width as f32 * module, 285.0);
+    layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+    for (i, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        let x = left + (i % width) as f32 * module;
+        let y = top - (i / width + 1) as f32 * module;
+        layer.add_rect(Rect::new(Mm(x), Mm(y), Mm(x + module), Mm(y + module)));
+    }
+
+    doc.save_to_bytes().map_err(|e| e.to_string())
+}
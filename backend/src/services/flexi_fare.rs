@@ -0,0 +1,232 @@
+// services/flexi_fare.rs
+//
+// Flexi pricing for premium trains: the base fare of the booked segment is
+// multiplied by the step matching the share of CNF berths already sold for the
+// journey and class. The fare is quoted and locked for a few minutes so checkout
+// charges what was shown, less each passenger's concession.
+
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::models::fare::{FareQuote, FlexiFareStep};
+use crate::services::availability::segment_base_fare;
+use crate::services::quota::age_and_sex;
+
+// Multiplier of the highest step reached; 1.0 below the first step or without steps
+pub fn multiplier(steps: &[FlexiFareStep], sold_percent: f64) -> f32 {
+    steps
+        .iter()
+        .filter(|s| f64::from(s.sold_percent) <= sold_percent)
+        .max_by_key(|s| s.sold_percent)
+        .map_or(1.0, |s| s.multiplier)
+}
+
+// A passenger's share of `fare`: half for children under 12, 70% over 60, then a
+// further 10% off for women and 20% off for passengers with a disability
+pub fn concession_fare(fare: f32, passenger: &Value) -> f32 {
+    let (age, sex) = age_and_sex(passenger);
+    let disabled = passenger
+        .get("disability")
+        .is_some_and(|d| d.as_bool().unwrap_or_else(|| d.as_i64().is_some_and(|d| d != 0)));
+
+    let mut share = match age {
+        Some(age) if age < 12 => 0.5,
+        Some(age) if age > 60 => 0.7,
+        _ => 1.0,
+    };
+    if sex.as_deref() == Some("F") {
+        share *= 0.9;
+    }
+    if disabled {
+        share *= 0.8;
+    }
+    (f64::from(fare) * share).round() as f32
+}
+
+// Steps for the journey's train type and class. Class-specific steps replace the
+// train type's defaults.
+pub async fn fetch_steps(
+    pool: &MySqlPool,
+    journey_id: i64,
+    reservation_category: &str,
+) -> Result<Vec<FlexiFareStep>, sqlx::Error> {
+    sqlx::query_as::<_, FlexiFareStep>(
+        r#"
+        SELECT f.sold_percent, f.multiplier
+        FROM flexi_fare_step f
+        JOIN train t ON t.train_type = f.train_type
+        JOIN journey j ON j.train_id = t.train_id
+        WHERE j.journey_id = ?
+        AND (
+            f.reservation_category = ?
+            OR (f.reservation_category IS NULL AND NOT EXISTS (
+                SELECT 1 FROM flexi_fare_step x
+                WHERE x.train_type = f.train_type AND x.reservation_category = ?
+            ))
+        )
+        ORDER BY f.sold_percent
+        "#,
+    )
+    .bind(journey_id)
+    .bind(reservation_category)
+    .bind(reservation_category)
+    .fetch_all(pool)
+    .await
+}
+
+// Share of the class's CNF berths with a live booking anywhere on the journey
+pub async fn sold_percent(pool: &MySqlPool, journey_id: i64, reservation_category: &str) -> Result<f64, sqlx::Error> {
+    let (seats, sold) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COUNT(DISTINCT s.seat_id), COUNT(DISTINCT b.seat_id)
+        FROM seat s
+        JOIN coach c ON c.coach_id = s.coach_id
+        JOIN journey j ON j.train_id = c.train_id
+        LEFT JOIN booking b ON b.seat_id = s.seat_id AND b.journey_id = j.journey_id
+            AND b.booking_status IN ('CONFIRMED', 'PENDING')
+        WHERE j.journey_id = ? AND c.coach_type = ? AND s.seat_category = 'CNF'
+        "#,
+    )
+    .bind(journey_id)
+    .bind(reservation_category)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(if seats == 0 { 100.0 } else { sold as f64 * 100.0 / seats as f64 })
+}
+
+// Prices the class for the segment now and holds that fare. None if the journey
+// does not run the class or a station is not one of its stops.
+pub async fn lock_quote(
+    pool: &MySqlPool,
+    journey_id: i64,
+    reservation_category: &str,
+    segment: (Option<i64>, Option<i64>),
+    lock_minutes: i64,
+) -> Result<Option<FareQuote>, sqlx::Error> {
+    let (from, to) = segment;
+    let Some(base_fare) = segment_base_fare(pool, journey_id, reservation_category, from, to).await? else {
+        return Ok(None);
+    };
+
+    let steps = fetch_steps(pool, journey_id, reservation_category).await?;
+    let multiplier = if steps.is_empty() {
+        1.0
+    } else {
+        multiplier(&steps, sold_percent(pool, journey_id, reservation_category).await?)
+    };
+    let fare = (base_fare * multiplier).round();
+
+    let quote_id = sqlx::query(
+        r#"
+        INSERT INTO fare_quote (journey_id, reservation_category, start_station_id, end_station_id, base_fare, multiplier, fare, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, NOW() + INTERVAL ? MINUTE)
+        "#,
+    )
+    .bind(journey_id)
+    .bind(reservation_category)
+    .bind(from)
+    .bind(to)
+    .bind(base_fare)
+    .bind(multiplier)
+    .bind(fare)
+    .bind(lock_minutes)
+    .execute(pool)
+    .await?
+    .last_insert_id();
+
+    sqlx::query_as::<_, FareQuote>(
+        r#"
+        SELECT quote_id, journey_id, reservation_category, start_station_id, end_station_id, base_fare, multiplier, fare, expires_at
+        FROM fare_quote
+        WHERE quote_id = ?
+        "#,
+    )
+    .bind(quote_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Marks the quote used by `txn_id` and returns the fare it locked, or the reason it
+// cannot be used.
+pub async fn redeem_quote(
+    tx: &mut Transaction<'_, MySql>,
+    quote_id: i64,
+    journey_id: i64,
+    reservation_category: &str,
+    segment: (i64, i64),
+    txn_id: i64,
+) -> Result<Result<f32, &'static str>, sqlx::Error> {
+    let quote = sqlx::query_as::<_, (i64, String, Option<i64>, Option<i64>, f32, bool, bool)>(
+        r#"
+        SELECT journey_id, reservation_category, start_station_id, end_station_id, fare,
+               expires_at <= NOW(), redeemed_at IS NOT NULL
+        FROM fare_quote
+        WHERE quote_id = ?
+        FOR UPDATE
+        "#,
+    )
+    .bind(quote_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let fare = match quote {
+        None => return Ok(Err("Fare quote not found")),
+        Some((quoted_journey, quoted_class, ..))
+            if quoted_journey != journey_id || quoted_class != reservation_category =>
+        {
+            return Ok(Err("Fare quote is for a different journey or class"))
+        }
+        Some((_, _, from, to, ..))
+            if from.is_some_and(|f| f != segment.0) || to.is_some_and(|t| t != segment.1) =>
+        {
+            return Ok(Err("Fare quote is for a different segment"))
+        }
+        Some((.., true)) => return Ok(Err("Fare quote has already been used")),
+        Some((.., true, _)) => return Ok(Err("Fare quote has expired; request a new one")),
+        Some((_, _, _, _, fare, _, _)) => fare,
+    };
+
+    sqlx::query("UPDATE fare_quote SET redeemed_at = NOW(), txn_id = ? WHERE quote_id = ?")
+        .bind(txn_id)
+        .bind(quote_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(Ok(fare))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn steps() -> Vec<FlexiFareStep> {
+        vec![
+            FlexiFareStep { sold_percent: 80, multiplier: 1.3 },
+            FlexiFareStep { sold_percent: 50, multiplier: 1.1 },
+        ]
+    }
+
+    #[test]
+    fn base_fare_below_the_first_step() {
+        assert_eq!(multiplier(&steps(), 10.0), 1.0);
+        assert_eq!(multiplier(&[], 100.0), 1.0);
+    }
+
+    #[test]
+    fn highest_step_reached_applies() {
+        assert_eq!(multiplier(&steps(), 50.0), 1.1);
+        assert_eq!(multiplier(&steps(), 79.9), 1.1);
+        assert_eq!(multiplier(&steps(), 95.0), 1.3);
+    }
+
+    #[test]
+    fn concessions_apply_to_the_quoted_fare() {
+        assert_eq!(concession_fare(1000.0, &json!({ "age": 30, "sex": "M", "disability": 0 })), 1000.0);
+        assert_eq!(concession_fare(1000.0, &json!({ "age": 8, "sex": "M" })), 500.0);
+        assert_eq!(concession_fare(1000.0, &json!({ "age": 65, "sex": "F" })), 630.0);
+        assert_eq!(concession_fare(1000.0, &json!({ "age": 30, "sex": "F", "disability": 1 })), 720.0);
+        assert_eq!(concession_fare(1000.0, &json!({ "age": 30, "sex": "M", "disability": true })), 800.0);
+    }
+}
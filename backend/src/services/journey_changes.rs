@@ -0,0 +1,263 @@
+// services/journey_changes.rs
+//
+// Railway-initiated changes to a booked journey: cancelling it outright, or
+// retiming/diverting it. Everything here runs inside the caller's transaction.
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{MySql, QueryBuilder, Transaction};
+
+use crate::models::journey::{AffectedBooking, BookingImpact, DivertedStop, ImpactKind};
+use crate::services::notifications::{enqueue_all, Notification};
+use crate::services::promo::release;
+
+const MAX_SHIFT_MINUTES: i64 = 7 * 24 * 60;
+
+// A journey stop as stored in `schedule`, in stop_number order
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct JourneyStop {
+    pub station_id: i64,
+    pub sched_toa: Option<DateTime<Utc>>,
+    pub sched_tod: Option<DateTime<Utc>>,
+    pub route_id: Option<i64>,
+}
+
+pub async fn fetch_stops(tx: &mut Transaction<'_, MySql>, journey_id: i64) -> Result<Vec<JourneyStop>, sqlx::Error> {
+    sqlx::query_as::<_, JourneyStop>(
+        r#"
+        SELECT station_id, sched_toa, sched_tod, route_id
+        FROM schedule
+        WHERE journey_id = ? AND station_id IS NOT NULL
+        ORDER BY stop_number
+        FOR UPDATE
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+// Bookings that still hold (or wait for) a place on the journey
+pub async fn fetch_live_bookings(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+) -> Result<Vec<AffectedBooking>, sqlx::Error> {
+    sqlx::query_as::<_, AffectedBooking>(
+        r#"
+        SELECT b.booking_id, b.pnr, p.email, b.booking_status, b.amount, b.txn_id,
+               b.start_station_id, b.end_station_id
+        FROM booking b
+        LEFT JOIN passenger p ON p.pnr = b.pnr
+        WHERE b.journey_id = ? AND b.booking_status IN ('CONFIRMED', 'PENDING')
+        FOR UPDATE
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+// Full refund of what was paid; unpaid (PENDING) bookings have nothing to refund
+pub fn full_refund(booking: &AffectedBooking) -> f32 {
+    match booking.booking_status.as_deref() {
+        Some("CONFIRMED") => booking.amount.unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+// Cancels the bookings with a full refund and queues `event_type` for each passenger
+#[tracing::instrument(skip(tx, bookings), fields(bookings = bookings.len()))]
+pub async fn cancel_with_refund(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+    bookings: &[AffectedBooking],
+    event_type: &'static str,
+    reason: &str,
+) -> Result<(), sqlx::Error> {
+    if bookings.is_empty() {
+        return Ok(());
+    }
+
+    let mut cancel: QueryBuilder<MySql> =
+        QueryBuilder::new("UPDATE booking SET booking_status = 'CANCELLED' WHERE booking_id IN (");
+    let mut ids = cancel.separated(", ");
+    for booking in bookings {
+        ids.push_bind(booking.booking_id);
+    }
+    ids.push_unseparated(")");
+    cancel.build().execute(&mut **tx).await?;
+
+    let cancel_time = Utc::now().naive_utc();
+    let mut records: QueryBuilder<MySql> =
+        QueryBuilder::new("INSERT INTO cancellation_record (booking_id, cancel_time, refund_amount, cancel_status, txn_id) ");
+    records.push_values(bookings, |mut row, b| {
+        row.push_bind(b.booking_id)
+            .push_bind(cancel_time)
+            .push_bind(full_refund(b))
+            .push_bind("COMPLETED")
+            .push_bind(b.txn_id);
+    });
+    records.build().execute(&mut **tx).await?;
+
+    // A transaction with nothing live left can no longer be paid, so a late
+    // COMPLETE cannot confirm the cancelled bookings, and its promo code is given back
+    let mut txn_ids: Vec<i64> = bookings.iter().filter_map(|b| b.txn_id).collect();
+    txn_ids.sort_unstable();
+    txn_ids.dedup();
+    for txn_id in txn_ids {
+        sqlx::query(
+            r#"
+            UPDATE payment_transaction pt
+            SET pt.txn_status = 'FAILED'
+            WHERE pt.txn_id = ? AND pt.txn_status = 'PENDING'
+            AND NOT EXISTS (
+                SELECT 1 FROM booking b
+                WHERE b.txn_id = pt.txn_id AND b.booking_status IN ('CONFIRMED', 'PENDING')
+            )
+            "#,
+        )
+        .bind(txn_id)
+        .execute(&mut **tx)
+        .await?;
+        release(tx, txn_id).await?;
+    }
+
+    let notifications: Vec<Notification> = bookings
+        .iter()
+        .map(|b| Notification {
+            email: b.email.clone(),
+            pnr: b.pnr,
+            event_type,
+            payload: serde_json::json!({
+                "journey_id": journey_id,
+                "booking_id": b.booking_id,
+                "reason": reason,
+                "refund_amount": full_refund(b),
+            }),
+        })
+        .collect();
+    enqueue_all(tx, &notifications).await
+}
+
+// The journey's stops after applying a shift or a diversion
+pub fn planned_stops(
+    current: &[JourneyStop],
+    shift_minutes: Option<i64>,
+    diversion: Option<&[DivertedStop]>,
+) -> Result<Vec<JourneyStop>, String> {
+    match (shift_minutes, diversion) {
+        (Some(minutes), None) => {
+            if minutes == 0 || minutes.abs() > MAX_SHIFT_MINUTES {
+                return Err(format!("shift_minutes must be non-zero and at most {} in size", MAX_SHIFT_MINUTES));
+            }
+            let shift = Duration::minutes(minutes);
+            Ok(current
+                .iter()
+                .map(|stop| JourneyStop {
+                    sched_toa: stop.sched_toa.map(|t| t + shift),
+                    sched_tod: stop.sched_tod.map(|t| t + shift),
+                    ..stop.clone()
+                })
+                .collect())
+        }
+        (None, Some(stops)) => {
+            if stops.len() < 2 {
+                return Err("A diverted journey needs at least two stops".to_string());
+            }
+            for (i, stop) in stops.iter().enumerate() {
+                if stops[..i].iter().any(|s| s.station_id == stop.station_id) {
+                    return Err(format!("Station {} appears more than once", stop.station_id));
+                }
+                if stop.sched_tod < stop.sched_toa {
+                    return Err(format!("Station {} departs before it arrives", stop.station_id));
+                }
+                if i > 0 && stop.sched_toa < stops[i - 1].sched_tod {
+                    return Err(format!("Station {} is reached before the previous stop is left", stop.station_id));
+                }
+            }
+            Ok(stops
+                .iter()
+                .map(|stop| JourneyStop {
+                    station_id: stop.station_id,
+                    sched_toa: Some(stop.sched_toa),
+                    sched_tod: Some(stop.sched_tod),
+                    route_id: stop.route_id,
+                })
+                .collect())
+        }
+        _ => Err("Give exactly one of shift_minutes or stops".to_string()),
+    }
+}
+
+// Scheduled departure and arrival of a leg
+type LegTimes = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+// Departure from `from` and arrival at `to`, if both are served in that order
+fn leg_times(stops: &[JourneyStop], from: Option<i64>, to: Option<i64>) -> Option<LegTimes> {
+    let board = stops.iter().position(|s| Some(s.station_id) == from)?;
+    let alight = stops.iter().position(|s| Some(s.station_id) == to)?;
+    (board < alight).then(|| (stops[board].sched_tod, stops[alight].sched_toa))
+}
+
+// Bookings affected by moving from `old` to `new` stops. Unaffected bookings are left out.
+pub fn assess_impact(bookings: &[AffectedBooking], old: &[JourneyStop], new: &[JourneyStop]) -> Vec<BookingImpact> {
+    bookings
+        .iter()
+        .filter_map(|b| {
+            let (old_departure, old_arrival) = leg_times(old, b.start_station_id, b.end_station_id).unwrap_or_default();
+            let (impact, new_departure, new_arrival) = match leg_times(new, b.start_station_id, b.end_station_id) {
+                None => (ImpactKind::StationRemoved, None, None),
+                Some((dep, arr)) if dep != old_departure || arr != old_arrival => (ImpactKind::TimeChanged, dep, arr),
+                Some(_) => return None,
+            };
+            Some(BookingImpact {
+                booking_id: b.booking_id,
+                pnr: b.pnr,
+                start_station_id: b.start_station_id,
+                end_station_id: b.end_station_id,
+                impact,
+                old_departure,
+                new_departure,
+                old_arrival,
+                new_arrival,
+            })
+        })
+        .collect()
+}
+
+// Replaces the journey's schedule with `stops` and moves its endpoints to match
+pub async fn write_stops(
+    tx: &mut Transaction<'_, MySql>,
+    journey_id: i64,
+    stops: &[JourneyStop],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM schedule WHERE journey_id = ?")
+        .bind(journey_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let mut insert: QueryBuilder<MySql> =
+        QueryBuilder::new("INSERT INTO schedule (station_id, sched_toa, sched_tod, journey_id, stop_number, route_id) ");
+    insert.push_values(stops.iter().enumerate(), |mut row, (i, stop)| {
+        row.push_bind(stop.station_id)
+            .push_bind(stop.sched_toa)
+            .push_bind(stop.sched_tod)
+            .push_bind(journey_id)
+            .push_bind(i as i32 + 1)
+            .push_bind(stop.route_id);
+    });
+    insert.build().execute(&mut **tx).await?;
+
+    let (first, last) = (&stops[0], &stops[stops.len() - 1]);
+    sqlx::query(
+        "UPDATE journey SET start_time = ?, end_time = ?, start_station_id = ?, end_station_id = ? WHERE journey_id = ?",
+    )
+    .bind(first.sched_tod.or(first.sched_toa))
+    .bind(last.sched_toa.or(last.sched_tod))
+    .bind(first.station_id)
+    .bind(last.station_id)
+    .bind(journey_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
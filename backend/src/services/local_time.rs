@@ -0,0 +1,34 @@
+// services/local_time.rs
+//
+// Schedule times are stored in UTC, but passengers pick dates and read times in
+// station-local time. Indian Railways runs on IST across the whole network, so
+// every station shares a single offset.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+
+const STATION_UTC_OFFSET_SECS: i32 = 5 * 3600 + 30 * 60; // IST, UTC+05:30
+
+pub fn station_offset() -> FixedOffset {
+    FixedOffset::east_opt(STATION_UTC_OFFSET_SECS).expect("valid UTC offset")
+}
+
+pub fn local_date(time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&station_offset()).date_naive()
+}
+
+// UTC instants bounding the local days first..=last, as a half-open range
+pub fn local_day_range(first: NaiveDate, last: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start_of = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_local_timezone(station_offset())
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    (start_of(first), start_of(last + Duration::days(1)))
+}
+
+// Day of the journey (1 = the day it leaves its origin) on which `time` falls
+pub fn journey_day(origin_departure: DateTime<Utc>, time: DateTime<Utc>) -> i64 {
+    (local_date(time) - local_date(origin_departure)).num_days() + 1
+}
@@ -0,0 +1,284 @@
+// services/login_guard.rs
+//
+// Brute-force protection for sign-in. Failures are counted per account and per
+// client IP. After a few failures each further attempt must wait twice as long
+// as the one before; past a hard limit the account (or IP) is locked for a
+// while. Suspicious activity is written to the security_event audit table.
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool};
+
+use crate::models::security::LoginFailure;
+
+pub const ACCOUNT: &str = "ACCOUNT";
+pub const IP: &str = "IP";
+
+pub const BACKOFF_AFTER: i32 = 3;          // Free attempts before backoff starts
+pub const MAX_BACKOFF_SECS: i64 = 300;
+pub const ACCOUNT_LOCK_AFTER: i32 = 10;
+pub const IP_BLOCK_AFTER: i32 = 30;        // Higher: many users can share an address
+pub const LOCK_MINUTES: i64 = 15;
+pub const FAILURE_WINDOW_MINUTES: i64 = 60; // Counters reset after this long without failures
+
+// Why an attempt is refused, and for how long
+#[derive(Debug, Clone)]
+pub struct Blocked {
+    pub scope: &'static str,
+    pub locked: bool, // false while only backing off
+    pub retry_after_secs: i64,
+}
+
+fn lock_threshold(scope: &str) -> i32 {
+    if scope == IP { IP_BLOCK_AFTER } else { ACCOUNT_LOCK_AFTER }
+}
+
+pub fn backoff(failures: i32) -> Duration {
+    if failures < BACKOFF_AFTER {
+        return Duration::zero();
+    }
+    let exponent = (failures - BACKOFF_AFTER).min(16) as u32;
+    Duration::seconds(2i64.pow(exponent).min(MAX_BACKOFF_SECS))
+}
+
+// When the next attempt is allowed, if not yet
+fn blocked_until(failure: &LoginFailure, now: DateTime<Utc>) -> Option<(DateTime<Utc>, bool)> {
+    if let Some(until) = failure.locked_until.filter(|until| *until > now) {
+        return Some((until, true));
+    }
+    if failure.last_failure_at < now - Duration::minutes(FAILURE_WINDOW_MINUTES) {
+        return None;
+    }
+    let next = failure.last_failure_at + backoff(failure.failures);
+    (next > now).then_some((next, false))
+}
+
+async fn fetch_failure(pool: &MySqlPool, scope: &str, subject: &str) -> Result<Option<LoginFailure>, sqlx::Error> {
+    sqlx::query_as::<_, LoginFailure>(
+        r#"
+        SELECT scope, subject, failures, first_failure_at, last_failure_at, locked_until
+        FROM login_failure
+        WHERE scope = ? AND subject = ?
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn audit<'e, E>(
+    executor: E,
+    event_type: &str,
+    email: Option<&str>,
+    client_ip: Option<&str>,
+    details: Value,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query("INSERT INTO security_event (event_type, email, client_ip, details) VALUES (?, ?, ?, ?)")
+        .bind(event_type)
+        .bind(email)
+        .bind(client_ip)
+        .bind(sqlx::types::Json(details))
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+// Refuses the attempt if the account or the IP is locked or backing off.
+// Refusals during a lock are audited. Nothing is held locked: failures are
+// counted atomically afterwards by record_failure.
+pub async fn check(pool: &MySqlPool, email: &str, client_ip: &str) -> Result<Option<Blocked>, sqlx::Error> {
+    let now = Utc::now();
+    let mut blocked: Option<Blocked> = None;
+
+    for (scope, subject) in [(ACCOUNT, email), (IP, client_ip)] {
+        let Some(failure) = fetch_failure(pool, scope, subject).await? else {
+            continue;
+        };
+        let Some((until, locked)) = blocked_until(&failure, now) else {
+            continue;
+        };
+        let retry_after_secs = (until - now).num_seconds().max(1);
+        // Report the longest wait, preferring a lock over a backoff
+        if blocked.as_ref().is_none_or(|b| (locked, retry_after_secs) > (b.locked, b.retry_after_secs)) {
+            blocked = Some(Blocked { scope, locked, retry_after_secs });
+        }
+    }
+
+    if let Some(blocked) = &blocked
+        && blocked.locked
+    {
+        audit(
+            pool,
+            "LOGIN_BLOCKED",
+            Some(email),
+            Some(client_ip),
+            serde_json::json!({ "scope": blocked.scope, "retry_after_secs": blocked.retry_after_secs }),
+        )
+        .await?;
+    }
+    Ok(blocked)
+}
+
+// Counts one failure in a single statement, so concurrent failures are never
+// lost. A run that went quiet for the failure window starts again from one.
+// MySQL applies the assignments left to right, so last_failure_at is updated
+// only after the others have read its old value.
+async fn bump(pool: &MySqlPool, scope: &str, subject: &str, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    let window_start = now - Duration::minutes(FAILURE_WINDOW_MINUTES);
+    sqlx::query(
+        r#"
+        INSERT INTO login_failure (scope, subject, failures, first_failure_at, last_failure_at)
+        VALUES (?, ?, 1, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            failures = IF(last_failure_at < ?, 1, failures + 1),
+            first_failure_at = IF(last_failure_at < ?, VALUES(first_failure_at), first_failure_at),
+            last_failure_at = VALUES(last_failure_at)
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(now)
+    .bind(now)
+    .bind(window_start)
+    .bind(window_start)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// Locks the account or IP once its count reaches the limit. Only one of several
+// concurrent failures gets to apply the lock. Counting starts afresh after it,
+// so the next lock needs a full run of failures.
+async fn lock_if_over_limit(pool: &MySqlPool, scope: &str, subject: &str, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let until = now + Duration::minutes(LOCK_MINUTES);
+    let result = sqlx::query(
+        r#"
+        UPDATE login_failure
+        SET locked_until = ?, failures = 0
+        WHERE scope = ? AND subject = ? AND failures >= ?
+          AND (locked_until IS NULL OR locked_until <= ?)
+        "#,
+    )
+    .bind(until)
+    .bind(scope)
+    .bind(subject)
+    .bind(lock_threshold(scope))
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok((result.rows_affected() > 0).then_some(until))
+}
+
+// Counts the failure against the account and the IP, locking either when it
+// reaches its limit. Call it after the password check, not before.
+pub async fn record_failure(pool: &MySqlPool, email: &str, client_ip: &str, known_account: bool) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    let scopes = [(ACCOUNT, email, "ACCOUNT_LOCKED"), (IP, client_ip, "IP_BLOCKED")];
+    for (scope, subject, event_type) in scopes {
+        bump(pool, scope, subject, now).await?;
+        if let Some(locked_until) = lock_if_over_limit(pool, scope, subject, now).await? {
+            audit(
+                pool,
+                event_type,
+                Some(email),
+                Some(client_ip),
+                serde_json::json!({ "known_account": known_account, "locked_until": locked_until }),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// Clears the account's failures. Success after a run of failures is audited,
+// since it may mean a guessed password.
+pub async fn record_success(pool: &MySqlPool, email: &str, client_ip: &str) -> Result<(), sqlx::Error> {
+    if let Some(failure) = fetch_failure(pool, ACCOUNT, email).await?
+        && failure.failures >= BACKOFF_AFTER
+    {
+        audit(
+            pool,
+            "LOGIN_AFTER_FAILURES",
+            Some(email),
+            Some(client_ip),
+            serde_json::json!({ "failures": failure.failures, "since": failure.first_failure_at }),
+        )
+        .await?;
+    }
+    clear(pool, ACCOUNT, email).await?;
+    Ok(())
+}
+
+// Removes the counter for an account or IP. Returns whether there was one.
+pub async fn clear<'e, E>(executor: E, scope: &str, subject: &str) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    let result = sqlx::query("DELETE FROM login_failure WHERE scope = ? AND subject = ?")
+        .bind(scope)
+        .bind(subject)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(failures: i32, last_failure_at: DateTime<Utc>, locked_until: Option<DateTime<Utc>>) -> LoginFailure {
+        LoginFailure {
+            scope: ACCOUNT.to_string(),
+            subject: "user@example.com".to_string(),
+            failures,
+            first_failure_at: last_failure_at,
+            last_failure_at,
+            locked_until,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_after_the_free_attempts() {
+        assert_eq!(backoff(0), Duration::zero());
+        assert_eq!(backoff(BACKOFF_AFTER - 1), Duration::zero());
+        assert_eq!(backoff(BACKOFF_AFTER), Duration::seconds(1));
+        assert_eq!(backoff(BACKOFF_AFTER + 1), Duration::seconds(2));
+        assert_eq!(backoff(BACKOFF_AFTER + 5), Duration::seconds(32));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff(BACKOFF_AFTER + 9), Duration::seconds(MAX_BACKOFF_SECS));
+        assert_eq!(backoff(i32::MAX), Duration::seconds(MAX_BACKOFF_SECS));
+    }
+
+    #[test]
+    fn blocked_while_backing_off() {
+        let now = Utc::now();
+        let recent = failure(BACKOFF_AFTER + 2, now - Duration::seconds(1), None);
+        assert_eq!(blocked_until(&recent, now), Some((now + Duration::seconds(3), false)));
+
+        let waited = failure(BACKOFF_AFTER + 2, now - Duration::seconds(10), None);
+        assert_eq!(blocked_until(&waited, now), None);
+    }
+
+    #[test]
+    fn blocked_while_locked() {
+        let now = Utc::now();
+        let until = now + Duration::minutes(LOCK_MINUTES);
+        let locked = failure(ACCOUNT_LOCK_AFTER, now - Duration::minutes(1), Some(until));
+        assert_eq!(blocked_until(&locked, now), Some((until, true)));
+    }
+
+    #[test]
+    fn old_failures_do_not_block() {
+        let now = Utc::now();
+        let stale = failure(BACKOFF_AFTER + 8, now - Duration::minutes(FAILURE_WINDOW_MINUTES + 1), None);
+        assert_eq!(blocked_until(&stale, now), None);
+    }
+}
@@ -0,0 +1,108 @@
+// services/mailer.rs
+//
+// Direct mail for account messages (verification and password reset) that must
+// go out immediately rather than through the notification outbox. SMTP is used
+// when configured; otherwise messages are written to a file so the tokens can be
+// picked up during local development. Without a file only the recipient and
+// subject are logged.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::NotificationConfig;
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, config: &NotificationConfig) -> Result<Self, String> {
+        let builder = if config.smtp_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|e| e.to_string())?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(user), Some(password)) => builder.credentials(Credentials::new(user.clone(), password.clone())),
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.port(config.smtp_port).build(),
+            from: config.smtp_from.parse().map_err(|e| format!("Invalid SMTP_FROM: {}", e))?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        let to: Mailbox = to.parse().map_err(|e| format!("{}", e))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string())
+            .map_err(|e| e.to_string())?;
+
+        self.transport.send(email).await.map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+// Appends each message to a file as one JSON line, or logs who it was for when no file is set
+pub struct LogMailer {
+    path: Option<String>,
+}
+
+impl LogMailer {
+    pub fn new(path: Option<String>) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        // The body carries verification and reset tokens, so it never goes to the log
+        let Some(path) = &self.path else {
+            tracing::info!(target: "mail", to, subject, "Account mail not sent: no SMTP host or mail log file configured");
+            return Ok(());
+        };
+
+        let line = serde_json::json!({
+            "to": to,
+            "subject": subject,
+            "message": body,
+            "sent_at": chrono::Utc::now(),
+        });
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| e.to_string())?;
+        file.write_all(format!("{}\n", line).as_bytes()).await.map_err(|e| e.to_string())
+    }
+}
+
+pub fn from_config(config: &NotificationConfig) -> Arc<dyn Mailer> {
+    if let Some(host) = &config.smtp_host {
+        match SmtpMailer::new(host, config) {
+            Ok(mailer) => return Arc::new(mailer),
+            Err(e) => tracing::warn!("SMTP mailer unavailable, logging account mail instead: {}", e),
+        }
+    }
+    Arc::new(LogMailer::new(config.mail_log_file.clone()))
+}
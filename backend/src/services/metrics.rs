@@ -0,0 +1,89 @@
+// services/metrics.rs
+//
+// Prometheus metrics served on GET /metrics by the separate metrics listener.
+// HTTP counters are recorded by the track_requests middleware, business counters
+// by the handlers that commit the change, and pool gauges are sampled when the
+// endpoint is scraped.
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::MySqlPool;
+
+const NAMESPACE: &str = "railway";
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_pool_connections: IntGaugeVec,
+    pub bookings_created: IntCounterVec,
+    pub payments: IntCounterVec,
+    pub cancellations: IntCounterVec,
+    pub waitlist_promotions: IntCounterVec,
+    pub search_duration: HistogramVec,
+}
+
+fn counter(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> prometheus::Result<IntCounterVec> {
+    let counter = IntCounterVec::new(Opts::new(name, help).namespace(NAMESPACE), labels)?;
+    registry.register(Box::new(counter.clone()))?;
+    Ok(counter)
+}
+
+fn histogram(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> prometheus::Result<HistogramVec> {
+    let histogram = HistogramVec::new(HistogramOpts::new(name, help).namespace(NAMESPACE), labels)?;
+    registry.register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "MySQL pool connections by state (active, idle, max)").namespace(NAMESPACE),
+            &["state"],
+        )?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+
+        Ok(Self {
+            http_requests: counter(&registry, "http_requests_total", "HTTP requests by route and status", &["method", "route", "status"])?,
+            http_request_duration: histogram(&registry, "http_request_duration_seconds", "HTTP request latency by route", &["method", "route"])?,
+            db_pool_connections,
+            bookings_created: counter(&registry, "bookings_created_total", "Passengers booked by class and initial status", &["class", "status"])?,
+            payments: counter(&registry, "payments_total", "Payments completed or failed", &["status"])?,
+            cancellations: counter(&registry, "cancellations_total", "Cancelled bookings by quota and status at cancellation", &["quota", "status"])?,
+            waitlist_promotions: counter(&registry, "waitlist_promotions_total", "Reservations moved up from WL or RAC", &["from", "to"])?,
+            search_duration: histogram(&registry, "search_duration_seconds", "Search latency by kind", &["kind"])?,
+            registry,
+        })
+    }
+
+    fn sample_pool(&self, pool: &MySqlPool) {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        self.db_pool_connections.with_label_values(&["active"]).set(size - idle);
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections
+            .with_label_values(&["max"])
+            .set(pool.options().get_max_connections() as i64);
+    }
+
+    // The text exposition format Prometheus scrapes
+    pub fn render(&self, pool: &MySqlPool) -> Result<String, String> {
+        self.sample_pool(pool);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| e.to_string())?;
+        String::from_utf8(buffer).map_err(|e| e.to_string())
+    }
+
+    // Only settled payments are counted; PENDING is neither completed nor failed
+    pub fn record_payment(&self, status: &str) {
+        if matches!(status, "COMPLETE" | "FAILED") {
+            self.payments.with_label_values(&[status]).inc();
+        }
+    }
+}
@@ -0,0 +1,20 @@
+pub mod rail_network;
+pub mod station_search;
+pub mod coach_layout;
+pub mod occupancy;
+pub mod availability;
+pub mod local_time;
+pub mod notifications;
+pub mod journey_changes;
+pub mod notification_sinks;
+pub mod notification_dispatcher;
+pub mod eticket;
+pub mod quota;
+pub mod tatkal;
+pub mod flexi_fare;
+pub mod promo;
+pub mod travellers;
+pub mod accounts;
+pub mod mailer;
+pub mod login_guard;
+pub mod metrics;
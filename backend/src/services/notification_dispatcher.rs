@@ -0,0 +1,206 @@
+// services/notification_dispatcher.rs
+//
+// Background task draining `notification_outbox`. Due rows are claimed by pushing
+// their next_attempt_at forward (SKIP LOCKED keeps several instances apart), then
+// delivered to every sink the recipient accepts. Failed channels are retried with
+// exponential backoff until `max_attempts`. A row no sink accepts (say, email-only
+// recipients while neither SMTP nor the log file is configured) stays PENDING
+// without using up an attempt, and is looked at again every hour.
+
+use std::time::Duration;
+
+use sqlx::MySqlPool;
+use tracing::Instrument;
+
+use crate::config::NotificationConfig;
+use crate::services::notification_sinks::{FileSink, NotificationSink, OutboxMessage, Recipient, SmtpSink, WebhookSink};
+
+const BATCH_SIZE: i64 = 50;
+const CLAIM_SECS: i64 = 300; // A claimed row is retried after this if the dispatcher dies mid-delivery
+const BASE_BACKOFF_SECS: i64 = 30;
+const UNROUTED_RETRY_SECS: i64 = 3600;
+
+pub fn build_sinks(config: &NotificationConfig) -> Vec<Box<dyn NotificationSink>> {
+    let mut sinks: Vec<Box<dyn NotificationSink>> = Vec::new();
+
+    match SmtpSink::new(config) {
+        Ok(Some(smtp)) => sinks.push(Box::new(smtp)),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("SMTP notifications disabled: {}", e),
+    }
+    sinks.push(Box::new(WebhookSink::new()));
+    if let Some(path) = &config.log_file {
+        sinks.push(Box::new(FileSink::new(path.clone())));
+    }
+    if sinks.len() == 1 {
+        tracing::warn!("Only webhook notifications are configured; email-only notifications stay pending");
+    }
+
+    sinks
+}
+
+pub fn spawn(pool: MySqlPool, config: NotificationConfig) {
+    let sinks = build_sinks(&config);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            let dispatch = dispatch_due(&pool, &sinks, config.max_attempts)
+                .instrument(tracing::info_span!("notification_dispatch"));
+            if let Err(e) = dispatch.await {
+                tracing::error!("Notification dispatch failed: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn claim_due(pool: &MySqlPool) -> Result<Vec<OutboxMessage>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let messages = sqlx::query_as::<_, OutboxMessage>(
+        r#"
+        SELECT notification_id, email, pnr, event_type, CAST(payload AS CHAR) AS payload,
+               attempts, delivered_channels
+        FROM notification_outbox
+        WHERE status = 'PENDING' AND next_attempt_at <= NOW()
+        ORDER BY notification_id
+        LIMIT ?
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for message in &messages {
+        sqlx::query("UPDATE notification_outbox SET next_attempt_at = NOW() + INTERVAL ? SECOND WHERE notification_id = ?")
+            .bind(CLAIM_SECS)
+            .bind(message.notification_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(messages)
+}
+
+async fn fetch_recipient(pool: &MySqlPool, email: Option<&str>) -> Result<Recipient, sqlx::Error> {
+    let preference = match email {
+        Some(email) => {
+            sqlx::query_as::<_, (bool, bool, Option<String>)>(
+                "SELECT email_enabled, webhook_enabled, webhook_url FROM notification_preference WHERE email = ?",
+            )
+            .bind(email)
+            .fetch_optional(pool)
+            .await?
+        }
+        None => None,
+    };
+
+    let (email_enabled, webhook_enabled, webhook_url) = preference.unwrap_or((true, false, None));
+    Ok(Recipient {
+        email: email.map(str::to_string),
+        email_enabled,
+        webhook_url: webhook_url.filter(|_| webhook_enabled),
+    })
+}
+
+pub async fn dispatch_due(
+    pool: &MySqlPool,
+    sinks: &[Box<dyn NotificationSink>],
+    max_attempts: i32,
+) -> Result<(), sqlx::Error> {
+    for message in claim_due(pool).await? {
+        let recipient = fetch_recipient(pool, message.email.as_deref()).await?;
+
+        let mut delivered: Vec<String> = message
+            .delivered_channels
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut errors: Vec<String> = Vec::new();
+
+        for sink in sinks {
+            let channel = sink.channel().as_str();
+            if !sink.accepts(&recipient) || delivered.iter().any(|c| c == channel) {
+                continue;
+            }
+            match sink.deliver(&message, &recipient).await {
+                Ok(()) => delivered.push(channel.to_string()),
+                Err(e) => errors.push(format!("{}: {}", channel, e)),
+            }
+        }
+
+        let (status, attempts, retry_in) = outcome(message.attempts, !delivered.is_empty(), &errors, max_attempts);
+        if delivered.is_empty() && errors.is_empty() {
+            errors.push("no configured delivery channel accepts this recipient".to_string());
+        }
+        let last_error: Option<String> = (!errors.is_empty()).then(|| errors.join("; ").chars().take(500).collect());
+
+        sqlx::query(
+            r#"
+            UPDATE notification_outbox
+            SET status = ?,
+                attempts = ?,
+                delivered_channels = ?,
+                last_error = ?,
+                next_attempt_at = NOW() + INTERVAL ? SECOND,
+                sent_at = IF(? = 'SENT', NOW(), sent_at)
+            WHERE notification_id = ?
+            "#,
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(delivered.join(","))
+        .bind(last_error)
+        .bind(retry_in)
+        .bind(status)
+        .bind(message.notification_id)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Status, attempt count and retry delay after one delivery round
+fn outcome(attempts: i32, delivered_any: bool, errors: &[String], max_attempts: i32) -> (&'static str, i32, i64) {
+    if !delivered_any && errors.is_empty() {
+        // No sink took it: wait for one to be configured rather than failing it
+        return ("PENDING", attempts, UNROUTED_RETRY_SECS);
+    }
+    let attempts = attempts + 1;
+    if errors.is_empty() {
+        ("SENT", attempts, 0)
+    } else if attempts >= max_attempts {
+        ("FAILED", attempts, 0)
+    } else {
+        ("PENDING", attempts, BASE_BACKOFF_SECS << (attempts - 1).min(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unrouted_messages_stay_pending() {
+        assert_eq!(outcome(0, false, &[], 5), ("PENDING", 0, UNROUTED_RETRY_SECS));
+        assert_eq!(outcome(4, false, &[], 5), ("PENDING", 4, UNROUTED_RETRY_SECS));
+    }
+
+    #[test]
+    fn failures_back_off_then_fail() {
+        let errors = ["webhook: timed out".to_string()];
+        assert_eq!(outcome(0, false, &errors, 3), ("PENDING", 1, BASE_BACKOFF_SECS));
+        assert_eq!(outcome(1, true, &errors, 3), ("PENDING", 2, BASE_BACKOFF_SECS * 2));
+        assert_eq!(outcome(2, false, &errors, 3), ("FAILED", 3, 0));
+    }
+
+    #[test]
+    fn delivered_without_errors_is_sent() {
+        assert_eq!(outcome(0, true, &[], 3), ("SENT", 1, 0));
+    }
+}
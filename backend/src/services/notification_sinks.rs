@@ -0,0 +1,370 @@
+// services/notification_sinks.rs
+//
+// Delivery channels for outbox notifications. Each sink decides whether it applies
+// to a recipient (from their preferences) and delivers one message; the dispatcher
+// handles retries.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::NotificationConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Email,
+    Webhook,
+    Log,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Email => "email",
+            Channel::Webhook => "webhook",
+            Channel::Log => "log",
+        }
+    }
+}
+
+// An outbox row being delivered
+#[derive(Debug, sqlx::FromRow)]
+pub struct OutboxMessage {
+    pub notification_id: i64,
+    pub email: Option<String>,
+    pub pnr: Option<i64>,
+    pub event_type: String,
+    pub payload: String, // JSON text
+    pub attempts: i32,
+    pub delivered_channels: String,
+}
+
+// Where a message may go, from the user's notification preferences
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub email: Option<String>,
+    pub email_enabled: bool,
+    pub webhook_url: Option<String>,
+}
+
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    fn channel(&self) -> Channel;
+    fn accepts(&self, recipient: &Recipient) -> bool;
+    async fn deliver(&self, message: &OutboxMessage, recipient: &Recipient) -> Result<(), String>;
+}
+
+fn payload_json(message: &OutboxMessage) -> serde_json::Value {
+    serde_json::from_str(&message.payload).unwrap_or(serde_json::Value::Null)
+}
+
+// Subject and plain-text body for a message
+pub fn render(message: &OutboxMessage) -> (String, String) {
+    let payload = payload_json(message);
+    let field = |key: &str| payload.get(key).map(|v| v.to_string().trim_matches('"').to_string()).unwrap_or_default();
+    let pnr = message.pnr.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+
+    let (subject, body) = match message.event_type.as_str() {
+        "BOOKING_CREATED" => (
+            "Booking received".to_string(),
+            format!("Your booking on journey {} is awaiting payment (transaction {}).", field("journey_id"), field("txn_id")),
+        ),
+        "BOOKING_CONFIRMED" => (
+            format!("PNR {} confirmed", pnr),
+            format!("Payment received. Booking {} on journey {} is confirmed.", field("booking_id"), field("journey_id")),
+        ),
+        "BOOKING_CANCELLED" => (
+            format!("PNR {} cancelled", pnr),
+            format!("Booking {} has been cancelled. Refund: {}.", field("booking_id"), field("refund_amount")),
+        ),
+        "PAYMENT_FAILED" => (
+            format!("Payment failed - PNR {}", pnr),
+            format!(
+                "Payment for transaction {} did not go through, so booking {} on journey {} is not confirmed.",
+                field("txn_id"),
+                field("booking_id"),
+                field("journey_id")
+            ),
+        ),
+        "RESERVATION_UPDATED" => (
+            format!("PNR {} status changed", pnr),
+            format!("Your reservation moved from {} to {}.", field("old_status"), field("new_status")),
+        ),
+        "JOURNEY_CANCELLED" => (
+            format!("Train cancelled - PNR {}", pnr),
+            format!(
+                "Journey {} has been cancelled ({}). A full refund of {} has been issued.",
+                field("journey_id"),
+                field("reason"),
+                field("refund_amount")
+            ),
+        ),
+        "JOURNEY_DIVERTED" => (
+            format!("Train diverted - PNR {}", pnr),
+            format!(
+                "Journey {} no longer serves your stations ({}). Your booking was cancelled with a full refund of {}.",
+                field("journey_id"),
+                field("reason"),
+                field("refund_amount")
+            ),
+        ),
+        "JOURNEY_RESCHEDULED" => (
+            format!("Timings changed - PNR {}", pnr),
+            format!(
+                "Journey {} has new timings ({}): departs {}, arrives {}. You may cancel free of charge.",
+                field("journey_id"),
+                field("reason"),
+                field("new_departure"),
+                field("new_arrival")
+            ),
+        ),
+        other => (format!("Update for PNR {}", pnr), format!("{}: {}", other, message.payload)),
+    };
+
+    (subject, body)
+}
+
+pub struct SmtpSink {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpSink {
+    pub fn new(config: &NotificationConfig) -> Result<Option<Self>, String> {
+        let Some(host) = &config.smtp_host else {
+            return Ok(None);
+        };
+
+        let builder = if config.smtp_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|e| e.to_string())?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(user), Some(password)) => builder.credentials(Credentials::new(user.clone(), password.clone())),
+            _ => builder,
+        };
+
+        Ok(Some(Self {
+            transport: builder.port(config.smtp_port).build(),
+            from: config.smtp_from.parse().map_err(|e| format!("Invalid SMTP_FROM: {}", e))?,
+        }))
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SmtpSink {
+    fn channel(&self) -> Channel {
+        Channel::Email
+    }
+
+    fn accepts(&self, recipient: &Recipient) -> bool {
+        recipient.email_enabled && recipient.email.is_some()
+    }
+
+    async fn deliver(&self, message: &OutboxMessage, recipient: &Recipient) -> Result<(), String> {
+        let to: Mailbox = recipient.email.as_deref().unwrap_or_default().parse().map_err(|e| format!("{}", e))?;
+        let (subject, body) = render(message);
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| e.to_string())?;
+
+        self.transport.send(email).await.map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64; // 100.64.0.0/10
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || shared)
+}
+
+// Webhooks may only reach public addresses, never the server's own network
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => !(v6.is_loopback() || v6.is_unspecified() || v6.is_unique_local() || v6.is_unicast_link_local()),
+        },
+    }
+}
+
+// Checks the scheme and any literal host of a webhook URL; names are resolved at delivery
+pub fn check_webhook_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| "webhook_url must be an http(s) URL".to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("webhook_url must be an http(s) URL".to_string());
+    }
+
+    let host = parsed.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    let host = host.to_ascii_lowercase();
+    if host.is_empty() || host == "localhost" || host.ends_with(".localhost") {
+        return Err("webhook_url must point to a public host".to_string());
+    }
+    if let Ok(ip) = host.parse::<IpAddr>()
+        && !is_public_ip(ip)
+    {
+        return Err("webhook_url must point to a public host".to_string());
+    }
+
+    Ok(parsed)
+}
+
+// Rejects a webhook whose host name currently resolves to a non-public address
+async fn check_webhook_target(url: &str) -> Result<(), String> {
+    let parsed = check_webhook_url(url)?;
+    let host = parsed.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let addresses = tokio::net::lookup_host((host, port)).await.map_err(|e| e.to_string())?;
+    for address in addresses {
+        if !is_public_ip(address.ip()) {
+            return Err(format!("webhook host {} resolves to a non-public address", host));
+        }
+    }
+    Ok(())
+}
+
+pub struct WebhookSink {
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            // A redirect could lead to an address the target check never saw
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    fn channel(&self) -> Channel {
+        Channel::Webhook
+    }
+
+    fn accepts(&self, recipient: &Recipient) -> bool {
+        recipient.webhook_url.is_some()
+    }
+
+    async fn deliver(&self, message: &OutboxMessage, recipient: &Recipient) -> Result<(), String> {
+        let url = recipient.webhook_url.as_deref().unwrap_or_default();
+        check_webhook_target(url).await?;
+        let (subject, body) = render(message);
+
+        self.client
+            .post(url)
+            .json(&serde_json::json!({
+                "notification_id": message.notification_id,
+                "event_type": message.event_type,
+                "pnr": message.pnr,
+                "subject": subject,
+                "message": body,
+                "payload": payload_json(message),
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Appends every notification to a file as one JSON line
+pub struct FileSink {
+    path: String,
+}
+
+impl FileSink {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for FileSink {
+    fn channel(&self) -> Channel {
+        Channel::Log
+    }
+
+    fn accepts(&self, _recipient: &Recipient) -> bool {
+        true
+    }
+
+    async fn deliver(&self, message: &OutboxMessage, recipient: &Recipient) -> Result<(), String> {
+        let (subject, body) = render(message);
+        let line = serde_json::json!({
+            "notification_id": message.notification_id,
+            "event_type": message.event_type,
+            "email": recipient.email,
+            "pnr": message.pnr,
+            "subject": subject,
+            "message": body,
+        });
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| e.to_string())?;
+        file.write_all(format!("{}\n", line).as_bytes()).await.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        assert!(is_public_ip(ip("8.8.8.8")));
+        assert!(is_public_ip(ip("2001:4860:4860::8888")));
+    }
+
+    #[test]
+    fn internal_addresses_are_refused() {
+        let internal = [
+            "127.0.0.1", "10.0.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1",
+        ];
+        for address in internal {
+            assert!(!is_public_ip(ip(address)), "{} should not be public", address);
+        }
+    }
+
+    #[test]
+    fn webhook_urls_must_be_http_to_a_public_host() {
+        assert!(check_webhook_url("https://hooks.example.com/notify").is_ok());
+        let refused = [
+            "not a url", "ftp://example.com/", "http://localhost:8080/", "http://api.localhost/",
+            "http://127.0.0.1/", "http://[::1]/",
+        ];
+        for url in refused {
+            assert!(check_webhook_url(url).is_err(), "{} should be refused", url);
+        }
+    }
+}
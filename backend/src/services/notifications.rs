@@ -0,0 +1,42 @@
+// services/notifications.rs
+//
+// Passenger notifications go through the `notification_outbox` table. Callers
+// enqueue inside the transaction that makes the change, so a message exists if
+// and only if the change was committed. BOOKING_CONFIRMED and RESERVATION_UPDATED
+// are queued by database triggers; delivery is done by `notification_dispatcher`.
+
+use serde_json::Value;
+use sqlx::{MySql, QueryBuilder, Transaction};
+
+pub const BOOKING_CREATED: &str = "BOOKING_CREATED";
+pub const BOOKING_CANCELLED: &str = "BOOKING_CANCELLED";
+pub const PAYMENT_FAILED: &str = "PAYMENT_FAILED";
+pub const JOURNEY_CANCELLED: &str = "JOURNEY_CANCELLED";
+pub const JOURNEY_RESCHEDULED: &str = "JOURNEY_RESCHEDULED";
+pub const JOURNEY_DIVERTED: &str = "JOURNEY_DIVERTED";
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub email: Option<String>,
+    pub pnr: Option<i64>,
+    pub event_type: &'static str,
+    pub payload: Value,
+}
+
+pub async fn enqueue_all(tx: &mut Transaction<'_, MySql>, notifications: &[Notification]) -> Result<(), sqlx::Error> {
+    if notifications.is_empty() {
+        return Ok(());
+    }
+
+    let mut insert: QueryBuilder<MySql> =
+        QueryBuilder::new("INSERT INTO notification_outbox (email, pnr, event_type, payload) ");
+    insert.push_values(notifications, |mut row, n| {
+        row.push_bind(&n.email)
+            .push_bind(n.pnr)
+            .push_bind(n.event_type)
+            .push_bind(n.payload.to_string());
+    });
+    insert.build().execute(&mut **tx).await?;
+
+    Ok(())
+}
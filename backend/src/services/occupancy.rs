@@ -0,0 +1,141 @@
+// services/occupancy.rs
+//
+// Segment-aware seat occupancy. A booking holds its seat from the boarding stop
+// up to (not including) the alighting stop, so two bookings only clash when
+// their stop ranges overlap.
+
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+use crate::models::booking::SegmentBooking;
+use crate::models::seat::SeatOccupancy;
+
+// Journey stops as (stop_number, station_id), ordered by stop_number
+pub async fn fetch_journey_stops(pool: &MySqlPool, journey_id: i64) -> Result<Vec<(i32, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (i32, i64)>(
+        r#"
+        SELECT stop_number, station_id
+        FROM schedule
+        WHERE journey_id = ? AND stop_number IS NOT NULL AND station_id IS NOT NULL
+        ORDER BY stop_number
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Stop range for travelling from one station to another on a journey.
+// Missing stations default to the first and last stops.
+pub fn segment_between(stops: &[(i32, i64)], from: Option<i64>, to: Option<i64>) -> Option<(i32, i32)> {
+    let stop_of = |station_id: i64| stops.iter().find(|(_, s)| *s == station_id).map(|(n, _)| *n);
+
+    let from_stop = match from {
+        Some(station_id) => stop_of(station_id)?,
+        None => stops.first()?.0,
+    };
+    let to_stop = match to {
+        Some(station_id) => stop_of(station_id)?,
+        None => stops.last()?.0,
+    };
+
+    (from_stop < to_stop).then_some((from_stop, to_stop))
+}
+
+// Bookings with no known stop range are treated as holding the whole journey
+pub fn overlaps(booking: &SegmentBooking, segment: (i32, i32)) -> bool {
+    let from = booking.from_stop.unwrap_or(i32::MIN);
+    let to = booking.to_stop.unwrap_or(i32::MAX);
+    from < segment.1 && to > segment.0
+}
+
+// Bookings (CONFIRMED or PENDING) of one journey that hold a seat, with their stop ranges
+pub async fn fetch_segment_bookings(
+    pool: &MySqlPool,
+    journey_id: i64,
+    coach_id: Option<i64>,
+) -> Result<Vec<SegmentBooking>, sqlx::Error> {
+    sqlx::query_as::<_, SegmentBooking>(
+        r#"
+        SELECT
+            b.journey_id,
+            b.seat_id,
+            b.booking_status,
+            rs.reservation_category,
+            ss.stop_number AS from_stop,
+            es.stop_number AS to_stop
+        FROM booking b
+        LEFT JOIN seat s ON s.seat_id = b.seat_id
+        LEFT JOIN reservation_status rs ON rs.pnr = b.pnr
+        LEFT JOIN schedule ss ON ss.journey_id = b.journey_id AND ss.station_id = b.start_station_id
+        LEFT JOIN schedule es ON es.journey_id = b.journey_id AND es.station_id = b.end_station_id
+        WHERE b.journey_id = ?
+        AND b.booking_status IN ('CONFIRMED', 'PENDING')
+        AND (? IS NULL OR s.coach_id = ?)
+        "#,
+    )
+    .bind(journey_id)
+    .bind(coach_id)
+    .bind(coach_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Occupancy of a single seat given the bookings overlapping the requested segment.
+// RAC seats take two passengers before they count as booked.
+pub fn seat_occupancy(seat_category: Option<&str>, bookings: &[&SegmentBooking]) -> SeatOccupancy {
+    let confirmed = bookings
+        .iter()
+        .filter(|b| b.booking_status.as_deref() == Some("CONFIRMED"))
+        .count();
+    let pending = bookings.len() - confirmed;
+    let capacity = if seat_category == Some("RAC") { 2 } else { 1 };
+
+    if confirmed >= capacity {
+        SeatOccupancy::Booked
+    } else if pending > 0 {
+        SeatOccupancy::Held
+    } else if confirmed > 0 {
+        SeatOccupancy::RacShared
+    } else {
+        SeatOccupancy::Free
+    }
+}
+
+// Binds `ids` after an already opened "IN (" and closes the list
+pub fn push_id_list(query: &mut QueryBuilder<'_, MySql>, ids: &[i64]) {
+    let mut list = query.separated(", ");
+    for id in ids {
+        list.push_bind(*id);
+    }
+    list.push_unseparated(")");
+}
+
+// Same as `fetch_segment_bookings`, for many journeys in one query
+pub async fn fetch_bookings_for_journeys(
+    pool: &MySqlPool,
+    journey_ids: &[i64],
+) -> Result<Vec<SegmentBooking>, sqlx::Error> {
+    if journey_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+        r#"
+        SELECT
+            b.journey_id,
+            b.seat_id,
+            b.booking_status,
+            rs.reservation_category,
+            ss.stop_number AS from_stop,
+            es.stop_number AS to_stop
+        FROM booking b
+        LEFT JOIN reservation_status rs ON rs.pnr = b.pnr
+        LEFT JOIN schedule ss ON ss.journey_id = b.journey_id AND ss.station_id = b.start_station_id
+        LEFT JOIN schedule es ON es.journey_id = b.journey_id AND es.station_id = b.end_station_id
+        WHERE b.booking_status IN ('CONFIRMED', 'PENDING')
+        AND b.journey_id IN ("#,
+    );
+    push_id_list(&mut query, journey_ids);
+
+    query.build_query_as::<SegmentBooking>().fetch_all(pool).await
+}
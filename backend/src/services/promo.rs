@@ -0,0 +1,245 @@
+// services/promo.rs
+//
+// Promo code evaluation and redemption. Codes are locked while they are checked
+// and redeemed, so global and per-user limits hold under concurrent bookings.
+// A redemption is released again when its payment fails or its bookings are cancelled.
+
+use chrono::Utc;
+use sqlx::{MySql, Transaction};
+
+use crate::models::promo::{PromoCode, PromoDiscount};
+
+const PROMO_SELECT: &str = r#"
+    SELECT p.promo_id, p.code, p.description, p.discount_type, p.discount_value, p.max_discount,
+           p.min_amount, p.valid_from, p.valid_until, p.train_types, p.classes, p.route_ids,
+           p.usage_limit, p.per_user_limit, p.active,
+           (SELECT COUNT(*) FROM promo_redemption r WHERE r.promo_id = p.promo_id AND r.released_at IS NULL) AS times_used
+    FROM promo_code p
+"#;
+
+pub async fn fetch_promo(
+    tx: &mut Transaction<'_, MySql>,
+    code: &str,
+) -> Result<Option<PromoCode>, sqlx::Error> {
+    let sql = format!("{} WHERE p.code = ? FOR UPDATE", PROMO_SELECT);
+    sqlx::query_as::<_, PromoCode>(&sql)
+        .bind(code.trim().to_uppercase())
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+pub async fn fetch_all_promos(pool: &sqlx::MySqlPool) -> Result<Vec<PromoCode>, sqlx::Error> {
+    let sql = format!("{} ORDER BY p.valid_until DESC, p.code", PROMO_SELECT);
+    sqlx::query_as::<_, PromoCode>(&sql).fetch_all(pool).await
+}
+
+fn discount_for(promo: &PromoCode, amount: f32) -> f32 {
+    let discount = match promo.discount_type.as_str() {
+        "PERCENT" => {
+            let discount = amount * promo.discount_value / 100.0;
+            promo.max_discount.map_or(discount, |cap| discount.min(cap))
+        }
+        _ => promo.discount_value,
+    };
+    (discount.min(amount) * 100.0).round() / 100.0
+}
+
+fn allows<T: PartialEq>(list: &Option<sqlx::types::Json<Vec<T>>>, value: Option<&T>) -> bool {
+    match list {
+        None => true,
+        Some(list) => value.is_some_and(|v| list.0.contains(v)),
+    }
+}
+
+// Works out the discount `code` gives on a booking, or why it does not apply
+pub async fn evaluate(
+    tx: &mut Transaction<'_, MySql>,
+    code: &str,
+    email: &str,
+    journey_id: i64,
+    reservation_category: &str,
+    amount: f32,
+) -> Result<Result<PromoDiscount, String>, sqlx::Error> {
+    let Some(promo) = fetch_promo(tx, code).await? else {
+        return Ok(Err("Promo code not found".to_string()));
+    };
+
+    let now = Utc::now();
+    if !promo.active || now < promo.valid_from || now >= promo.valid_until {
+        return Ok(Err("Promo code is not valid at this time".to_string()));
+    }
+    if promo.min_amount.is_some_and(|min| amount < min) {
+        return Ok(Err(format!("Promo code needs a booking of at least {}", promo.min_amount.unwrap_or_default())));
+    }
+    if promo.usage_limit.is_some_and(|limit| promo.times_used >= i64::from(limit)) {
+        return Ok(Err("Promo code has been fully redeemed".to_string()));
+    }
+
+    let train_type = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT t.train_type FROM journey j JOIN train t ON t.train_id = j.train_id WHERE j.journey_id = ?",
+    )
+    .bind(journey_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .flatten();
+    let route_ids = sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT route_id FROM schedule WHERE journey_id = ? AND route_id IS NOT NULL",
+    )
+    .bind(journey_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    if !allows(&promo.train_types, train_type.as_ref()) {
+        return Ok(Err("Promo code does not apply to this train".to_string()));
+    }
+    if !allows(&promo.classes, Some(&reservation_category.to_string())) {
+        return Ok(Err("Promo code does not apply to this class".to_string()));
+    }
+    let on_route = match &promo.route_ids {
+        None => true,
+        Some(allowed) => route_ids.iter().any(|r| allowed.0.contains(r)),
+    };
+    if !on_route {
+        return Ok(Err("Promo code does not apply to this route".to_string()));
+    }
+
+    if let Some(limit) = promo.per_user_limit {
+        let used: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM promo_redemption WHERE promo_id = ? AND email = ? AND released_at IS NULL",
+        )
+        .bind(promo.promo_id)
+        .bind(email)
+        .fetch_one(&mut **tx)
+        .await?;
+        if used >= i64::from(limit) {
+            return Ok(Err("You have already used this promo code".to_string()));
+        }
+    }
+
+    let discount = discount_for(&promo, amount);
+    Ok(Ok(PromoDiscount {
+        promo_id: promo.promo_id,
+        code: promo.code,
+        amount,
+        discount,
+        payable: amount - discount,
+    }))
+}
+
+// Records the redemption and spreads the discount over the transaction's bookings
+// in proportion to their fares.
+pub async fn redeem(
+    tx: &mut Transaction<'_, MySql>,
+    promo: &PromoDiscount,
+    email: &str,
+    txn_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO promo_redemption (promo_id, email, txn_id, discount) VALUES (?, ?, ?, ?)")
+        .bind(promo.promo_id)
+        .bind(email)
+        .bind(txn_id)
+        .bind(promo.discount)
+        .execute(&mut **tx)
+        .await?;
+
+    let share = if promo.amount > 0.0 { promo.discount / promo.amount } else { 0.0 };
+    sqlx::query(
+        r#"
+        UPDATE booking
+        SET discount = ROUND(amount * ?, 2), amount = amount - ROUND(amount * ?, 2)
+        WHERE txn_id = ?
+        "#,
+    )
+    .bind(share)
+    .bind(share)
+    .bind(txn_id)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE payment_transaction
+        SET discount = ?, promo_id = ?,
+            total_amount = (SELECT SUM(amount) FROM booking WHERE txn_id = ?)
+        WHERE txn_id = ?
+        "#,
+    )
+    .bind(promo.discount)
+    .bind(promo.promo_id)
+    .bind(txn_id)
+    .bind(txn_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+// Gives the code back once nothing on `txn_id` is live: the payment failed or every
+// booking on it was cancelled. The row stays, marked released.
+pub async fn release(tx: &mut Transaction<'_, MySql>, txn_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE promo_redemption r
+        SET r.released_at = NOW()
+        WHERE r.txn_id = ? AND r.released_at IS NULL
+        AND (
+            EXISTS (SELECT 1 FROM payment_transaction pt WHERE pt.txn_id = r.txn_id AND pt.txn_status = 'FAILED')
+            OR NOT EXISTS (
+                SELECT 1 FROM booking b
+                WHERE b.txn_id = r.txn_id AND b.booking_status IN ('CONFIRMED', 'PENDING')
+            )
+        )
+        "#,
+    )
+    .bind(txn_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn promo(discount_type: &str, discount_value: f32, max_discount: Option<f32>) -> PromoCode {
+        PromoCode {
+            promo_id: 1,
+            code: "TEST".to_string(),
+            description: None,
+            discount_type: discount_type.to_string(),
+            discount_value,
+            max_discount,
+            min_amount: None,
+            valid_from: Utc::now(),
+            valid_until: Utc::now(),
+            train_types: None,
+            classes: None,
+            route_ids: None,
+            usage_limit: None,
+            per_user_limit: None,
+            active: true,
+            times_used: 0,
+        }
+    }
+
+    #[test]
+    fn percent_discount_is_capped() {
+        let promo = promo("PERCENT", 10.0, Some(50.0));
+        assert_eq!(discount_for(&promo, 300.0), 30.0);
+        assert_eq!(discount_for(&promo, 1000.0), 50.0);
+    }
+
+    #[test]
+    fn percent_discount_is_rounded_to_paise() {
+        assert_eq!(discount_for(&promo("PERCENT", 12.5, None), 99.99), 12.5);
+    }
+
+    #[test]
+    fn flat_discount_never_exceeds_the_amount() {
+        let promo = promo("FLAT", 100.0, None);
+        assert_eq!(discount_for(&promo, 500.0), 100.0);
+        assert_eq!(discount_for(&promo, 60.0), 60.0);
+    }
+}
@@ -0,0 +1,191 @@
+// services/quota.rs
+//
+// Booking quotas and who may book under them. Seats carry a default quota, a
+// journey can override it per seat, and chart preparation releases every
+// remaining quota seat to general (see the `seat_quota` SQL function).
+
+use std::time::Duration;
+
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, Transaction};
+use tracing::Instrument;
+
+pub const GENERAL: &str = "GN";
+pub const LADIES: &str = "LD";
+pub const SENIOR: &str = "SS";
+pub const TATKAL: &str = "TQ";
+pub const EMERGENCY: &str = "EQ";
+
+pub const QUOTAS: &[&str] = &[GENERAL, LADIES, SENIOR, TATKAL, EMERGENCY];
+
+const CHART_POLL_SECS: u64 = 60;
+
+const CHILD_MAX_AGE: i64 = 11;
+const SENIOR_MALE_AGE: i64 = 60;
+const SENIOR_FEMALE_AGE: i64 = 58;
+
+pub fn is_quota(quota: &str) -> bool {
+    QUOTAS.contains(&quota)
+}
+
+fn passenger_label(passenger: &Value, index: usize) -> String {
+    passenger
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("passenger {}", index + 1))
+}
+
+pub fn age_and_sex(passenger: &Value) -> (Option<i64>, Option<String>) {
+    let age = passenger
+        .get("age")
+        .and_then(|a| a.as_i64().or_else(|| a.as_str().and_then(|s| s.parse().ok())));
+    let sex = passenger.get("sex").and_then(Value::as_str).map(str::to_uppercase);
+    (age, sex)
+}
+
+// Checks every passenger in the booking against the quota's rules. Ladies quota
+// admits women and children under 12 travelling with at least one woman; senior
+// citizen quota admits men from 60 and women from 58. Emergency quota is checked
+// by the caller since it depends on who is booking, not who is travelling.
+pub fn check_eligibility(quota: &str, passengers: &Value) -> Result<(), String> {
+    let passengers = passengers.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    match quota {
+        LADIES => {
+            let mut adult_women = 0;
+            for (i, passenger) in passengers.iter().enumerate() {
+                match age_and_sex(passenger) {
+                    (Some(age), Some(sex)) if sex == "F" && age > CHILD_MAX_AGE => adult_women += 1,
+                    (Some(age), _) if age <= CHILD_MAX_AGE => {}
+                    _ => {
+                        return Err(format!(
+                            "{} is not eligible for the ladies quota",
+                            passenger_label(passenger, i)
+                        ))
+                    }
+                }
+            }
+            if adult_women == 0 {
+                return Err("Ladies quota needs at least one adult woman travelling".to_string());
+            }
+            Ok(())
+        }
+        SENIOR => {
+            for (i, passenger) in passengers.iter().enumerate() {
+                let eligible = match age_and_sex(passenger) {
+                    (Some(age), Some(sex)) if sex == "F" => age >= SENIOR_FEMALE_AGE,
+                    (Some(age), Some(_)) => age >= SENIOR_MALE_AGE,
+                    _ => false,
+                };
+                if !eligible {
+                    return Err(format!(
+                        "{} is not eligible for the senior citizen quota",
+                        passenger_label(passenger, i)
+                    ));
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Marks the chart prepared, releasing unsold quota seats to general. Returns the
+// number of seats released, or None if the chart was already prepared.
+pub async fn prepare_chart(tx: &mut Transaction<'_, MySql>, journey_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    let released: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM seat s
+        JOIN coach c ON c.coach_id = s.coach_id
+        JOIN journey j ON j.train_id = c.train_id
+        LEFT JOIN booking b ON b.seat_id = s.seat_id AND b.journey_id = j.journey_id
+            AND b.booking_status IN ('CONFIRMED', 'PENDING')
+        WHERE j.journey_id = ? AND b.booking_id IS NULL
+        AND seat_quota(j.journey_id, s.seat_id) <> 'GN'
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let updated = sqlx::query("UPDATE journey SET chart_prepared_at = NOW() WHERE journey_id = ? AND chart_prepared_at IS NULL")
+        .bind(journey_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok((updated.rows_affected() > 0).then_some(released))
+}
+
+async fn prepare_due_charts(pool: &MySqlPool, chart_lead_hours: i64) -> Result<(), sqlx::Error> {
+    let due = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT journey_id
+        FROM journey
+        WHERE chart_prepared_at IS NULL AND journey_status = 'SCHEDULED'
+        AND start_time <= NOW() + INTERVAL ? HOUR
+        "#,
+    )
+    .bind(chart_lead_hours)
+    .fetch_all(pool)
+    .await?;
+
+    for journey_id in due {
+        let mut tx = pool.begin().await?;
+        if let Some(released) = prepare_chart(&mut tx, journey_id).await? {
+            tracing::info!("Chart prepared for journey {}, {} quota seats released", journey_id, released);
+        }
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+// Prepares charts automatically as journeys come within `chart_lead_hours` of departure
+pub fn spawn_chart_preparation(pool: MySqlPool, chart_lead_hours: i64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(CHART_POLL_SECS));
+        loop {
+            interval.tick().await;
+            let preparation = prepare_due_charts(&pool, chart_lead_hours)
+                .instrument(tracing::info_span!("chart_preparation"));
+            if let Err(e) = preparation.await {
+                tracing::error!("Chart preparation failed: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ladies_quota_admits_women_and_children_with_a_woman() {
+        let passengers = json!([{ "name": "A", "age": 30, "sex": "F" }, { "name": "B", "age": 8, "sex": "M" }]);
+        assert!(check_eligibility(LADIES, &passengers).is_ok());
+    }
+
+    #[test]
+    fn ladies_quota_rejects_adult_men_and_unaccompanied_children() {
+        let err = check_eligibility(LADIES, &json!([{ "name": "A", "age": 30, "sex": "M" }])).unwrap_err();
+        assert!(err.starts_with("A "));
+        assert!(check_eligibility(LADIES, &json!([{ "age": 8, "sex": "F" }])).is_err());
+    }
+
+    #[test]
+    fn senior_quota_uses_the_age_for_each_sex() {
+        let passengers = json!([{ "age": 58, "sex": "F" }, { "age": "60", "sex": "m" }]);
+        assert!(check_eligibility(SENIOR, &passengers).is_ok());
+        assert!(check_eligibility(SENIOR, &json!([{ "age": 59, "sex": "M" }])).is_err());
+        assert!(check_eligibility(SENIOR, &json!([{ "sex": "F" }])).is_err());
+    }
+
+    #[test]
+    fn other_quotas_have_no_passenger_rules() {
+        let passengers = json!([{ "age": 20, "sex": "M" }]);
+        assert!(check_eligibility(GENERAL, &passengers).is_ok());
+        assert!(check_eligibility(TATKAL, &passengers).is_ok());
+    }
+}
@@ -0,0 +1,398 @@
+// services/rail_network.rs
+//
+// In-memory graph of the rail network built from every route's distance_map.
+// Stations are nodes; consecutive stations on a route are joined by an edge
+// weighted by the distance between them.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::RwLock;
+
+use serde::Serialize;
+use sqlx::MySqlPool;
+
+use crate::models::route::RouteStationRow;
+
+pub type SharedRailNetwork = RwLock<RailNetwork>;
+
+#[derive(Debug, Clone)]
+struct Edge {
+    to: i64,
+    distance: f64,
+    route_id: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct RailNetwork {
+    adjacency: HashMap<i64, Vec<Edge>>,
+    station_names: HashMap<i64, Option<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathStop {
+    pub station_id: i64,
+    pub station_name: Option<String>,
+    pub route_id: Option<i64>, // Route used to reach this stop, None for the origin
+    pub leg_distance: f64,
+    pub distance: f64, // Cumulative distance from the origin
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkPath {
+    pub total_distance: f64,
+    pub route_changes: usize,
+    pub stops: Vec<PathStop>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReachableStation {
+    pub station_id: i64,
+    pub station_name: Option<String>,
+    pub distance: f64,
+    pub hops: usize,
+}
+
+// Min-heap entry for Dijkstra
+#[derive(Debug, PartialEq)]
+struct State {
+    cost: f64,
+    station_id: i64,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| self.station_id.cmp(&other.station_id))
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// One step of a path: the station reached, the route used and the leg's length
+type Hop = (i64, Option<i64>, f64);
+
+impl RailNetwork {
+    pub async fn load(pool: &MySqlPool) -> Result<Self, sqlx::Error> {
+        let rows = sqlx::query_as::<_, RouteStationRow>(
+            r#"
+            SELECT dm.route_id, r.route_name, dm.station_id, s.station_name, dm.distance, dm.order_from_start
+            FROM distance_map dm
+            JOIN route r ON r.route_id = dm.route_id
+            LEFT JOIN station s ON s.station_id = dm.station_id
+            ORDER BY dm.route_id, dm.distance
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::from_rows(&rows))
+    }
+
+    // Rows must be grouped by route and ordered by distance within each route
+    pub fn from_rows(rows: &[RouteStationRow]) -> Self {
+        let mut network = RailNetwork::default();
+
+        for row in rows {
+            network.station_names.entry(row.station_id).or_insert_with(|| row.station_name.clone());
+        }
+
+        for pair in rows.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if a.route_id != b.route_id || a.station_id == b.station_id {
+                continue;
+            }
+            let distance = (b.distance.unwrap_or(0.0) - a.distance.unwrap_or(0.0)).abs() as f64;
+            network.add_edge(a.station_id, b.station_id, distance, a.route_id);
+            network.add_edge(b.station_id, a.station_id, distance, a.route_id);
+        }
+
+        network
+    }
+
+    // Parallel edges from different routes collapse into the shortest one
+    fn add_edge(&mut self, from: i64, to: i64, distance: f64, route_id: i64) {
+        let edges = self.adjacency.entry(from).or_default();
+        match edges.iter_mut().find(|e| e.to == to) {
+            Some(edge) if distance < edge.distance => {
+                edge.distance = distance;
+                edge.route_id = route_id;
+            }
+            Some(_) => {}
+            None => edges.push(Edge { to, distance, route_id }),
+        }
+    }
+
+    pub fn station_count(&self) -> usize {
+        self.station_names.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.values().map(Vec::len).sum::<usize>() / 2
+    }
+
+    pub fn contains(&self, station_id: i64) -> bool {
+        self.station_names.contains_key(&station_id)
+    }
+
+    pub fn shortest_path(&self, from: i64, to: i64) -> Option<NetworkPath> {
+        self.dijkstra(from, to, &HashSet::new(), &HashSet::new())
+            .map(|hops| self.to_path(&hops))
+    }
+
+    // Yen's algorithm: up to `k` loopless paths in increasing order of distance
+    pub fn k_shortest_paths(&self, from: i64, to: i64, k: usize) -> Vec<NetworkPath> {
+        let Some(first) = self.dijkstra(from, to, &HashSet::new(), &HashSet::new()) else {
+            return Vec::new();
+        };
+
+        let mut accepted: Vec<Vec<Hop>> = vec![first];
+        let mut candidates: Vec<(f64, Vec<Hop>)> = Vec::new();
+
+        while accepted.len() < k {
+            let previous = accepted.last().cloned().unwrap_or_default();
+
+            for i in 0..previous.len().saturating_sub(1) {
+                let spur_station = previous[i].0;
+                let root = &previous[..=i];
+
+                let mut banned_edges = HashSet::new();
+                for path in &accepted {
+                    if path.len() > i + 1 && same_stations(&path[..=i], root) {
+                        banned_edges.insert((path[i].0, path[i + 1].0));
+                    }
+                }
+
+                let banned_stations: HashSet<i64> = root[..i].iter().map(|hop| hop.0).collect();
+
+                let Some(spur) = self.dijkstra(spur_station, to, &banned_stations, &banned_edges) else {
+                    continue;
+                };
+
+                let mut candidate: Vec<Hop> = root.to_vec();
+                candidate.extend_from_slice(&spur[1..]);
+
+                let already_known = accepted.iter().any(|p| same_stations(p, &candidate))
+                    || candidates.iter().any(|(_, p)| same_stations(p, &candidate));
+                if !already_known {
+                    candidates.push((path_cost(&candidate), candidate));
+                }
+            }
+
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+                .map(|(i, _)| i)
+            else {
+                break;
+            };
+            accepted.push(candidates.swap_remove(best).1);
+        }
+
+        accepted.iter().map(|hops| self.to_path(hops)).collect()
+    }
+
+    // Every station reachable from `from`, with its shortest distance
+    pub fn reachable_from(&self, from: i64, max_distance: Option<f64>) -> Vec<ReachableStation> {
+        let mut best: HashMap<i64, (f64, usize)> = HashMap::from([(from, (0.0, 0))]);
+        let mut heap = BinaryHeap::from([State { cost: 0.0, station_id: from }]);
+
+        while let Some(State { cost, station_id }) = heap.pop() {
+            let (known, hops) = best[&station_id];
+            if cost > known {
+                continue;
+            }
+            for edge in self.adjacency.get(&station_id).into_iter().flatten() {
+                let next = cost + edge.distance;
+                if max_distance.is_some_and(|max| next > max) {
+                    continue;
+                }
+                if best.get(&edge.to).is_none_or(|&(d, _)| next < d) {
+                    best.insert(edge.to, (next, hops + 1));
+                    heap.push(State { cost: next, station_id: edge.to });
+                }
+            }
+        }
+
+        let mut stations: Vec<ReachableStation> = best
+            .into_iter()
+            .filter(|(station_id, _)| *station_id != from)
+            .map(|(station_id, (distance, hops))| ReachableStation {
+                station_id,
+                station_name: self.station_names.get(&station_id).cloned().flatten(),
+                distance,
+                hops,
+            })
+            .collect();
+        stations.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        stations
+    }
+
+    fn dijkstra(
+        &self,
+        from: i64,
+        to: i64,
+        banned_stations: &HashSet<i64>,
+        banned_edges: &HashSet<(i64, i64)>,
+    ) -> Option<Vec<Hop>> {
+        if !self.contains(from) || !self.contains(to) || banned_stations.contains(&from) {
+            return None;
+        }
+
+        let mut dist: HashMap<i64, f64> = HashMap::from([(from, 0.0)]);
+        let mut prev: HashMap<i64, (i64, i64, f64)> = HashMap::new();
+        let mut heap = BinaryHeap::from([State { cost: 0.0, station_id: from }]);
+
+        while let Some(State { cost, station_id }) = heap.pop() {
+            if station_id == to {
+                break;
+            }
+            if cost > dist[&station_id] {
+                continue;
+            }
+            for edge in self.adjacency.get(&station_id).into_iter().flatten() {
+                if banned_stations.contains(&edge.to) || banned_edges.contains(&(station_id, edge.to)) {
+                    continue;
+                }
+                let next = cost + edge.distance;
+                if dist.get(&edge.to).is_none_or(|&d| next < d) {
+                    dist.insert(edge.to, next);
+                    prev.insert(edge.to, (station_id, edge.route_id, edge.distance));
+                    heap.push(State { cost: next, station_id: edge.to });
+                }
+            }
+        }
+
+        if from != to && !prev.contains_key(&to) {
+            return None;
+        }
+
+        let mut hops: Vec<Hop> = Vec::new();
+        let mut current = to;
+        while let Some(&(previous, route_id, leg)) = prev.get(&current) {
+            hops.push((current, Some(route_id), leg));
+            current = previous;
+        }
+        hops.push((from, None, 0.0));
+        hops.reverse();
+        Some(hops)
+    }
+
+    fn to_path(&self, hops: &[Hop]) -> NetworkPath {
+        let mut total = 0.0;
+        let stops: Vec<PathStop> = hops
+            .iter()
+            .map(|&(station_id, route_id, leg)| {
+                total += leg;
+                PathStop {
+                    station_id,
+                    station_name: self.station_names.get(&station_id).cloned().flatten(),
+                    route_id,
+                    leg_distance: leg,
+                    distance: total,
+                }
+            })
+            .collect();
+
+        let route_changes = stops
+            .windows(2)
+            .filter(|w| w[0].route_id.is_some() && w[0].route_id != w[1].route_id)
+            .count();
+
+        NetworkPath { total_distance: total, route_changes, stops }
+    }
+}
+
+fn same_stations(a: &[Hop], b: &[Hop]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.0 == y.0)
+}
+
+fn path_cost(hops: &[Hop]) -> f64 {
+    hops.iter().map(|hop| hop.2).sum()
+}
+
+// Reloads the graph after routes change. Failures are logged and the old graph is kept.
+pub async fn refresh(pool: &MySqlPool, network: &SharedRailNetwork) {
+    match RailNetwork::load(pool).await {
+        Ok(fresh) => {
+            let mut guard = network.write().unwrap_or_else(|e| e.into_inner());
+            *guard = fresh;
+        }
+        Err(e) => tracing::error!("Failed to rebuild rail network: {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(route_id: i64, station_id: i64, distance: f32) -> RouteStationRow {
+        RouteStationRow {
+            route_id,
+            route_name: None,
+            station_id,
+            station_name: Some(format!("S{}", station_id)),
+            distance: Some(distance),
+            order_from_start: None,
+        }
+    }
+
+    // Route 1: 1 -10- 2 -10- 3; route 2: 1 -5- 4 -25- 3
+    fn network() -> RailNetwork {
+        RailNetwork::from_rows(&[
+            row(1, 1, 0.0),
+            row(1, 2, 10.0),
+            row(1, 3, 20.0),
+            row(2, 1, 0.0),
+            row(2, 4, 5.0),
+            row(2, 3, 30.0),
+        ])
+    }
+
+    fn stations(path: &NetworkPath) -> Vec<i64> {
+        path.stops.iter().map(|s| s.station_id).collect()
+    }
+
+    #[test]
+    fn shortest_path_follows_the_lowest_distance() {
+        let path = network().shortest_path(1, 3).unwrap();
+
+        assert_eq!(stations(&path), vec![1, 2, 3]);
+        assert_eq!(path.total_distance, 20.0);
+        assert_eq!(path.route_changes, 0);
+        assert_eq!(path.stops[0].route_id, None);
+        assert_eq!(path.stops[2].distance, 20.0);
+    }
+
+    #[test]
+    fn shortest_path_counts_route_changes() {
+        let path = network().shortest_path(4, 2).unwrap();
+
+        assert_eq!(stations(&path), vec![4, 1, 2]);
+        assert_eq!(path.route_changes, 1);
+    }
+
+    #[test]
+    fn shortest_path_to_unknown_station_is_none() {
+        assert!(network().shortest_path(1, 99).is_none());
+        assert!(network().k_shortest_paths(99, 1, 3).is_empty());
+    }
+
+    #[test]
+    fn k_shortest_paths_are_loopless_and_ordered() {
+        let paths = network().k_shortest_paths(1, 3, 3);
+
+        let found: Vec<_> = paths.iter().map(|p| (stations(p), p.total_distance)).collect();
+        assert_eq!(found, vec![(vec![1, 2, 3], 20.0), (vec![1, 4, 3], 30.0)]);
+    }
+
+    #[test]
+    fn k_shortest_paths_stops_at_k() {
+        assert_eq!(network().k_shortest_paths(1, 3, 1).len(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+// services/station_search.rs
+//
+// Typeahead ranking for stations: codes, name and alias prefixes, substrings
+// and misspellings (edit distance), plus great-circle distances for nearby lookups.
+
+use std::collections::HashMap;
+
+use crate::models::station::{StationResponse, StationSuggestion};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// Levenshtein distance over chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+// Great-circle distance between two coordinates
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// Best score of `query` against one name (station name or alias)
+fn score_name(query: &str, name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+
+    if name == query {
+        return Some(90);
+    }
+    if name.starts_with(query) {
+        return Some(70);
+    }
+    if name.split_whitespace().any(|word| word.starts_with(query)) {
+        return Some(60);
+    }
+    if name.contains(query) {
+        return Some(50);
+    }
+
+    // Misspellings: compare against the leading part of the name and each word
+    // so that "bangalor" still finds "Bangalore City"
+    let query_len = query.chars().count();
+    let allowed = (query_len / 4).max(1);
+    let prefix: String = name.chars().take(query_len).collect();
+
+    let distance = std::iter::once(prefix.as_str())
+        .chain(name.split_whitespace())
+        .map(|candidate| edit_distance(query, candidate))
+        .min()?;
+
+    (query_len >= 3 && distance <= allowed).then(|| 40 - 10 * distance.min(3) as u32)
+}
+
+pub fn rank_stations(
+    query: &str,
+    stations: Vec<StationResponse>,
+    aliases: &HashMap<i64, Vec<String>>,
+    limit: usize,
+) -> Vec<StationSuggestion> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<StationSuggestion> = stations
+        .into_iter()
+        .filter_map(|station| {
+            let station_aliases = aliases.get(&station.station_id).cloned().unwrap_or_default();
+            let code = station.station_code.as_deref().unwrap_or_default().to_lowercase();
+
+            let mut best: Option<(u32, &str)> = None;
+            let mut consider = |score: Option<u32>, matched_on: &'static str| {
+                if let Some(score) = score
+                    && best.is_none_or(|(s, _)| score > s)
+                {
+                    best = Some((score, matched_on));
+                }
+            };
+
+            if !code.is_empty() {
+                consider((code == query).then_some(100), "code");
+                consider(code.starts_with(&query).then_some(80), "code");
+            }
+
+            let name = station.station_name.as_deref().unwrap_or_default();
+            if let Some(score) = score_name(&query, name) {
+                consider(Some(score), if score >= 50 { "name" } else { "fuzzy" });
+            }
+
+            for alias in &station_aliases {
+                // Alias hits rank just below the same kind of hit on the real name
+                if let Some(score) = score_name(&query, alias) {
+                    consider(Some(score - 5), if score >= 50 { "alias" } else { "fuzzy" });
+                }
+            }
+
+            best.map(|(score, matched_on)| StationSuggestion {
+                station,
+                aliases: station_aliases,
+                matched_on: matched_on.to_string(),
+                score,
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.station.station_name.cmp(&b.station.station_name))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
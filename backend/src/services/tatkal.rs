@@ -0,0 +1,159 @@
+// services/tatkal.rs
+//
+// Tatkal: a last-minute quota that opens the day before departure at a class-
+// specific local time, sold at the base fare plus a capped premium. Confirmed
+// tatkal tickets are not refunded on cancellation.
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::MySqlPool;
+
+use crate::models::tatkal::{TatkalClassQuote, TatkalFare, TatkalWindow};
+use crate::services::availability::segment_base_fare;
+use crate::services::local_time::{local_date, station_offset};
+
+pub fn premium(rule: &TatkalFare, base_fare: f32) -> f32 {
+    (base_fare * rule.premium_rate).clamp(rule.min_premium, rule.max_premium)
+}
+
+// The window opens at `opens_at` (IST) on the local day before the train leaves
+// its origin, and closes when the chart is prepared (or is due, `chart_lead_hours`
+// before departure).
+pub fn booking_window(
+    rule: &TatkalFare,
+    origin_departure: DateTime<Utc>,
+    chart_prepared_at: Option<DateTime<Utc>>,
+    chart_lead_hours: i64,
+) -> TatkalWindow {
+    let opens_at = (local_date(origin_departure) - Duration::days(1))
+        .and_time(rule.opens_at)
+        .and_local_timezone(station_offset())
+        .unwrap()
+        .with_timezone(&Utc);
+    let chart_due = origin_departure - Duration::hours(chart_lead_hours);
+    let closes_at = chart_prepared_at.map_or(chart_due, |prepared| prepared.min(chart_due));
+
+    TatkalWindow { opens_at, closes_at }
+}
+
+pub async fn fetch_tatkal_fare(pool: &MySqlPool, reservation_category: &str) -> Result<Option<TatkalFare>, sqlx::Error> {
+    sqlx::query_as::<_, TatkalFare>(
+        r#"
+        SELECT reservation_category, premium_rate, min_premium, max_premium, opens_at
+        FROM tatkal_fare
+        WHERE reservation_category = ?
+        "#,
+    )
+    .bind(reservation_category)
+    .fetch_optional(pool)
+    .await
+}
+
+// Departure from the journey's first stop (falling back to the journey start) and
+// when its chart was prepared. None if the journey does not exist.
+pub async fn fetch_origin_departure(
+    pool: &MySqlPool,
+    journey_id: i64,
+) -> Result<Option<(DateTime<Utc>, Option<DateTime<Utc>>)>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(
+        r#"
+        SELECT
+            COALESCE(
+                (SELECT s.sched_tod FROM schedule s WHERE s.journey_id = j.journey_id ORDER BY s.stop_number LIMIT 1),
+                j.start_time
+            ),
+            j.chart_prepared_at
+        FROM journey j
+        WHERE j.journey_id = ?
+        "#,
+    )
+    .bind(journey_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|(departure, chart)| departure.map(|d| (d, chart))))
+}
+
+// Tatkal fare and window for one class of a journey, priced for the segment from
+// `from` to `to` (the whole journey when None). None if the journey does not run
+// that class between those stations or tatkal is not offered for it.
+pub async fn tatkal_quote(
+    pool: &MySqlPool,
+    journey_id: i64,
+    reservation_category: &str,
+    segment: (Option<i64>, Option<i64>),
+    chart_lead_hours: i64,
+) -> Result<Option<TatkalClassQuote>, sqlx::Error> {
+    let Some(rule) = fetch_tatkal_fare(pool, reservation_category).await? else {
+        return Ok(None);
+    };
+    let Some((origin_departure, chart_prepared_at)) = fetch_origin_departure(pool, journey_id).await? else {
+        return Ok(None);
+    };
+    let Some(base_fare) = segment_base_fare(pool, journey_id, reservation_category, segment.0, segment.1).await? else {
+        return Ok(None);
+    };
+
+    let premium = premium(&rule, base_fare);
+    Ok(Some(TatkalClassQuote {
+        reservation_category: rule.reservation_category.clone(),
+        base_fare,
+        premium,
+        fare: base_fare + premium,
+        window: booking_window(&rule, origin_departure, chart_prepared_at, chart_lead_hours),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, TimeZone};
+
+    fn rule() -> TatkalFare {
+        TatkalFare {
+            reservation_category: "SL".to_string(),
+            premium_rate: 0.3,
+            min_premium: 100.0,
+            max_premium: 400.0,
+            opens_at: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn premium_is_a_share_of_the_base_fare_within_bounds() {
+        assert_eq!(premium(&rule(), 1000.0), 300.0);
+        assert_eq!(premium(&rule(), 200.0), 100.0);
+        assert_eq!(premium(&rule(), 2000.0), 400.0);
+    }
+
+    #[test]
+    fn window_opens_the_local_day_before_departure() {
+        // 09:30 IST on 10 May
+        let departure = Utc.with_ymd_and_hms(2025, 5, 10, 4, 0, 0).unwrap();
+        let window = booking_window(&rule(), departure, None, 4);
+
+        assert_eq!(window.opens_at, Utc.with_ymd_and_hms(2025, 5, 9, 4, 30, 0).unwrap());
+        assert_eq!(window.closes_at, Utc.with_ymd_and_hms(2025, 5, 10, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn window_uses_the_local_date_not_the_utc_date() {
+        // 01:00 IST on 11 May is still 10 May in UTC
+        let departure = Utc.with_ymd_and_hms(2025, 5, 10, 19, 30, 0).unwrap();
+        let window = booking_window(&rule(), departure, None, 4);
+
+        assert_eq!(window.opens_at, Utc.with_ymd_and_hms(2025, 5, 10, 4, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn window_closes_early_when_the_chart_is_prepared() {
+        let departure = Utc.with_ymd_and_hms(2025, 5, 10, 4, 0, 0).unwrap();
+        let prepared = Utc.with_ymd_and_hms(2025, 5, 9, 23, 0, 0).unwrap();
+        assert_eq!(booking_window(&rule(), departure, Some(prepared), 4).closes_at, prepared);
+
+        let late = Utc.with_ymd_and_hms(2025, 5, 10, 1, 0, 0).unwrap();
+        assert_eq!(
+            booking_window(&rule(), departure, Some(late), 4).closes_at,
+            Utc.with_ymd_and_hms(2025, 5, 10, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// services/travellers.rs
+//
+// Saved travellers: validation, and filling in booking passengers that refer to
+// a traveller by id. Age comes from date of birth on the boarding date.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
+
+use crate::models::traveller::{SaveTraveller, Traveller};
+use crate::services::local_time::local_date;
+use crate::services::occupancy::push_id_list;
+
+pub const ID_TYPES: &[&str] = &["AADHAAR", "PASSPORT", "PAN", "VOTER_ID", "DRIVING_LICENCE"];
+
+fn valid_id_number(id_type: &str, number: &str) -> bool {
+    let chars: Vec<char> = number.chars().collect();
+    match id_type {
+        "AADHAAR" => chars.len() == 12 && chars.iter().all(char::is_ascii_digit),
+        "PAN" => {
+            chars.len() == 10
+                && chars[..5].iter().all(char::is_ascii_uppercase)
+                && chars[5..9].iter().all(char::is_ascii_digit)
+                && chars[9].is_ascii_uppercase()
+        }
+        "PASSPORT" => chars.len() == 8 && chars[0].is_ascii_uppercase() && chars[1..].iter().all(char::is_ascii_digit),
+        _ => (4..=20).contains(&chars.len()) && chars.iter().all(|c| c.is_ascii_alphanumeric() || *c == '-'),
+    }
+}
+
+// Normalises the request in place and checks it
+pub fn validate(traveller: &mut SaveTraveller) -> Result<(), &'static str> {
+    traveller.name = traveller.name.trim().to_string();
+    traveller.sex = traveller.sex.trim().to_uppercase();
+    traveller.id_type = traveller.id_type.as_ref().map(|t| t.trim().to_uppercase());
+    traveller.id_number = traveller.id_number.as_ref().map(|n| n.trim().to_uppercase().replace(' ', ""));
+
+    if traveller.name.is_empty() {
+        return Err("name is required");
+    }
+    if !matches!(traveller.sex.as_str(), "M" | "F" | "O") {
+        return Err("sex must be M, F or O");
+    }
+    if traveller.date_of_birth > Utc::now().date_naive() {
+        return Err("date_of_birth cannot be in the future");
+    }
+    match (&traveller.id_type, &traveller.id_number) {
+        (None, None) => Ok(()),
+        (Some(id_type), Some(number)) => {
+            if !ID_TYPES.contains(&id_type.as_str()) {
+                Err("Unknown id_type")
+            } else if !valid_id_number(id_type, number) {
+                Err("id_number is not valid for id_type")
+            } else {
+                Ok(())
+            }
+        }
+        _ => Err("id_type and id_number must be given together"),
+    }
+}
+
+// Completed years of age on `date`
+pub fn age_on(date_of_birth: NaiveDate, date: NaiveDate) -> i32 {
+    let mut age = date.year() - date_of_birth.year();
+    if (date.month(), date.day()) < (date_of_birth.month(), date_of_birth.day()) {
+        age -= 1;
+    }
+    age.max(0)
+}
+
+// Local date the passenger boards at `station_id`, falling back to the journey's start
+async fn boarding_date(pool: &MySqlPool, journey_id: i64, station_id: i64) -> Result<Option<NaiveDate>, sqlx::Error> {
+    let departure = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        r#"
+        SELECT COALESCE(
+            (SELECT s.sched_tod FROM schedule s WHERE s.journey_id = j.journey_id AND s.station_id = ?),
+            j.start_time
+        )
+        FROM journey j
+        WHERE j.journey_id = ?
+        "#,
+    )
+    .bind(station_id)
+    .bind(journey_id)
+    .fetch_optional(pool)
+    .await?
+    .flatten();
+
+    Ok(departure.map(local_date))
+}
+
+// Replaces passengers given as {"traveller_id": .., "fare": ..} with the saved
+// traveller's details. Returns each passenger's traveller id, or why the list
+// cannot be booked.
+pub async fn resolve_passengers(
+    pool: &MySqlPool,
+    email: &str,
+    journey_id: i64,
+    start_station_id: i64,
+    passenger_data: &mut Value,
+) -> Result<Result<Vec<Option<i64>>, String>, sqlx::Error> {
+    let Some(passengers) = passenger_data.as_array_mut() else {
+        return Ok(Ok(Vec::new()));
+    };
+    let ids: Vec<Option<i64>> = passengers
+        .iter()
+        .map(|p| p.get("traveller_id").and_then(Value::as_i64))
+        .collect();
+    let wanted: Vec<i64> = ids.iter().flatten().copied().collect();
+    if wanted.is_empty() {
+        return Ok(Ok(ids));
+    }
+
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+        r#"
+        SELECT traveller_id, email, name, date_of_birth, sex, disability, id_type, id_number
+        FROM traveller
+        WHERE email = "#,
+    );
+    query.push_bind(email);
+    query.push(" AND traveller_id IN (");
+    push_id_list(&mut query, &wanted);
+    let travellers: HashMap<i64, Traveller> = query
+        .build_query_as::<Traveller>()
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|t| (t.traveller_id, t))
+        .collect();
+
+    let Some(travel_date) = boarding_date(pool, journey_id, start_station_id).await? else {
+        return Ok(Err("Journey not found".to_string()));
+    };
+
+    for (passenger, id) in passengers.iter_mut().zip(&ids) {
+        let Some(id) = id else { continue };
+        let Some(traveller) = travellers.get(id) else {
+            return Ok(Err(format!("Saved traveller {} not found", id)));
+        };
+        passenger["name"] = Value::from(traveller.name.clone());
+        passenger["age"] = Value::from(age_on(traveller.date_of_birth, travel_date));
+        passenger["sex"] = Value::from(traveller.sex.clone());
+        passenger["disability"] = Value::from(traveller.disability);
+    }
+
+    Ok(Ok(ids))
+}
+
+// Links the PNRs just created for `txn_id` to the travellers they were booked for.
+// PNRs are issued in passenger order, so the n-th PNR is the n-th passenger.
+pub async fn link_passengers(
+    tx: &mut Transaction<'_, MySql>,
+    email: &str,
+    txn_id: i64,
+    traveller_ids: &[Option<i64>],
+) -> Result<(), sqlx::Error> {
+    if traveller_ids.iter().all(Option::is_none) {
+        return Ok(());
+    }
+
+    let pnrs = sqlx::query_scalar::<_, i64>("SELECT pnr FROM booking WHERE txn_id = ? ORDER BY pnr")
+        .bind(txn_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+    for (pnr, traveller_id) in pnrs.into_iter().zip(traveller_ids) {
+        if let Some(traveller_id) = traveller_id {
+            // Only the account's own travellers are linked
+            sqlx::query(
+                r#"
+                UPDATE passenger p
+                JOIN traveller t ON t.traveller_id = ? AND t.email = ?
+                SET p.traveller_id = t.traveller_id
+                WHERE p.pnr = ?
+                "#,
+            )
+            .bind(traveller_id)
+            .bind(email)
+            .bind(pnr)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn age_counts_completed_years() {
+        assert_eq!(age_on(date(2000, 5, 20), date(2025, 5, 19)), 24);
+        assert_eq!(age_on(date(2000, 5, 20), date(2025, 5, 20)), 25);
+    }
+
+    #[test]
+    fn leap_day_birthdays_turn_over_on_the_first_of_march() {
+        assert_eq!(age_on(date(2004, 2, 29), date(2025, 2, 28)), 20);
+        assert_eq!(age_on(date(2004, 2, 29), date(2025, 3, 1)), 21);
+    }
+
+    #[test]
+    fn age_is_never_negative() {
+        assert_eq!(age_on(date(2030, 1, 1), date(2025, 1, 1)), 0);
+    }
+}
@@ -88,18 +88,8 @@ class BookingDetail {
 
 class CancelBookingRequest {
   final int bookingId;
-  final double refundAmount;
-  final int txnId;
 
-  CancelBookingRequest({
-    required this.bookingId,
-    required this.refundAmount,
-    required this.txnId,
-  });
+  CancelBookingRequest({required this.bookingId});
 
-  Map<String, dynamic> toJson() => {
-        'booking_id': bookingId,
-        'refund_amount': refundAmount,
-        'txn_id': txnId,
-      };
+  Map<String, dynamic> toJson() => {'booking_id': bookingId};
 }
@@ -94,4 +94,33 @@ class CoachFare {
       'fare': fare,
     };
   }
+}
+
+class FareQuote {
+  final int quoteId;
+  final String reservationCategory;
+  final double baseFare;
+  final double multiplier;
+  final double fare;
+  final DateTime expiresAt;
+
+  FareQuote({
+    required this.quoteId,
+    required this.reservationCategory,
+    required this.baseFare,
+    required this.multiplier,
+    required this.fare,
+    required this.expiresAt,
+  });
+
+  factory FareQuote.fromJson(Map<String, dynamic> json) {
+    return FareQuote(
+      quoteId: json['quote_id'] as int,
+      reservationCategory: json['reservation_category'] as String,
+      baseFare: (json['base_fare'] as num).toDouble(),
+      multiplier: (json['multiplier'] as num).toDouble(),
+      fare: (json['fare'] as num).toDouble(),
+      expiresAt: DateTime.parse(json['expires_at']),
+    );
+  }
 }
\ No newline at end of file
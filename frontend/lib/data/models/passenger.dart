@@ -1,3 +1,19 @@
+// A passenger's share of a fare; the server applies the same concessions to flexi fares
+double concessionFare(double fare, {required int age, required String sex, required bool isDisabled}) {
+  if (age < 12) {
+    fare *= 0.5; // 50% discount for children under 12
+  } else if (age > 60) {
+    fare *= 0.7;  // 30% discount for senior citizens
+  }
+  if (sex == "Female") {
+    fare *= 0.9;  // 10% discount for women
+  }
+  if (isDisabled) {
+    fare *= 0.8; // 20% discount for disabled passengers
+  }
+  return fare;
+}
+
 class CreatePassenger {
   final String name;
   final int age;
@@ -49,6 +65,7 @@ class GroupBookingRequest {
   final int txnId;
   final String email;
   final String reservationCategory;
+  final int? quoteId;
 
   GroupBookingRequest({
     required this.groupSize,
@@ -61,6 +78,7 @@ class GroupBookingRequest {
     required this.txnId,
     required this.email,
     required this.reservationCategory,
+    this.quoteId,
   });
 
   Map<String, dynamic> toJson() => {
@@ -74,6 +92,7 @@ class GroupBookingRequest {
         'txn_id': txnId,
         'email': email,
         'reservation_category': reservationCategory,
+        if (quoteId != null) 'quote_id': quoteId,
       };
 }
 
@@ -108,6 +108,7 @@ class JourneyBetweenStations {
   final int journeyId;
   final int? trainId;
   final String? trainName;
+  final String? trainType;
   final int? startStationId;
   final int? startScheduleId;
   final String? startStation;
@@ -119,11 +120,16 @@ class JourneyBetweenStations {
   final int? startStopNumber;
   final int? endStopNumber;
   final int? travelTime;
+  final String? travelDate;
+  final int? boardingDay;
+  final int? alightingDay;
+  final List<JourneyClass> classes;
 
   JourneyBetweenStations({
     required this.journeyId,
     this.trainId,
     this.trainName,
+    this.trainType,
     this.startStationId,
     this.startScheduleId,
     this.startStation,
@@ -135,6 +141,10 @@ class JourneyBetweenStations {
     this.startStopNumber,
     this.endStopNumber,
     this.travelTime,
+    this.travelDate,
+    this.boardingDay,
+    this.alightingDay,
+    this.classes = const [],
   });
 
   factory JourneyBetweenStations.fromJson(Map<String, dynamic> json) {
@@ -142,6 +152,7 @@ class JourneyBetweenStations {
       journeyId: json['journey_id'],
       trainId: json['train_id'],
       trainName: json['train_name'],
+      trainType: json['train_type'],
       startStationId: json['start_station_id'],
       startScheduleId: json['start_schedule_id'],
       startStation: json['start_station'],
@@ -153,6 +164,41 @@ class JourneyBetweenStations {
       startStopNumber: json['start_stop_number'],
       endStopNumber: json['end_stop_number'],
       travelTime: json['travel_time'],
+      travelDate: json['travel_date'],
+      boardingDay: json['boarding_day'],
+      alightingDay: json['alighting_day'],
+      classes: (json['classes'] as List<dynamic>? ?? [])
+          .map((e) => JourneyClass.fromJson(e))
+          .toList(),
+    );
+  }
+}
+
+class JourneyClass {
+  final String reservationCategory;
+  final int cnfAvailable;
+  final int racAvailable;
+  final int waitlist;
+  final String status;
+  final double? fare;
+
+  JourneyClass({
+    required this.reservationCategory,
+    required this.cnfAvailable,
+    required this.racAvailable,
+    required this.waitlist,
+    required this.status,
+    this.fare,
+  });
+
+  factory JourneyClass.fromJson(Map<String, dynamic> json) {
+    return JourneyClass(
+      reservationCategory: json['reservation_category'] as String,
+      cnfAvailable: json['cnf_available'] as int,
+      racAvailable: json['rac_available'] as int,
+      waitlist: json['waitlist'] as int,
+      status: json['status'] as String,
+      fare: (json['fare'] as num?)?.toDouble(),
     );
   }
 }
\ No newline at end of file
@@ -89,20 +89,37 @@ class CreateSeat {
   }
 }
 
-class SeatCount {
+class ClassAvailability {
   final String reservationCategory;
-  final int seatCount;
+  final int capacity;
+  final int cnfSeats;
+  final int racBerths;
+  final int cnfAvailable;
+  final int racAvailable;
+  final int waitlist;
+  final String status;
 
-  SeatCount({
+  ClassAvailability({
     required this.reservationCategory,
-    required this.seatCount,
+    required this.capacity,
+    required this.cnfSeats,
+    required this.racBerths,
+    required this.cnfAvailable,
+    required this.racAvailable,
+    required this.waitlist,
+    required this.status,
   });
 
-  factory SeatCount.fromJson(Map<String, dynamic> json) {
-    return SeatCount(
+  factory ClassAvailability.fromJson(Map<String, dynamic> json) {
+    return ClassAvailability(
       reservationCategory: json['reservation_category'] as String,
-      seatCount: json['seat_count'] as int,
+      capacity: json['capacity'] as int,
+      cnfSeats: json['cnf_seats'] as int,
+      racBerths: json['rac_berths'] as int,
+      cnfAvailable: json['cnf_available'] as int,
+      racAvailable: json['rac_available'] as int,
+      waitlist: json['waitlist'] as int,
+      status: json['status'] as String,
     );
   }
 }
-
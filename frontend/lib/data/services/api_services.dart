@@ -59,6 +59,14 @@ class PaginatedResponse<T> {
 class ApiService {
   static const String baseUrl = 'http://localhost:8080/api';
 
+  // Session token from the last successful login
+  static String? _token;
+
+  static Map<String, String> get _authHeaders => {
+        'Content-Type': 'application/json',
+        if (_token != null) 'Authorization': 'Bearer $_token',
+      };
+
   static Future<ApiResponse<User>> login(String email, String password) async {
     try {
       final response = await http.post(
@@ -68,7 +76,9 @@ class ApiService {
       );
 
       if (response.statusCode == 200) {
-        final user = User.fromJson(jsonDecode(response.body));
+        final json = jsonDecode(response.body);
+        _token = json['token'];
+        final user = User.fromJson(json);
         return ApiResponse(data: user, statusCode: response.statusCode);
       } else {
         return ApiResponse(
@@ -884,21 +894,27 @@ class ApiService {
     }
   }
 
-  static Future<ApiResponse<List<SeatCount>>> getReservedSeatCount({
+  static Future<ApiResponse<List<ClassAvailability>>> getAvailability({
     required int journeyId,
-    required String type,
+    int? fromStationId,
+    int? toStationId,
   }) async {
     try {
-      final uri = Uri.parse('$baseUrl/booking/seat/$type/$journeyId');
+      final uri = Uri.parse('$baseUrl/booking/availability/$journeyId').replace(
+        queryParameters: {
+          if (fromStationId != null) 'from': fromStationId.toString(),
+          if (toStationId != null) 'to': toStationId.toString(),
+        },
+      );
       final response = await http.get(uri);
 
       if (response.statusCode == 200) {
-        final List<dynamic> jsonList = jsonDecode(response.body);
-        final data = jsonList.map((e) => SeatCount.fromJson(e)).toList();
+        final List<dynamic> jsonList = jsonDecode(response.body)['data'];
+        final data = jsonList.map((e) => ClassAvailability.fromJson(e)).toList();
         return ApiResponse(data: data, statusCode: 200);
       } else {
         return ApiResponse(
-          error: jsonDecode(response.body)['error'] ?? "Failed to fetch seat count",
+          error: jsonDecode(response.body)['error'] ?? "Failed to fetch availability",
           statusCode: response.statusCode,
         );
       }
@@ -907,21 +923,20 @@ class ApiService {
     }
   }
 
-  static Future<ApiResponse<List<SeatCount>>> getTotalSeatCount({
+  static Future<ApiResponse<List<CoachFare>>> getCoachFare({
     required int journeyId,
-    required String type,
   }) async {
     try {
-      final uri = Uri.parse('$baseUrl/seats/total/$type/$journeyId');
+      final uri = Uri.parse('$baseUrl/journeys/fare/$journeyId');
       final response = await http.get(uri);
 
       if (response.statusCode == 200) {
         final List<dynamic> jsonList = jsonDecode(response.body);
-        final data = jsonList.map((e) => SeatCount.fromJson(e)).toList();
+        final data = jsonList.map((e) => CoachFare.fromJson(e)).toList();
         return ApiResponse(data: data, statusCode: 200);
       } else {
         return ApiResponse(
-          error: jsonDecode(response.body)['error'] ?? "Failed to fetch seat count",
+          error: jsonDecode(response.body)['error'] ?? "Failed to fetch fare",
           statusCode: response.statusCode,
         );
       }
@@ -930,20 +945,57 @@ class ApiService {
     }
   }
 
-  static Future<ApiResponse<List<CoachFare>>> getCoachFare({
-    required int journeyId,
+  // Whether a class of a train type is flexi priced; class-specific steps replace the defaults
+  static Future<ApiResponse<bool>> hasFlexiFare({
+    required String trainType,
+    required String reservationCategory,
   }) async {
     try {
-      final uri = Uri.parse('$baseUrl/journeys/fare/$journeyId');
+      final uri = Uri.parse('$baseUrl/booking/flexi/$trainType');
       final response = await http.get(uri);
 
       if (response.statusCode == 200) {
-        final List<dynamic> jsonList = jsonDecode(response.body);
-        final data = jsonList.map((e) => CoachFare.fromJson(e)).toList();
-        return ApiResponse(data: data, statusCode: 200);
+        final List<dynamic> steps = jsonDecode(response.body)['data'];
+        final hasSteps = steps.any((s) =>
+            s['reservation_category'] == reservationCategory || s['reservation_category'] == null);
+        return ApiResponse(data: hasSteps, statusCode: 200);
       } else {
         return ApiResponse(
-          error: jsonDecode(response.body)['error'] ?? "Failed to fetch fare",
+          error: jsonDecode(response.body)['error'] ?? "Failed to fetch flexi fares",
+          statusCode: response.statusCode,
+        );
+      }
+    } catch (e) {
+      return ApiResponse(error: e.toString());
+    }
+  }
+
+  // Locks the current fare of a class for the checkout
+  static Future<ApiResponse<FareQuote>> createFareQuote({
+    required int journeyId,
+    required String reservationCategory,
+    int? startStationId,
+    int? endStationId,
+  }) async {
+    try {
+      final uri = Uri.parse('$baseUrl/booking/quote');
+      final response = await http.post(
+        uri,
+        headers: {'Content-Type': 'application/json'},
+        body: jsonEncode({
+          'journey_id': journeyId,
+          'reservation_category': reservationCategory,
+          'start_station_id': startStationId,
+          'end_station_id': endStationId,
+        }),
+      );
+
+      if (response.statusCode == 201) {
+        final data = FareQuote.fromJson(jsonDecode(response.body));
+        return ApiResponse(data: data, statusCode: 201);
+      } else {
+        return ApiResponse(
+          error: jsonDecode(response.body)['error'] ?? "Failed to fetch fare quote",
           statusCode: response.statusCode,
         );
       }
@@ -1032,7 +1084,7 @@ class ApiService {
       final uri = Uri.parse('$baseUrl/booking/book');
       final response = await http.post(
         uri,
-        headers: {'Content-Type': 'application/json'},
+        headers: _authHeaders,
         body: jsonEncode(request.toJson()),
       );
 
@@ -1054,7 +1106,7 @@ class ApiService {
   static Future<ApiResponse<List<BookingDetail>>> getBookingDetails(String email) async {
     try {
       final uri = Uri.parse('$baseUrl/booking/details?email=$email');
-      final response = await http.get(uri);
+      final response = await http.get(uri, headers: _authHeaders);
 
       if (response.statusCode == 200) {
         final jsonList = jsonDecode(response.body) as List;
@@ -1076,7 +1128,7 @@ class ApiService {
       final uri = Uri.parse('$baseUrl/booking/cancel');
       final response = await http.post(
         uri,
-        headers: {'Content-Type': 'application/json'},
+        headers: _authHeaders,
         body: jsonEncode(request.toJson()),
       );
 
@@ -75,31 +75,25 @@ class _BookingCategoryScreenState extends State<BookingCategoryScreen> {
         'wl': {},
       };
 
-      for (var type in types) {
-        final bookedRes = await ApiService.getReservedSeatCount(
-          journeyId: widget.journey.journeyId,
-          type: type,
-        );
-
-        for (final category in bookedRes.data ?? []) {
-          seatMap[type]![category.reservationCategory] = {
-            'booked': category.seatCount,
-          };
-        }
-
-        if (type != 'wl') {
-          final totalRes = await ApiService.getTotalSeatCount(
-            journeyId: widget.journey.journeyId,
-            type: type,
-          );
-
-          for (final category in totalRes.data ?? []) {
-            seatMap[type]![category.reservationCategory] = {
-              ...?seatMap[type]![category.reservationCategory],
-              'total': category.seatCount,
-            };
-          }
-        }
+      // Availability for the searched segment, not the whole run
+      final res = await ApiService.getAvailability(
+        journeyId: widget.journey.journeyId,
+        fromStationId: widget.journey.startStationId,
+        toStationId: widget.journey.endStationId,
+      );
+
+      for (final cls in res.data ?? []) {
+        seatMap['cnf']![cls.reservationCategory] = {
+          'booked': cls.cnfSeats - cls.cnfAvailable,
+          'total': cls.cnfSeats,
+        };
+        seatMap['rac']![cls.reservationCategory] = {
+          'booked': cls.racBerths * 2 - cls.racAvailable,
+          'total': cls.racBerths * 2,
+        };
+        seatMap['wl']![cls.reservationCategory] = {
+          'booked': cls.waitlist,
+        };
       }
 
       setState(() {
@@ -133,15 +133,9 @@ class _BookingHistoryScreenState extends State<BookingHistoryScreen> {
       builder: (_) => const Center(child: CircularProgressIndicator()),
     );
 
-    // Sample refund logic — adjust as needed
-    double refundAmount = booking.amount * 0.8;
-
+    // The refund is worked out by the server
     final response = await ApiService.cancelBooking(
-      CancelBookingRequest(
-        bookingId: booking.bookingId,
-        refundAmount: refundAmount,
-        txnId: booking.txnId!,
-      ),
+      CancelBookingRequest(bookingId: booking.bookingId),
     );
 
     // Close the loading dialog
@@ -1,4 +1,5 @@
 import 'package:flutter/material.dart';
+import 'package:frontend/data/models/journey.dart';
 import 'package:frontend/data/models/passenger.dart';
 import 'package:frontend/data/models/schedule.dart';
 import 'package:frontend/data/models/transaction.dart';
@@ -29,6 +30,64 @@ class BookingPaymentScreen extends StatefulWidget {
 
 class _BookingPaymentScreenState extends State<BookingPaymentScreen> {
   bool _isProcessing = false;
+  bool _isFlexi = false;
+  FareQuote? _quote;
+
+  @override
+  void initState() {
+    super.initState();
+    _checkFlexi();
+  }
+
+  // Only flexi-priced classes are sold at a locked quote
+  Future<void> _checkFlexi() async {
+    final trainType = widget.journey.trainType;
+    if (trainType == null) return;
+    final flexiResponse = await ApiService.hasFlexiFare(
+      trainType: trainType,
+      reservationCategory: widget.category!,
+    );
+    if (flexiResponse.data == true) {
+      if (mounted) {
+        setState(() {
+          _isFlexi = true;
+        });
+      }
+      await _lockFare();
+    }
+  }
+
+  // Lock the fare so the amount shown is the amount charged
+  Future<FareQuote?> _lockFare() async {
+    final quoteResponse = await ApiService.createFareQuote(
+      journeyId: widget.journey.journeyId,
+      reservationCategory: widget.category!,
+      startStationId: widget.journey.startStationId,
+      endStationId: widget.journey.endStationId,
+    );
+    if (mounted && quoteResponse.data != null) {
+      setState(() {
+        _quote = quoteResponse.data;
+      });
+    }
+    return quoteResponse.data;
+  }
+
+  // Under a quote each passenger pays the quoted fare less their concession
+  double _passengerFare(CreatePassenger passenger, FareQuote? quote) {
+    if (quote == null) return passenger.fare ?? 0;
+    return concessionFare(
+      quote.fare,
+      age: passenger.age,
+      sex: passenger.gender,
+      isDisabled: passenger.isDisabled,
+    ).roundToDouble();
+  }
+
+  double _fareTotal(FareQuote? quote) =>
+      widget.passengers.fold(0.0, (total, p) => total + _passengerFare(p, quote));
+
+  double get _totalFare => _fareTotal(_quote);
 
   // Process the payment transaction
   void _processTransaction() async {
@@ -36,8 +95,22 @@ class _BookingPaymentScreenState extends State<BookingPaymentScreen> {
       _isProcessing = true;
     });
 
+    // A quote that lapsed while the screen was open is replaced before paying
+    FareQuote? quote;
+    if (_isFlexi) {
+      final current = _quote;
+      quote = current == null || current.expiresAt.isBefore(DateTime.now()) ? await _lockFare() : current;
+      if (quote == null) {
+        setState(() {
+          _isProcessing = false;
+        });
+        _showErrorDialog('Could not fetch the current fare');
+        return;
+      }
+    }
+
     final paymentTransaction = CreateTransaction(
-      totalAmount: widget.totalFare,
+      totalAmount: _fareTotal(quote),
       txnStatus: 'PENDING',
       paymentMode: 'UPI',
     );
@@ -56,7 +129,7 @@ class _BookingPaymentScreenState extends State<BookingPaymentScreen> {
             age: p.age,
             sex: p.gender,
             disability: p.isDisabled,
-            fare: p.fare!,
+            fare: _passengerFare(p, quote),
           );
         }).toList(),
         journeyId: widget.journey.journeyId,
@@ -67,6 +140,7 @@ class _BookingPaymentScreenState extends State<BookingPaymentScreen> {
         txnId: txnId,
         email: widget.user.email,
         reservationCategory: widget.category!,
+        quoteId: quote?.quoteId,
       );
 
       // Make group booking API call
@@ -206,7 +280,7 @@ class _BookingPaymentScreenState extends State<BookingPaymentScreen> {
                     ),
                     SizedBox(height: 10),
                     Text(
-                      "Total Fare: ₹${widget.totalFare}",
+                      "Total Fare: ₹$_totalFare",
                       style: TextStyle(
                         color: Colors.white,
                         fontSize: 18,
@@ -171,19 +171,7 @@ class _BookingUserDetailsScreenState extends State<BookingUserDetailsScreen> {
     //     SET p_individual_amount = p_base_amount;
 
   double calculateFare({int age = 0, required String sex, required bool isDisabled}) {
-    double fare = widget.fare;
-    if (age < 12) {
-      fare *= 0.5; // 50% discount for children under 12
-    } else if (age > 60) {
-      fare *= 0.7;  // 30% discount for senior citizens
-    }
-    if (sex == "Female") {
-      fare *= 0.9;  // 10% discount for women
-    }
-    if (isDisabled) {
-      fare *= 0.8; // 20% discount for disabled passengers
-    }
-    return fare;
+    return concessionFare(widget.fare, age: age, sex: sex, isDisabled: isDisabled);
   }
 
   Widget _infoRow(String label, String value) {